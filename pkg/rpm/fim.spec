@@ -41,6 +41,7 @@ install -m 0750 target/release/fim ${RPM_BUILD_ROOT}%{_bindir}/
 install -m 0640 config/linux/config.yml ${RPM_BUILD_ROOT}%{_configdir}/
 install -m 0640 config/linux/rules.yml ${RPM_BUILD_ROOT}%{_configdir}/
 install -m 0640 config/index_template.json ${RPM_BUILD_ROOT}%{_configdir}/
+install -m 0640 config/index_template_ecs.json ${RPM_BUILD_ROOT}%{_configdir}/
 install -m 0644 pkg/fim.service ${RPM_BUILD_ROOT}/lib/systemd/system/
 install -m 0644 pkg/fim.1 ${RPM_BUILD_ROOT}/usr/share/man/man1/
 
@@ -95,6 +96,7 @@ rm -fr %{buildroot}
 %attr(640, root, root) %config(noreplace) %{_configdir}/config.yml
 %attr(640, root, root) %config(noreplace) %{_configdir}/rules.yml
 %attr(640, root, root) %{_configdir}/index_template.json
+%attr(640, root, root) %{_configdir}/index_template_ecs.json
 %attr(644, root, root) /lib/systemd/system/fim.service
 %attr(644, root, root) /usr/share/man/man1/fim.1.gz
 
@@ -5,22 +5,39 @@ pub const VERSION: &str = "0.6.4";
 pub const NETWORK_MODE: &str = "NETWORK";
 pub const FILE_MODE: &str = "FILE";
 pub const BOTH_MODE: &str = "BOTH";
+pub const SYSLOG_MODE: &str = "SYSLOG";
+pub const WEBHOOK_MODE: &str = "WEBHOOK";
 pub const MACHINE_ID_PATH: &str = "/etc/machine-id";
+// Longest coalescing window of monitor events, in milliseconds
+pub const MAX_DEBOUNCE: u64 = 60000;
+const DESTINATIONS: [&str; 4] = ["file", "network", "both", "syslog"];
+const SIZE_UNITS: [&str; 5] = ["b", "kb", "mb", "gb", "tb"];
+const TIME_UNITS: [&str; 4] = ["d", "h", "m", "s"];
+const WEBHOOK_METHODS: [&str; 3] = ["POST", "PUT", "PATCH"];
 const CONFIG_MACOS_PATH: &str = "/Applications/FileMonitor.app/config.yml";
 const CONFIG_LINUX_PATH: &str = "/etc/fim/config.yml";
 const CONFIG_WINDOWS_PATH: &str = "C:\\Program Files\\File Integrity Monitor\\config.yml";
 
 // Required dependencies
-use yaml_rust::yaml::{Yaml, YamlLoader, Array};
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::fs::OpenOptions;
+use std::io::Write;
 use std::path::Path;
 use simplelog::LevelFilter;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
+use reqwest::Client;
 
 use crate::utils;
 use crate::integration::Integration;
 use crate::hash::ShaType;
+use crate::configfile::*;
+use crate::ruleset;
+use crate::matcher;
+use crate::output;
+use crate::output::{Batch, Router};
+use crate::spool;
+use crate::syslog;
+use crate::formatter;
+use crate::tls;
 
 // ----------------------------------------------------------------------------
 
@@ -29,8 +46,12 @@ pub struct AppConfig {
     pub version: String,
     pub path: String,
     pub events_watcher: String,
+    pub poll_interval: u64,
+    pub poll_compare_contents: bool,
+    pub events_watch_limit: String,
     pub events_destination: String,
     pub events_max_file_checksum: usize,
+    pub events_debounce: u64,
     pub events_max_file_size: usize,
     pub checksum_algorithm: ShaType,
     pub endpoint_type: String,
@@ -38,22 +59,67 @@ pub struct AppConfig {
     pub endpoint_user: String,
     pub endpoint_pass: String,
     pub endpoint_token: String,
+    pub splunk_index: String,
+    pub splunk_sourcetype: String,
+    pub splunk_source: String,
+    pub splunk_ack: bool,
+    pub splunk_channel: String,
+    pub splunk_ack_timeout: u64,
+    pub elastic_index: String,
+    pub elastic_data_stream: bool,
+    pub elastic_ilm: Option<IlmConfig>,
     pub events_file: String,
-    pub monitor: Array,
-    pub audit: Array,
+    pub monitor: Vec<PathConfig>,
+    pub audit: Vec<PathConfig>,
     pub node: String,
     pub log_file: String,
     pub log_level: String,
     pub log_max_file_size: usize,
     pub system: String,
     pub insecure: bool,
+    pub endpoint_ca_file: Option<String>,
+    pub endpoint_client_cert: Option<String>,
+    pub endpoint_client_key: Option<String>,
+    pub endpoint_pins: Vec<String>,
     pub events_lock: Arc<Mutex<String>>,
     pub log_lock: Arc<Mutex<String>>,
     pub hashscanner_file: String,
     pub hashscanner_enabled: bool,
     pub hashscanner_interval: usize,
     pub hashscanner_algorithm: ShaType,
-    pub engine: String
+    pub spool_file: String,
+    pub spool_max_events: usize,
+    pub spool_overflow: String,
+    pub spool_max_backoff: u64,
+    pub spool_lock: Arc<Mutex<Option<spool::Connection>>>,
+    pub batch_max_events: usize,
+    pub batch_max_bytes: usize,
+    pub batch_interval: u64,
+    pub batch_lock: Arc<Mutex<Batch>>,
+    pub client: Arc<OnceLock<Client>>,
+    pub syslog_address: String,
+    pub syslog_protocol: String,
+    pub syslog_format: String,
+    pub syslog_framing: String,
+    pub syslog_facility: u8,
+    pub syslog_severity: u8,
+    pub syslog_app_name: String,
+    pub syslog_insecure: bool,
+    pub syslog_ca_file: Option<String>,
+    pub syslog_client_cert: Option<String>,
+    pub syslog_client_key: Option<String>,
+    pub syslog_pins: Vec<String>,
+    pub syslog_lock: Arc<Mutex<Option<syslog::Connection>>>,
+    pub format_file: String,
+    pub format_network: String,
+    pub format_syslog: String,
+    pub chain_enabled: bool,
+    pub chain_key: String,
+    pub webhooks: Vec<WebhookConfig>,
+    pub router: Arc<Mutex<Option<Arc<Router>>>>,
+    pub engine: String,
+    pub rules: Vec<RuleConfig>,
+    pub includes: Vec<String>
 }
 
 impl AppConfig {
@@ -63,8 +129,12 @@ impl AppConfig {
             version: self.version.clone(),
             path: self.path.clone(),
             events_watcher: self.events_watcher.clone(),
+            poll_interval: self.poll_interval,
+            poll_compare_contents: self.poll_compare_contents,
+            events_watch_limit: self.events_watch_limit.clone(),
             events_destination: self.events_destination.clone(),
             events_max_file_checksum: self.events_max_file_checksum,
+            events_debounce: self.events_debounce,
             events_max_file_size: self.events_max_file_size,
             checksum_algorithm: self.checksum_algorithm.clone(),
             endpoint_type: self.endpoint_type.clone(),
@@ -72,6 +142,15 @@ impl AppConfig {
             endpoint_user: self.endpoint_user.clone(),
             endpoint_pass: self.endpoint_pass.clone(),
             endpoint_token: self.endpoint_token.clone(),
+            splunk_index: self.splunk_index.clone(),
+            splunk_sourcetype: self.splunk_sourcetype.clone(),
+            splunk_source: self.splunk_source.clone(),
+            splunk_ack: self.splunk_ack,
+            splunk_channel: self.splunk_channel.clone(),
+            splunk_ack_timeout: self.splunk_ack_timeout,
+            elastic_index: self.elastic_index.clone(),
+            elastic_data_stream: self.elastic_data_stream,
+            elastic_ilm: self.elastic_ilm.clone(),
             events_file: self.events_file.clone(),
             monitor: self.monitor.clone(),
             audit: self.audit.clone(),
@@ -81,155 +160,193 @@ impl AppConfig {
             log_max_file_size: self.log_max_file_size,
             system: self.system.clone(),
             insecure: self.insecure,
+            endpoint_ca_file: self.endpoint_ca_file.clone(),
+            endpoint_client_cert: self.endpoint_client_cert.clone(),
+            endpoint_client_key: self.endpoint_client_key.clone(),
+            endpoint_pins: self.endpoint_pins.clone(),
             events_lock: self.events_lock.clone(),
             log_lock: self.log_lock.clone(),
             hashscanner_file: self.hashscanner_file.clone(),
             hashscanner_enabled: self.hashscanner_enabled,
             hashscanner_interval: self.hashscanner_interval,
             hashscanner_algorithm: self.hashscanner_algorithm.clone(),
-            engine: self.engine.clone()
+            spool_file: self.spool_file.clone(),
+            spool_max_events: self.spool_max_events,
+            spool_overflow: self.spool_overflow.clone(),
+            spool_max_backoff: self.spool_max_backoff,
+            spool_lock: self.spool_lock.clone(),
+            batch_max_events: self.batch_max_events,
+            batch_max_bytes: self.batch_max_bytes,
+            batch_interval: self.batch_interval,
+            batch_lock: self.batch_lock.clone(),
+            client: self.client.clone(),
+            syslog_address: self.syslog_address.clone(),
+            syslog_protocol: self.syslog_protocol.clone(),
+            syslog_format: self.syslog_format.clone(),
+            syslog_framing: self.syslog_framing.clone(),
+            syslog_facility: self.syslog_facility,
+            syslog_severity: self.syslog_severity,
+            syslog_app_name: self.syslog_app_name.clone(),
+            syslog_insecure: self.syslog_insecure,
+            syslog_ca_file: self.syslog_ca_file.clone(),
+            syslog_client_cert: self.syslog_client_cert.clone(),
+            syslog_client_key: self.syslog_client_key.clone(),
+            syslog_pins: self.syslog_pins.clone(),
+            syslog_lock: self.syslog_lock.clone(),
+            format_file: self.format_file.clone(),
+            format_network: self.format_network.clone(),
+            format_syslog: self.format_syslog.clone(),
+            chain_enabled: self.chain_enabled,
+            chain_key: self.chain_key.clone(),
+            webhooks: self.webhooks.clone(),
+            router: self.router.clone(),
+            engine: self.engine.clone(),
+            rules: self.rules.clone(),
+            includes: self.includes.clone()
         }
     }
 
-    pub fn new(system: &str, config_path: Option<&str>) -> Self {
+    pub fn new(system: &str, config_path: Option<&str>) -> Result<Self, ConfigErrors> {
         println!("[INFO] System detected '{}'", system);
         let cfg = match config_path {
             Some(path) => String::from(path),
             None => get_config_path(system)
         };
         println!("[INFO] Loaded config from: '{}'", cfg);
-        let yaml = read_config(cfg.clone());
+        let yaml = read_config(cfg.clone())?;
+        let mut errors: Vec<ConfigError> = Vec::new();
 
         // Manage null value on events->destination value
-        let events_destination = match yaml[0]["events"]["destination"].as_str() {
-            Some(value) => String::from(value),
+        let events_destination = match yaml.events.destination.clone() {
+            Some(value) => {
+                if !DESTINATIONS.contains(&value.as_str()) {
+                    errors.push(yaml.error("events.destination",
+                        "unknown destination, expected one of file, network, both, syslog"));
+                }
+                value
+            },
             None => {
                 println!("[WARN] events->destination not found in config.yml, using 'file'.");
                 String::from("file")
             }
         };
 
+        // Destinations used by any monitor or audit path, on top of the global one
+        let mut destinations = vec![events_destination.clone()];
+        for (section, paths) in [("monitor", &yaml.monitor), ("audit", yaml.audit.as_ref().unwrap_or(&Vec::new()))] {
+            for (i, element) in paths.iter().enumerate() {
+                if let Some(value) = &element.destination {
+                    if !DESTINATIONS.contains(&value.as_str()) {
+                        errors.push(yaml.error(&format!("{}[{}].destination", section, i),
+                            "unknown destination, expected one of file, network, both, syslog"));
+                    }
+                    destinations.push(value.clone());
+                }
+            }
+        }
+        let uses_file = destinations.iter().any(|d| d == "file" || d == "both");
+        let uses_network = destinations.iter().any(|d| d == "network" || d == "both");
+        let uses_syslog = destinations.iter().any(|d| d == "syslog");
+
         // Manage value on events->watcher value
-        let events_watcher = match yaml[0]["events"]["watcher"].as_str() {
+        let events_watcher = match yaml.events.watcher.as_deref() {
             Some("poll"|"P"|"POLL"|"Poll") => String::from("Poll"),
-            _ => String::from("Recommended")
+            Some("hybrid"|"HYBRID"|"Hybrid") => String::from("Hybrid"),
+            Some("fanotify"|"FANOTIFY"|"Fanotify") => {
+                if system != "linux" {
+                    errors.push(yaml.error("events.watcher", "fanotify is only available on Linux"));
+                }
+                String::from("Fanotify")
+            },
+            None|Some("recommended"|"RECOMMENDED"|"Recommended") => String::from("Recommended"),
+            Some(_) => {
+                errors.push(yaml.error("events.watcher", "expected one of recommended, poll, hybrid or fanotify"));
+                String::from("Recommended")
+            }
+        };
+        // Polling every 30 seconds by default, as notify does
+        let poll = yaml.events.poll.clone().unwrap_or_default();
+        let poll_interval = poll.interval.unwrap_or(30);
+        if poll_interval == 0 {
+            errors.push(yaml.error("events.poll.interval", "expected an interval of at least 1 second"));
+        }
+        let poll_compare_contents = poll.compare_contents.unwrap_or(false);
+        // What to do with trees that exceed the inotify watches left
+        let events_watch_limit = match yaml.events.watch_limit.as_deref() {
+            None|Some("alert"|"ALERT"|"Alert") => String::from("Alert"),
+            Some("poll"|"POLL"|"Poll") => String::from("Poll"),
+            Some(_) => {
+                errors.push(yaml.error("events.watch_limit", "expected one of alert or poll"));
+                String::from("Alert")
+            }
         };
 
         // Manage null value on events->file value
-        let events_file = match yaml[0]["events"]["file"].as_str() {
-            Some(value) => String::from(value),
+        let events_file = match yaml.events.file.clone() {
+            Some(value) => value,
             None => {
-                if events_destination != *"network" {
-                    println!("[ERROR] events->file not found in config.yml.");
-                    panic!("events->file not found in config.yml.");
-                }else{
-                    String::from("Not_defined")
+                if uses_file {
+                    errors.push(yaml.error("events", "missing field `file`, required when destination is file or both"));
                 }
+                String::from("Not_defined")
             }
         };
 
         // Manage null value on events->max_file_checksum value
-        let events_max_file_checksum = match yaml[0]["events"]["max_file_checksum"].as_i64() {
-            Some(value) => usize::try_from(value).unwrap(),
-            None => 64
-        };
+        let events_max_file_checksum = yaml.events.max_file_checksum.unwrap_or(64);
+
+        // Coalescing window of the monitor events, disabled by default
+        let events_debounce = yaml.events.debounce.unwrap_or(0);
+        if events_debounce > MAX_DEBOUNCE {
+            errors.push(yaml.error("events.debounce", &format!("expected a window up to {} milliseconds", MAX_DEBOUNCE)));
+        }
+        for (i, element) in yaml.monitor.iter().enumerate() {
+            if element.debounce.unwrap_or(0) > MAX_DEBOUNCE {
+                errors.push(yaml.error(&format!("monitor[{}].debounce", i),
+                    &format!("expected a window up to {} milliseconds", MAX_DEBOUNCE)));
+            }
+        }
 
         // Manage null value on events->max_file_size value
-        let events_max_file_size = match yaml[0]["events"]["max_file_size"].as_i64() {
-            Some(value) => usize::try_from(value).unwrap(),
-            None => 128
-        };
+        let events_max_file_size = yaml.events.max_file_size.unwrap_or(128);
 
-        // Temporal value
-        let checksum_algorithm = match yaml[0]["events"]["checksum_algorithm"].as_str() {
-            Some(value) => {
-                match value {
-                    "sha224"|"224"|"SHA224"|"Sha224" => ShaType::Sha224,
-                    "sha256"|"256"|"SHA256"|"Sha256" => ShaType::Sha256,
-                    "sha384"|"384"|"SHA384"|"Sha384" => ShaType::Sha384,
-                    "sha512"|"512"|"SHA512"|"Sha512" => ShaType::Sha512,
-                    "keccak224"|"K224"|"KECCAK224"|"Keccak224" => ShaType::Keccak224,
-                    "keccak256"|"K256"|"KECCAK256"|"Keccak256" => ShaType::Keccak256,
-                    "keccak384"|"K384"|"KECCAK384"|"Keccak384" => ShaType::Keccak384,
-                    "keccak512"|"K512"|"KECCAK512"|"Keccak512" => ShaType::Keccak512,
-                    _ => ShaType::Sha512
-                }
-            },
-            None => ShaType::Sha256
-        };
+        let checksum_algorithm = yaml.events.checksum_algorithm.clone().unwrap_or(ShaType::Sha256);
+        let hashscanner_algorithm = yaml.hashscanner.algorithm.clone().unwrap_or(ShaType::Sha256);
 
-        let hashscanner_algorithm = match yaml[0]["hashscanner"]["algorithm"].as_str() {
-            Some(value) => {
-                match value {
-                    "sha224"|"224"|"SHA224"|"Sha224" => ShaType::Sha224,
-                    "sha256"|"256"|"SHA256"|"Sha256" => ShaType::Sha256,
-                    "sha384"|"384"|"SHA384"|"Sha384" => ShaType::Sha384,
-                    "sha512"|"512"|"SHA512"|"Sha512" => ShaType::Sha512,
-                    "keccak224"|"K224"|"KECCAK224"|"Keccak224" => ShaType::Keccak224,
-                    "keccak256"|"K256"|"KECCAK256"|"Keccak256" => ShaType::Keccak256,
-                    "keccak384"|"K384"|"KECCAK384"|"Keccak384" => ShaType::Keccak384,
-                    "keccak512"|"K512"|"KECCAK512"|"Keccak512" => ShaType::Keccak512,
-                    _ => ShaType::Sha256
-                }
-            },
-            None => ShaType::Sha256
-        };
+        let endpoint = yaml.events.endpoint.clone().unwrap_or_default();
+        let credentials = endpoint.credentials.clone().unwrap_or_default();
 
         // Manage null value on events->endpoint->insecure value
-        let insecure = match yaml[0]["events"]["endpoint"]["insecure"].as_bool() {
+        let insecure = match endpoint.insecure {
             Some(value) => value,
             None => {
-                if events_destination != *"file" {
+                if uses_network {
                     println!("[WARN] events->endpoint->insecure not found in config.yml, using 'false'.");
-                    false
-                }else{ false }
+                }
+                false
             }
         };
 
         // Manage null value on events->endpoint->address value
-        let endpoint_address = match yaml[0]["events"]["endpoint"]["address"].as_str() {
-            Some(value) => String::from(value),
+        let endpoint_address = match endpoint.address {
+            Some(value) => value,
             None => {
-                if events_destination != *"file" {
-                    println!("[ERROR] events->endpoint->address not found in config.yml.");
-                    panic!("events->endpoint->address not found in config.yml.");
-                }else{
-                    String::from("Not_defined")
+                if uses_network {
+                    errors.push(yaml.error("events.endpoint", "missing field `address`, required when destination is network or both"));
                 }
+                String::from("Not_defined")
             }
         };
 
-        // Manage null value on events->endpoint->credentials->token value
-        let endpoint_token = match yaml[0]["events"]["endpoint"]["credentials"]["token"].as_str() {
-            Some(value) => String::from(value),
-            None => String::from("Not_defined")
-        };
-
-        // Manage null value on events->endpoint->credentials->user value
-        let endpoint_user = match yaml[0]["events"]["endpoint"]["credentials"]["user"].as_str() {
-            Some(value) => String::from(value),
-            None => {
-                if events_destination != *"file" && endpoint_token.is_empty() {
-                    println!("[ERROR] events->endpoint->credentials->user not found in config.yml.");
-                    panic!("events->endpoint->credentials->user not found in config.yml.");
-                }else{
-                    String::from("Not_defined")
-                }
-            }
-        };
+        // Custom CA, client certificate and pinned certificates of the endpoint
+        let endpoint_pins = get_tls_pins(&yaml, "events.endpoint", insecure, endpoint.ca_file.as_deref(),
+            endpoint.client_cert.as_deref(), endpoint.client_key.as_deref(), endpoint.pins.clone(), &mut errors);
 
-        // Manage null value on events->endpoint->credentials->password value
-        let endpoint_pass = match yaml[0]["events"]["endpoint"]["credentials"]["password"].as_str() {
-            Some(value) => String::from(value),
-            None => {
-                if events_destination != *"file" && endpoint_token.is_empty()  {
-                    println!("[ERROR] events->endpoint->credentials->password not found in config.yml.");
-                    panic!("events->endpoint->credentials->password not found in config.yml.");
-                }else{
-                    String::from("Not_defined")
-                }
-            }
-        };
+        let endpoint_token = get_secret(&yaml, credentials.token, credentials.token_file,
+            "events.endpoint.credentials.token", &mut errors);
+        let endpoint_user = credentials.user.unwrap_or(String::from("Not_defined"));
+        let endpoint_pass = get_secret(&yaml, credentials.password, credentials.password_file,
+            "events.endpoint.credentials.password", &mut errors);
 
         let endpoint_type = if endpoint_token != "Not_defined" {
             String::from("Splunk")
@@ -239,40 +356,118 @@ impl AppConfig {
             String::from("Not_defined")
         };
 
-        if endpoint_token == "Not_defined" && (endpoint_user == "Not_defined" ||
-            endpoint_pass == "Not_defined") && events_destination != *"file" {
-            println!("[ERROR] events->endpoint->credentials->[token or user and password] not found in config.yml.");
-            panic!("No endpoint credentials provided in config.yml.");
+        if endpoint_type == "Not_defined" && uses_network {
+            errors.push(yaml.error("events.endpoint.credentials",
+                "missing credentials, set `token` or both `user` and `password`, secrets can be read from `token_file` or `password_file`"));
         }
 
+        // Splunk HTTP event collector metadata and indexer acknowledgement
+        let splunk = endpoint.splunk.clone().unwrap_or_default();
+        let splunk_index = splunk.index.unwrap_or(String::from("fim_events"));
+        let splunk_sourcetype = splunk.sourcetype.unwrap_or(String::from("_json"));
+        let splunk_ack = splunk.ack.unwrap_or(false);
+        let splunk_channel = match splunk.channel {
+            Some(value) => {
+                if uuid::Uuid::parse_str(&value).is_err() {
+                    errors.push(yaml.error("events.endpoint.splunk.channel", "channel must be a GUID"));
+                }
+                value
+            },
+            None => utils::get_uuid()
+        };
+        let splunk_ack_timeout = splunk.ack_timeout.unwrap_or(60).max(1);
+
+        // Elasticsearch daily indices by default, or a data stream
+        let elastic = endpoint.elastic.clone().unwrap_or_default();
+        let elastic_data_stream = elastic.data_stream.unwrap_or(false);
+        let elastic_index = elastic.index.unwrap_or(String::from(match elastic_data_stream {
+            true => output::DEFAULT_DATA_STREAM,
+            false => output::DEFAULT_INDEX
+        }));
+        if let Err(e) = output::check_index_pattern(&elastic_index) {
+            errors.push(yaml.error("events.endpoint.elastic.index", &e));
+        } else if elastic_data_stream && output::has_date_tokens(&elastic_index) {
+            // A data stream rolls over by itself, a dated name would create a new stream each day
+            errors.push(yaml.error("events.endpoint.elastic.index", "a data stream name can't contain date tokens"));
+        }
+        let elastic_ilm = elastic.ilm.map(|ilm| {
+            let ilm = IlmConfig {
+                rollover_size: Some(ilm.rollover_size.unwrap_or(String::from("50gb"))),
+                rollover_age: Some(ilm.rollover_age.unwrap_or(String::from("30d"))),
+                delete_after: ilm.delete_after
+            };
+            for (key, value, units) in [("rollover_size", &ilm.rollover_size, &SIZE_UNITS[..]),
+                ("rollover_age", &ilm.rollover_age, &TIME_UNITS[..]), ("delete_after", &ilm.delete_after, &TIME_UNITS[..])] {
+                if let Some(value) = value {
+                    if !is_unit_value(value, units) {
+                        errors.push(yaml.error(&format!("events.endpoint.elastic.ilm.{}", key),
+                            &format!("expected a number followed by one of {}", units.join(", "))));
+                    }
+                }
+            }
+            ilm
+        });
 
         // Manage null value on monitor value
-        let monitor = match yaml[0]["monitor"].as_vec() {
-            Some(value) => value.to_vec(),
-            None => Vec::new()
-        };
+        let monitor = yaml.monitor.clone();
 
         // Manage null value on audit value
         let mut engine = String::from("monitor");
-        let audit = match yaml[0]["audit"].as_vec() {
+        let audit = match yaml.audit.clone() {
             Some(value) => {
                 if utils::get_os() != "linux" {
-                    panic!("Audit only supported in Linux systems.");
+                    errors.push(yaml.error("audit", "audit only supported in Linux systems"));
                 }
                 engine = String::from("audit");
-                value.to_vec()
+                value
             },
             None => {
                 if monitor.is_empty() {
-                    panic!("Neither monitor or audit section found in config.yml.");
+                    errors.push(yaml.error("", "neither monitor or audit section found"));
                 };
                 Vec::new()
             }
         };
 
+        // Integration conditions are evaluated as [field, operator, value]
+        for (section, paths) in [("monitor", &monitor), ("audit", &audit)] {
+            for (i, element) in paths.iter().enumerate() {
+                for (j, integration) in element.integrations.iter().enumerate() {
+                    let key = format!("{}[{}].integrations[{}].condition", section, i, j);
+                    if integration.condition.len() != 3 {
+                        errors.push(yaml.error(&key, "expected three elements, field, operator and value"));
+                    }else if !["==", "!="].contains(&integration.condition[1].as_str()) {
+                        errors.push(yaml.error(&format!("{}[1]", key), "unknown operator, expected one of ==, !="));
+                    }
+                }
+            }
+        }
+
+        // Patterns are checked once here, matching skips the invalid ones
+        for (section, paths) in [("monitor", &monitor), ("audit", &audit)] {
+            for (i, element) in paths.iter().enumerate() {
+                let mode = element.match_mode;
+                if let Err(e) = matcher::check_pattern(mode, &element.path) {
+                    errors.push(yaml.error(&format!("{}[{}].path", section, i), &e));
+                }else if element.root().is_empty() {
+                    errors.push(yaml.error(&format!("{}[{}].path", section, i),
+                        "pattern must start with a fixed folder to watch"));
+                }
+                for (field, patterns) in [("ignore", &element.ignore), ("exclude", &element.exclude), ("allowed", &element.allowed)] {
+                    for (j, pattern) in patterns.iter().flatten().enumerate() {
+                        if let Err(e) = matcher::check_pattern(mode, pattern) {
+                            errors.push(yaml.error(&format!("{}[{}].{}[{}]", section, i, field, j), &e));
+                        }
+                    }
+                }
+                // Compiled now, not for each event
+                element.matchers();
+            }
+        }
+
         // Manage null value on node value
-        let node = match yaml[0]["node"].as_str() {
-            Some(value) => String::from(value),
+        let node = match yaml.node.clone() {
+            Some(value) => value,
             None => {
                 match system {
                     "linux" => match utils::get_machine_id().is_empty() {
@@ -292,17 +487,17 @@ impl AppConfig {
         };
 
         // Manage null value on log->file value
-        let log_file = match yaml[0]["log"]["file"].as_str() {
-            Some(value) => String::from(value),
+        let log_file = match yaml.log.file.clone() {
+            Some(value) => value,
             None => {
-                println!("[ERROR] log->file not found in config.yml.");
-                panic!("log->file not found in config.yml.");
+                errors.push(yaml.error("log", "missing field `file`"));
+                String::new()
             }
         };
 
         // Manage null value on log->level value
-        let log_level = match yaml[0]["log"]["level"].as_str() {
-            Some(value) => String::from(value),
+        let log_level = match yaml.log.level.clone() {
+            Some(value) => value,
             None => {
                 println!("[WARN] log->level not found in config.yml, using 'info'.");
                 String::from("info")
@@ -310,36 +505,144 @@ impl AppConfig {
         };
 
         // Manage null value on log->max_file_size value
-        let log_max_file_size = match yaml[0]["log"]["max_file_size"].as_i64() {
-            Some(value) => usize::try_from(value).unwrap(),
-            None => 64
-        };
+        let log_max_file_size = yaml.log.max_file_size.unwrap_or(64);
 
         // Manage null value on hashscanner->file value
-        let hashscanner_file = match yaml[0]["hashscanner"]["file"].as_str() {
-            Some(value) => String::from(value),
+        let hashscanner_file = match yaml.hashscanner.file.clone() {
+            Some(value) => value,
             None => {
-                println!("[ERROR] hashscanner->file not found in config.yml.");
-                panic!("hashscanner->file not found in config.yml.");
+                errors.push(yaml.error("hashscanner", "missing field `file`"));
+                String::new()
             }
         };
 
-        let hashscanner_interval = match yaml[0]["hashscanner"]["interval"].as_i64() {
-            Some(value) => {
-                let interval = usize::try_from(value).unwrap();
-                if interval >= 5 { interval * 60 }else{ 300 } // Minimum of five minutes 
+        let hashscanner_interval = match yaml.hashscanner.interval {
+            Some(interval) => {
+                if interval >= 5 { interval * 60 }else{ 300 } // Minimum of five minutes
             },
             None => 3600 // Default one hour
         };
 
-        let hashscanner_enabled = yaml[0]["hashscanner"]["enabled"].as_bool().unwrap_or(true);
+        let hashscanner_enabled = yaml.hashscanner.enabled.unwrap_or(true);
 
-        AppConfig {
+        // Network events pending to be delivered, stored next to the hashscanner database by default
+        let spool = yaml.events.spool.clone().unwrap_or_default();
+        let spool_file = match spool.file {
+            Some(value) => value,
+            None => Path::new(&hashscanner_file).with_file_name("spool.db").to_string_lossy().to_string()
+        };
+        let spool_max_events = spool.max_events.unwrap_or(100000);
+        let spool_overflow = match spool.overflow {
+            Some(value) => {
+                if !["drop_oldest", "drop_newest"].contains(&value.as_str()) {
+                    errors.push(yaml.error("events.spool.overflow",
+                        "unknown overflow policy, expected one of drop_oldest, drop_newest"));
+                }
+                value
+            },
+            None => String::from("drop_oldest")
+        };
+        let spool_max_backoff = spool.max_backoff.unwrap_or(300).max(1);
+
+        // Network events sent together, up to 500 events or 5MB every 5 seconds by default
+        let batch = yaml.events.batch.clone().unwrap_or_default();
+        let batch_max_events = batch.max_events.unwrap_or(500).max(1);
+        let batch_max_bytes = batch.max_bytes.unwrap_or(5242880).max(1);
+        let batch_interval = batch.interval.unwrap_or(5);
+
+        // Syslog collector, UDP and RFC 5424 messages by default
+        let syslog_config = yaml.events.syslog.clone().unwrap_or_default();
+        let syslog_address = match syslog_config.address {
+            Some(value) => value,
+            None => {
+                if uses_syslog {
+                    errors.push(yaml.error("events.syslog", "missing field `address`, required when destination is syslog"));
+                }
+                String::from("Not_defined")
+            }
+        };
+        let syslog_protocol = get_option(&yaml, syslog_config.protocol, "udp", &["udp", "tcp", "tls"],
+            "events.syslog.protocol", &mut errors);
+        let syslog_format = get_option(&yaml, syslog_config.format, "rfc5424", &["rfc5424", "rfc3164"],
+            "events.syslog.format", &mut errors);
+        let syslog_framing = get_option(&yaml, syslog_config.framing, "octet-counting",
+            &["octet-counting", "non-transparent"], "events.syslog.framing", &mut errors);
+        let syslog_facility = get_option(&yaml, syslog_config.facility, "user", &syslog::FACILITIES,
+            "events.syslog.facility", &mut errors);
+        let syslog_severity = get_option(&yaml, syslog_config.severity, "notice", &syslog::SEVERITIES,
+            "events.syslog.severity", &mut errors);
+        let syslog_app_name = syslog_config.app_name.unwrap_or(String::from("fim"));
+        if syslog_app_name.is_empty() || syslog_app_name.len() > 48 || !syslog_app_name.chars().all(|c| c.is_ascii_graphic()) {
+            errors.push(yaml.error("events.syslog.app_name", "expected 1 to 48 printable characters without spaces"));
+        }
+        let syslog_insecure = syslog_config.insecure.unwrap_or(false);
+        let syslog_pins = get_tls_pins(&yaml, "events.syslog", syslog_insecure, syslog_config.ca_file.as_deref(),
+            syslog_config.client_cert.as_deref(), syslog_config.client_key.as_deref(), syslog_config.pins.clone(), &mut errors);
+
+        // Format of the events of each destination, JSON by default
+        let format = yaml.events.format.clone().unwrap_or_default();
+        let format_file = get_option(&yaml, format.file, "json", &formatter::FORMATS,
+            "events.format.file", &mut errors);
+        let format_network = get_option(&yaml, format.network, "json", &formatter::FORMATS,
+            "events.format.network", &mut errors);
+        let format_syslog = get_option(&yaml, format.syslog, "json", &formatter::FORMATS,
+            "events.format.syslog", &mut errors);
+
+        // Hash chain of the events file records, the key is a secret
+        let chain = yaml.events.chain.clone().unwrap_or_default();
+        let chain_enabled = chain.enabled.unwrap_or(false);
+        let chain_key = get_secret(&yaml, chain.key, chain.key_file, "events.chain.key", &mut errors);
+
+        // Webhooks, their secrets are resolved as the endpoint ones
+        let mut webhooks = yaml.events.webhooks.clone().unwrap_or_default();
+        let mut names = Vec::new();
+        for (i, webhook) in webhooks.iter_mut().enumerate() {
+            let key = format!("events.webhooks[{}]", i);
+            let name = webhook.name.clone().unwrap_or(format!("webhook{}", i));
+            if names.contains(&name) {
+                errors.push(yaml.error(&format!("{}.name", key), &format!("duplicated webhook name '{}'", name)));
+            }
+            names.push(name.clone());
+            webhook.name = Some(name);
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                errors.push(yaml.error(&format!("{}.url", key), "expected an http:// or https:// url"));
+            }
+            webhook.method = Some(get_option(&yaml, webhook.method.clone(), "POST", &WEBHOOK_METHODS,
+                &format!("{}.method", key), &mut errors));
+            webhook.format = Some(get_option(&yaml, webhook.format.clone(), "json", &formatter::FORMATS,
+                &format!("{}.format", key), &mut errors));
+            if let Some(ca_file) = &webhook.ca_file {
+                if let Err(e) = tls::load_certs(ca_file) {
+                    errors.push(yaml.error(&format!("{}.ca_file", key), &e));
+                }
+            }
+            if let Some(credentials) = webhook.credentials.as_mut() {
+                let token = get_secret(&yaml, credentials.token.take(), credentials.token_file.take(),
+                    &format!("{}.credentials.token", key), &mut errors);
+                let password = get_secret(&yaml, credentials.password.take(), credentials.password_file.take(),
+                    &format!("{}.credentials.password", key), &mut errors);
+                credentials.token = Some(token).filter(|token| token != "Not_defined");
+                credentials.password = Some(password).filter(|password| password != "Not_defined");
+            }
+        }
+
+        // Rules defined in configuration files
+        errors.extend(ruleset::check_rules(&yaml.rules, |key, message| yaml.error(key, message)));
+
+        if !errors.is_empty() {
+            return Err(ConfigErrors::new(&cfg, errors));
+        }
+
+        Ok(AppConfig {
             version: String::from(VERSION),
             path: cfg,
             events_watcher,
+            poll_interval,
+            poll_compare_contents,
+            events_watch_limit,
             events_destination,
             events_max_file_checksum,
+            events_debounce,
             events_max_file_size,
             checksum_algorithm,
             endpoint_type,
@@ -347,6 +650,15 @@ impl AppConfig {
             endpoint_user,
             endpoint_pass,
             endpoint_token,
+            splunk_index,
+            splunk_sourcetype,
+            splunk_source: splunk.source.unwrap_or(node.clone()),
+            splunk_ack,
+            splunk_channel,
+            splunk_ack_timeout,
+            elastic_index,
+            elastic_data_stream,
+            elastic_ilm,
             events_file: events_file.clone(),
             monitor,
             audit,
@@ -356,18 +668,53 @@ impl AppConfig {
             log_max_file_size,
             system: String::from(system),
             insecure,
+            endpoint_ca_file: endpoint.ca_file,
+            endpoint_client_cert: endpoint.client_cert,
+            endpoint_client_key: endpoint.client_key,
+            endpoint_pins,
             events_lock: Arc::new(Mutex::new(events_file)),
             log_lock: Arc::new(Mutex::new(log_file)),
             hashscanner_file,
             hashscanner_enabled,
             hashscanner_interval,
             hashscanner_algorithm,
-            engine
-        }
+            spool_file,
+            spool_max_events,
+            spool_overflow,
+            spool_max_backoff,
+            spool_lock: Arc::new(Mutex::new(None)),
+            batch_max_events,
+            batch_max_bytes,
+            batch_interval,
+            batch_lock: Arc::new(Mutex::new(Batch::default())),
+            client: Arc::new(OnceLock::new()),
+            syslog_address,
+            syslog_protocol,
+            syslog_format,
+            syslog_framing,
+            syslog_facility: syslog::FACILITIES.iter().position(|f| *f == syslog_facility).unwrap_or(1) as u8,
+            syslog_severity: syslog::SEVERITIES.iter().position(|s| *s == syslog_severity).unwrap_or(5) as u8,
+            syslog_app_name,
+            syslog_insecure,
+            syslog_ca_file: syslog_config.ca_file,
+            syslog_client_cert: syslog_config.client_cert,
+            syslog_client_key: syslog_config.client_key,
+            syslog_pins,
+            syslog_lock: Arc::new(Mutex::new(None)),
+            format_file,
+            format_network,
+            format_syslog,
+            chain_enabled,
+            chain_key,
+            webhooks,
+            router: Arc::new(Mutex::new(None)),
+            engine,
+            rules: yaml.rules.clone(),
+            includes: yaml.includes.clone()
+        })
     }
 
     // ------------------------------------------------------------------------
-
     // To process log level set on config file
     pub fn get_level_filter(&self) -> LevelFilter {
         let mut log = OpenOptions::new()
@@ -392,24 +739,124 @@ impl AppConfig {
 
     // ------------------------------------------------------------------------
 
+    /// Sinks of the configuration, built on the first event and again when
+    /// the configuration is reloaded. Threads started with a previous
+    /// configuration use them too.
+    pub fn get_router(&self) -> Arc<Router> {
+        self.router.lock().unwrap().get_or_insert_with(|| Arc::new(Router::new(self))).clone()
+    }
+
+    // ------------------------------------------------------------------------
+
     pub fn get_events_destination(&self) -> String {
-        match self.events_destination.clone().as_str() {
-            "both" => String::from(BOTH_MODE),
-            "network" => String::from(NETWORK_MODE),
-            // Default option is to log into file
-            _ => String::from(FILE_MODE)
+        get_mode(&self.events_destination)
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Destination of the events of a monitor or audit path
+    pub fn get_destination(&self, index: usize, array: Vec<PathConfig>) -> String {
+        match array.get(index).and_then(|element| element.destination.as_deref()) {
+            Some(destination) => get_mode(destination),
+            None => self.get_events_destination()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Destination of the events of a given path, looked up in monitor and audit paths
+    pub fn get_path_destination(&self, path: &str) -> String {
+        let index = self.get_index(path, "", self.monitor.clone());
+        match index != usize::MAX {
+            true => self.get_destination(index, self.monitor.clone()),
+            false => {
+                let index = self.get_index(path, "", self.audit.clone());
+                self.get_destination(index, self.audit.clone())
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Splunk metadata of the events of a given path, looked up in monitor and audit paths
+    pub fn get_path_splunk(&self, path: &str) -> SplunkPathConfig {
+        let index = self.get_index(path, "", self.monitor.clone());
+        let element = match index != usize::MAX {
+            true => self.monitor.get(index),
+            false => self.audit.get(self.get_index(path, "", self.audit.clone()))
+        };
+        element.and_then(|element| element.splunk.clone()).unwrap_or_default()
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Checksum algorithm of the events of a given path, looked up in monitor and audit paths
+    pub fn get_path_checksum_algorithm(&self, path: &str) -> ShaType {
+        let index = self.get_index(path, "", self.monitor.clone());
+        match index != usize::MAX {
+            true => self.get_checksum_algorithm(index, self.monitor.clone()),
+            false => {
+                let index = self.get_index(path, "", self.audit.clone());
+                self.get_checksum_algorithm(index, self.audit.clone())
+            }
         }
     }
 
     // ------------------------------------------------------------------------
 
-    pub fn get_index(&self, raw_path: &str, cwd: &str, array: Array) -> usize {
+    // Destinations used by all paths, to prepare the events file and the index.
+    // SYSLOG_MODE when neither the events file nor the network endpoint are used.
+    pub fn get_all_destinations(&self) -> String {
+        let modes: Vec<String> = self.monitor.iter().chain(self.audit.iter())
+            .filter_map(|element| element.destination.as_deref().map(get_mode))
+            .chain([self.get_events_destination()]).collect();
+        let file = modes.iter().any(|m| m == FILE_MODE || m == BOTH_MODE);
+        let network = modes.iter().any(|m| m == NETWORK_MODE || m == BOTH_MODE);
+        match (file, network) {
+            (true, true) => String::from(BOTH_MODE),
+            (false, true) => String::from(NETWORK_MODE),
+            (true, false) => String::from(FILE_MODE),
+            (false, false) => String::from(SYSLOG_MODE)
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn get_max_file_checksum(&self, index: usize, array: Vec<PathConfig>) -> usize {
+        match array.get(index).and_then(|element| element.max_file_checksum) {
+            Some(value) => value,
+            None => self.events_max_file_checksum
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Coalescing window of the events of a path in milliseconds, 0 when disabled.
+    pub fn get_debounce(&self, index: usize, array: &[PathConfig]) -> u64 {
+        match array.get(index).and_then(|element| element.debounce) {
+            Some(value) => value,
+            None => self.events_debounce
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn get_checksum_algorithm(&self, index: usize, array: Vec<PathConfig>) -> ShaType {
+        match array.get(index).and_then(|element| element.checksum_algorithm.clone()) {
+            Some(value) => value,
+            None => self.checksum_algorithm.clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn get_index(&self, raw_path: &str, cwd: &str, array: Vec<PathConfig>) -> usize {
         // Iterate over monitoring paths to match ignore string and ignore event or not
         match array.iter().position(|it| {
             if !cwd.is_empty() && (raw_path.starts_with("./") || raw_path == "." || !raw_path.contains('/')) {
-                utils::match_path(cwd, it["path"].as_str().unwrap())
+                it.matchers().path.match_path(cwd)
             }else{
-                utils::match_path(raw_path, it["path"].as_str().unwrap())
+                it.matchers().path.match_path(raw_path)
             }
         }){
             Some(pos) => pos,
@@ -419,34 +866,27 @@ impl AppConfig {
 
     // ------------------------------------------------------------------------
 
-    pub fn get_labels(&self, index: usize, array: Array) -> Vec<String> {
-        array[index]["labels"].clone().into_vec().unwrap_or_default()
-        .to_vec().iter().map(|element| String::from(element.as_str().unwrap()) ).collect()
+    pub fn get_labels(&self, index: usize, array: Vec<PathConfig>) -> Vec<String> {
+        array[index].labels.clone()
     }
 
     // ------------------------------------------------------------------------
 
-    pub fn match_ignore(&self, index: usize, filename: &str, array: Array) -> bool {
-        match array[index]["ignore"].as_vec() {
-            Some(igv) => igv.to_vec().iter().any(|ignore| filename.contains(ignore.as_str().unwrap()) ),
-            None => false
-        }
+    pub fn match_ignore(&self, index: usize, filename: &str, array: Vec<PathConfig>) -> bool {
+        array[index].matchers().ignore.iter().any(|ignore| ignore.match_name(filename))
     }
 
     // ------------------------------------------------------------------------
 
-    pub fn match_exclude(&self, index: usize, path: &str, array: Array) -> bool {
-        match array[index]["exclude"].as_vec() {
-            Some(igv) => igv.to_vec().iter().any(|exclude| path.contains(exclude.as_str().unwrap()) ),
-            None => false
-        }
+    pub fn match_exclude(&self, index: usize, path: &str, array: Vec<PathConfig>) -> bool {
+        array[index].matchers().exclude.iter().any(|exclude| exclude.match_folder(path))
     }
 
     // ------------------------------------------------------------------------
 
-    pub fn match_allowed(&self, index: usize, filename: &str, array: Array) -> bool {
-        match array[index]["allowed"].as_vec() {
-            Some(allowed) => allowed.to_vec().iter().any(|allw| filename.contains(allw.as_str().unwrap())),
+    pub fn match_allowed(&self, index: usize, filename: &str, array: Vec<PathConfig>) -> bool {
+        match &array[index].matchers().allowed {
+            Some(allowed) => allowed.iter().any(|allw| allw.match_name(filename)),
             None => true
         }
     }
@@ -454,48 +894,121 @@ impl AppConfig {
     // ------------------------------------------------------------------------
 
     // Returns if a given path and filename is in the configuration paths
-    pub fn path_in(&self, raw_path: &str, cwd: &str, vector: Vec<Yaml>) -> bool {
+    pub fn path_in(&self, raw_path: &str, cwd: &str, vector: Vec<PathConfig>) -> bool {
         // Iterate over monitoring paths to match ignore string and ignore event or not
         vector.iter().any(|it| {
             if raw_path.starts_with("./") || raw_path == "." || !raw_path.contains('/') {
-                utils::match_path(cwd, it["path"].as_str().unwrap())
+                it.matchers().path.match_path(cwd)
             }else{
-                utils::match_path(raw_path, it["path"].as_str().unwrap())
+                it.matchers().path.match_path(raw_path)
             }
         })
     }
 
     // ------------------------------------------------------------------------
 
-    pub fn get_integrations(&self, index: usize, array: Array) -> Vec<Integration> {
-        let default = array[index]["integrations"].clone().into_vec();
-        let data = default.unwrap_or_default();
-        let mut integrations: Vec<Integration> = Vec::new();
-        data.iter().for_each(|info|
-            integrations.push(Integration::new(
-                String::from(info["name"].as_str().unwrap()), 
-                info["condition"]
-                    .clone().into_vec().unwrap().iter().map(|element| 
-                        String::from(element.as_str().unwrap()) ).collect(), 
-                String::from(info["binary"].as_str().unwrap()), 
-                String::from(info["script"].as_str().unwrap()), 
-                String::from(info["parameters"].as_str().unwrap()) ))
-        );
-        integrations
+    pub fn get_integrations(&self, index: usize, array: Vec<PathConfig>) -> Vec<Integration> {
+        array[index].integrations.clone()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Events destination mode of a `destination` setting
+fn get_mode(destination: &str) -> String {
+    match destination {
+        "both" => String::from(BOTH_MODE),
+        "network" => String::from(NETWORK_MODE),
+        "syslog" => String::from(SYSLOG_MODE),
+        // Default option is to log into file
+        _ => String::from(FILE_MODE)
     }
 }
 
 // ----------------------------------------------------------------------------
 
-// To read the Yaml configuration file
-pub fn read_config(path: String) -> Vec<Yaml> {
-    let mut file: File = File::open(path.clone())
-        .unwrap_or_else(|_| panic!("(read_config): Unable to open file '{}'", path));
-    let mut contents: String = String::new();
+// Value of a setting restricted to a list of options
+fn get_option(yaml: &ConfigFile, value: Option<String>, default: &str, options: &[&str],
+    key: &str, errors: &mut Vec<ConfigError>) -> String {
+    match value {
+        Some(value) => {
+            if !options.contains(&value.as_str()) {
+                errors.push(yaml.error(key, &format!("unknown value, expected one of {}", options.join(", "))));
+            }
+            value
+        },
+        None => String::from(default)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Elasticsearch sizes and time units, as 50gb or 30d
+fn is_unit_value(value: &str, units: &[&str]) -> bool {
+    let digits = value.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && units.contains(&&value[digits..])
+}
+
+// ----------------------------------------------------------------------------
+
+// Secret given in the config or read from the file set in `<key>_file`
+fn get_secret(yaml: &ConfigFile, value: Option<String>, file: Option<String>,
+    key: &str, errors: &mut Vec<ConfigError>) -> String {
+    match (value, file) {
+        (Some(_), Some(_)) => {
+            errors.push(yaml.error(key, &format!("set either `{0}` or `{0}_file`, not both",
+                key.rsplit('.').next().unwrap_or_default())));
+            String::from("Not_defined")
+        },
+        (Some(value), None) => value,
+        (None, Some(file)) => match utils::read_secret(&file) {
+            Ok(secret) => secret,
+            Err(e) => {
+                errors.push(yaml.error(&format!("{}_file", key), &e));
+                String::from("Not_defined")
+            }
+        },
+        (None, None) => String::from("Not_defined")
+    }
+}
+
+// ----------------------------------------------------------------------------
 
-    file.read_to_string(&mut contents)
-        .expect("Unable to read file");
-    YamlLoader::load_from_str(&contents).unwrap()
+// Check the CA, client certificate and pins of a TLS connection can be used,
+// returning the pins in the form expected by the TLS configuration
+#[allow(clippy::too_many_arguments)]
+fn get_tls_pins(yaml: &ConfigFile, key: &str, insecure: bool, ca_file: Option<&str>, client_cert: Option<&str>,
+    client_key: Option<&str>, pins: Option<Vec<String>>, errors: &mut Vec<ConfigError>) -> Vec<String> {
+    let tls_errors = errors.len();
+    if let Some(ca_file) = ca_file {
+        if let Err(e) = tls::load_certs(ca_file) {
+            errors.push(yaml.error(&format!("{}.ca_file", key), &e));
+        }
+    }
+    match (client_cert, client_key) {
+        (Some(cert), Some(client_key)) => {
+            if let Err(e) = tls::load_certs(cert) {
+                errors.push(yaml.error(&format!("{}.client_cert", key), &e));
+            }
+            if let Err(e) = tls::load_key(client_key) {
+                errors.push(yaml.error(&format!("{}.client_key", key), &e));
+            }
+        },
+        (Some(_), None) => errors.push(yaml.error(key, "missing field `client_key`, required with `client_cert`")),
+        (None, Some(_)) => errors.push(yaml.error(key, "missing field `client_cert`, required with `client_key`")),
+        (None, None) => ()
+    }
+    let pins: Vec<String> = pins.unwrap_or_default().iter().enumerate()
+        .filter_map(|(i, pin)| tls::parse_pin(pin)
+            .map_err(|e| errors.push(yaml.error(&format!("{}.pins[{}]", key, i), &e))).ok())
+        .collect();
+    // Clients are built on first use, so settings they can't apply are rejected here
+    if errors.len() == tls_errors {
+        if let Err(e) = tls::get_config(insecure, ca_file, client_cert, client_key, &pins) {
+            errors.push(yaml.error(key, &e));
+        }
+    }
+    pins
 }
 
 // ----------------------------------------------------------------------------
@@ -537,6 +1050,7 @@ pub fn get_config_path(system: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::matcher::MatchMode;
 
     // ------------------------------------------------------------------------
 
@@ -545,8 +1059,12 @@ mod tests {
             version: String::from(VERSION),
             path: String::from("test"),
             events_watcher: String::from("Recommended"),
+            poll_interval: 30,
+            poll_compare_contents: false,
+            events_watch_limit: String::from("Alert"),
             events_destination: String::from(events_destination),
             events_max_file_checksum: 64,
+            events_debounce: 0,
             events_max_file_size: 128,
             checksum_algorithm: ShaType::Sha512,
             endpoint_type: String::from("Elastic"),
@@ -554,22 +1072,67 @@ mod tests {
             endpoint_user: String::from("test"),
             endpoint_pass: String::from("test"),
             endpoint_token: String::from("test"),
+            splunk_index: String::from("fim_events"),
+            splunk_sourcetype: String::from("_json"),
+            splunk_source: String::from("test"),
+            splunk_ack: false,
+            splunk_channel: String::from("test"),
+            splunk_ack_timeout: 60,
+            elastic_index: String::from(output::DEFAULT_INDEX),
+            elastic_data_stream: false,
+            elastic_ilm: None,
             events_file: String::from("test"),
-            monitor: Array::new(),
-            audit: Array::new(),
+            monitor: Vec::new(),
+            audit: Vec::new(),
             node: String::from("test"),
             log_file: String::from("./test.log"),
             log_level: String::from(filter),
             log_max_file_size: 64,
             system: String::from("test"),
             insecure: true,
+            endpoint_ca_file: None,
+            endpoint_client_cert: None,
+            endpoint_client_key: None,
+            endpoint_pins: Vec::new(),
             events_lock: Arc::new(Mutex::new(String::from("test"))),
             log_lock: Arc::new(Mutex::new(String::from("test"))),
             hashscanner_file: String::from("test"),
             hashscanner_enabled: true,
             hashscanner_interval: 3600,
             hashscanner_algorithm: ShaType::Sha256,
-            engine: String::from("monitor")
+            spool_file: String::from("spool.db"),
+            spool_max_events: 100000,
+            spool_overflow: String::from("drop_oldest"),
+            spool_max_backoff: 300,
+            spool_lock: Arc::new(Mutex::new(None)),
+            batch_max_events: 500,
+            batch_max_bytes: 5242880,
+            batch_interval: 5,
+            batch_lock: Arc::new(Mutex::new(Batch::default())),
+            client: Arc::new(OnceLock::new()),
+            syslog_address: String::from("Not_defined"),
+            syslog_protocol: String::from("udp"),
+            syslog_format: String::from("rfc5424"),
+            syslog_framing: String::from("octet-counting"),
+            syslog_facility: 1,
+            syslog_severity: 5,
+            syslog_app_name: String::from("fim"),
+            syslog_insecure: false,
+            syslog_ca_file: None,
+            syslog_client_cert: None,
+            syslog_client_key: None,
+            syslog_pins: Vec::new(),
+            syslog_lock: Arc::new(Mutex::new(None)),
+            format_file: String::from("json"),
+            format_network: String::from("json"),
+            format_syslog: String::from("json"),
+            chain_enabled: false,
+            chain_key: String::from("Not_defined"),
+            webhooks: Vec::new(),
+            router: Arc::new(Mutex::new(None)),
+            engine: String::from("monitor"),
+            rules: Vec::new(),
+            includes: Vec::new()
         }
     }
 
@@ -612,7 +1175,7 @@ mod tests {
     fn test_new_config_windows() {
         let dir = utils::get_current_dir();
         let disk = dir.get(0..1).unwrap();
-        let cfg = AppConfig::new("windows", None);
+        let cfg = AppConfig::new("windows", None).unwrap();
 
         assert_eq!(cfg.version, String::from(VERSION));
         assert_eq!(cfg.events_destination, String::from("file"));
@@ -642,7 +1205,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_destination() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_destination_none.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_destination_none.yml")).unwrap();
         assert_eq!(cfg.events_destination, String::from("file"));
     }
 
@@ -650,18 +1213,16 @@ mod tests {
 
     #[cfg(target_os = "windows")]
     #[test]
-    #[should_panic]
     fn test_new_config_windows_hashscanner_file() {
-        AppConfig::new("windows", Some("test/unit/config/windows/hashscanner_file_none.yml"));
+        assert!(AppConfig::new("windows", Some("test/unit/config/windows/hashscanner_file_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
 
     #[cfg(target_os = "windows")]
     #[test]
-    #[should_panic]
     fn test_new_config_windows_events_file() {
-        AppConfig::new("windows", Some("test/unit/config/windows/events_file_none.yml"));
+        assert!(AppConfig::new("windows", Some("test/unit/config/windows/events_file_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -669,7 +1230,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_destination_network() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_destination_network.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_destination_network.yml")).unwrap();
         assert_eq!(cfg.events_file, String::from("Not_defined"));
     }
 
@@ -678,7 +1239,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_max_file_checksum() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_max_file_checksum.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_max_file_checksum.yml")).unwrap();
         assert_eq!(cfg.events_max_file_checksum, 128);
     }
 
@@ -687,7 +1248,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_max_file_size() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_max_file_size.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_max_file_size.yml")).unwrap();
         assert_eq!(cfg.events_max_file_size, 256);
     }
 
@@ -696,7 +1257,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_endpoint_insecure() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_endpoint_insecure.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_endpoint_insecure.yml")).unwrap();
         assert_eq!(cfg.insecure, true);
     }
 
@@ -705,7 +1266,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_endpoint_insecure_none() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_endpoint_insecure_none.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_endpoint_insecure_none.yml")).unwrap();
         assert_eq!(cfg.insecure, false);
     }
 
@@ -714,7 +1275,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_destination_network_address() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_destination_network_address.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_destination_network_address.yml")).unwrap();
         assert_eq!(cfg.endpoint_address, "0.0.0.0");
     }
 
@@ -722,9 +1283,8 @@ mod tests {
 
     #[cfg(target_os = "windows")]
     #[test]
-    #[should_panic]
     fn test_new_config_windows_events_destination_network_address_none() {
-        AppConfig::new("windows", Some("test/unit/config/windows/events_destination_network_address_none.yml"));
+        assert!(AppConfig::new("windows", Some("test/unit/config/windows/events_destination_network_address_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -732,7 +1292,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_credentials_user() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_user.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_user.yml")).unwrap();
         assert_eq!(cfg.endpoint_user, "test_user");
     }
 
@@ -740,9 +1300,8 @@ mod tests {
 
     #[cfg(target_os = "windows")]
     #[test]
-    #[should_panic]
     fn test_new_config_windows_events_credentials_user_none() {
-        AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_user_none.yml"));
+        assert!(AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_user_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -750,7 +1309,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_credentials_password() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_password.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_password.yml")).unwrap();
         assert_eq!(cfg.endpoint_pass, "test_password");
     }
 
@@ -759,7 +1318,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_events_credentials_token() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_token.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_token.yml")).unwrap();
         assert_eq!(cfg.endpoint_token, "test_token");
     }
 
@@ -767,18 +1326,16 @@ mod tests {
 
     #[cfg(target_os = "windows")]
     #[test]
-    #[should_panic]
     fn test_new_config_windows_events_credentials_password_none() {
-        AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_password_none.yml"));
+        assert!(AppConfig::new("windows", Some("test/unit/config/windows/events_credentials_password_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
 
     #[cfg(target_os = "windows")]
     #[test]
-    #[should_panic]
     fn test_new_config_windows_monitor_none() {
-        AppConfig::new("windows", Some("test/unit/config/windows/monitor_none.yml"));
+        assert!(AppConfig::new("windows", Some("test/unit/config/windows/monitor_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -786,7 +1343,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_node_none() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/node_none.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/node_none.yml")).unwrap();
         assert_eq!(cfg.node, utils::get_hostname());
     }
 
@@ -794,9 +1351,8 @@ mod tests {
 
     #[cfg(target_os = "windows")]
     #[test]
-    #[should_panic]
     fn test_new_config_windows_log_file_none() {
-        AppConfig::new("windows", Some("test/unit/config/windows/log_file_none.yml"));
+        assert!(AppConfig::new("windows", Some("test/unit/config/windows/log_file_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -804,7 +1360,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_log_level_none() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/log_level_none.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/log_level_none.yml")).unwrap();
         assert_eq!(cfg.log_level, "info");
     }
 
@@ -813,7 +1369,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_config_windows_log_max_file_size_none() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/log_max_file_size_none.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/log_max_file_size_none.yml")).unwrap();
         assert_eq!(cfg.log_max_file_size, 64);
     }
 
@@ -822,7 +1378,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_destination() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_destination_none.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_destination_none.yml")).unwrap();
         assert_eq!(cfg.events_destination, String::from("file"));
     }
 
@@ -830,18 +1386,16 @@ mod tests {
 
     #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic]
     fn test_new_config_linux_hashscanner_file() {
-        AppConfig::new("linux", Some("test/unit/config/linux/hashscanner_file_none.yml"));
+        assert!(AppConfig::new("linux", Some("test/unit/config/linux/hashscanner_file_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
 
     #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic]
     fn test_new_config_linux_events_file() {
-        AppConfig::new("linux", Some("test/unit/config/linux/events_file_none.yml"));
+        assert!(AppConfig::new("linux", Some("test/unit/config/linux/events_file_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -849,7 +1403,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_destination_network() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_destination_network.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_destination_network.yml")).unwrap();
         assert_eq!(cfg.events_file, String::from("Not_defined"));
     }
 
@@ -858,7 +1412,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_max_file_checksum() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_max_file_checksum.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_max_file_checksum.yml")).unwrap();
         assert_eq!(cfg.events_max_file_checksum, 128);
     }
 
@@ -867,7 +1421,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_max_file_size() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_max_file_size.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_max_file_size.yml")).unwrap();
         assert_eq!(cfg.events_max_file_size, 256);
     }
 
@@ -876,7 +1430,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_endpoint_insecure() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_endpoint_insecure.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_endpoint_insecure.yml")).unwrap();
         assert_eq!(cfg.insecure, true);
     }
 
@@ -885,7 +1439,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_endpoint_insecure_none() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_endpoint_insecure_none.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_endpoint_insecure_none.yml")).unwrap();
         assert_eq!(cfg.insecure, false);
     }
 
@@ -894,7 +1448,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_destination_network_address() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_destination_network_address.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_destination_network_address.yml")).unwrap();
         assert_eq!(cfg.endpoint_address, "0.0.0.0");
     }
 
@@ -902,9 +1456,8 @@ mod tests {
 
     #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic]
     fn test_new_config_linux_events_destination_network_address_none() {
-        AppConfig::new("linux", Some("test/unit/config/linux/events_destination_network_address_none.yml"));
+        assert!(AppConfig::new("linux", Some("test/unit/config/linux/events_destination_network_address_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -912,7 +1465,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_credentials_user() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_user.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_user.yml")).unwrap();
         assert_eq!(cfg.endpoint_user, "test_user");
     }
 
@@ -920,9 +1473,8 @@ mod tests {
 
     #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic]
     fn test_new_config_linux_events_credentials_user_none() {
-        AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_user_none.yml"));
+        assert!(AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_user_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -930,7 +1482,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_credentials_password() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_password.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_password.yml")).unwrap();
         assert_eq!(cfg.endpoint_pass, "test_password");
     }
 
@@ -939,7 +1491,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_events_credentials_token() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_token.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_token.yml")).unwrap();
         assert_eq!(cfg.endpoint_token, "test_token");
     }
 
@@ -947,9 +1499,8 @@ mod tests {
 
     #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic]
     fn test_new_config_linux_events_credentials_password_none() {
-        AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_password_none.yml"));
+        assert!(AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_password_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -957,7 +1508,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_monitor_none() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_none.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_none.yml")).unwrap();
         assert_eq!(cfg.monitor, Vec::new());
     }
 
@@ -966,7 +1517,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_audit_none() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/audit_none.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/audit_none.yml")).unwrap();
         assert_eq!(cfg.audit, Vec::new());
     }
 
@@ -974,9 +1525,8 @@ mod tests {
 
     #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic]
     fn test_new_config_linux_audit_and_monitor_none() {
-        AppConfig::new("linux", Some("test/unit/config/linux/audit_and_monitor_none.yml"));
+        assert!(AppConfig::new("linux", Some("test/unit/config/linux/audit_and_monitor_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -984,7 +1534,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_node_none() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/node_none.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/node_none.yml")).unwrap();
         let machine_id = utils::get_machine_id();
         match machine_id.is_empty(){
             true => assert_eq!(cfg.node, utils::get_hostname()),
@@ -996,9 +1546,8 @@ mod tests {
 
     #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic]
     fn test_new_config_linux_log_file_none() {
-        AppConfig::new("linux", Some("test/unit/config/linux/log_file_none.yml"));
+        assert!(AppConfig::new("linux", Some("test/unit/config/linux/log_file_none.yml")).is_err());
     }
 
     // ------------------------------------------------------------------------
@@ -1006,7 +1555,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_log_level_none() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/log_level_none.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/log_level_none.yml")).unwrap();
         assert_eq!(cfg.log_level, "info");
     }
 
@@ -1015,7 +1564,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_new_config_linux_log_max_file_size_none() {
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/log_max_file_size_none.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/log_max_file_size_none.yml")).unwrap();
         assert_eq!(cfg.log_max_file_size, 64);
     }
 
@@ -1025,7 +1574,7 @@ mod tests {
     #[test]
     fn test_new_config_linux() {
         if utils::get_os() == "linux" {
-            let cfg = AppConfig::new("linux", None);
+            let cfg = AppConfig::new("linux", None).unwrap();
             assert_eq!(cfg.version, String::from(VERSION));
             assert_eq!(cfg.events_destination, String::from("file"));
             assert_eq!(cfg.endpoint_type, String::from("Not_defined"));
@@ -1055,7 +1604,7 @@ mod tests {
     #[cfg(target_os = "macos")]
     #[test]
     fn test_new_config_macos() {
-        let cfg = AppConfig::new("macos", None);
+        let cfg = AppConfig::new("macos", None).unwrap();
         assert_eq!(cfg.version, String::from(VERSION));
         assert_eq!(cfg.events_destination, String::from("file"));
         assert_eq!(cfg.endpoint_type, String::from("Not_defined"));
@@ -1166,24 +1715,312 @@ mod tests {
 
     // ------------------------------------------------------------------------
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_path_overrides() {
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_overrides.yml")).unwrap();
+        assert_eq!(cfg.get_max_file_checksum(0, cfg.monitor.clone()), 1024);
+        assert_eq!(cfg.get_max_file_checksum(1, cfg.monitor.clone()), 1);
+        assert_eq!(cfg.get_max_file_checksum(2, cfg.monitor.clone()), 64);
+        assert_eq!(cfg.get_max_file_checksum(usize::MAX, cfg.monitor.clone()), 64);
+        assert_eq!(cfg.get_debounce(0, &cfg.monitor), 500);
+        assert_eq!(cfg.get_debounce(1, &cfg.monitor), 0);
+        assert_eq!(cfg.get_debounce(2, &cfg.monitor), 100);
+        assert_eq!(cfg.get_debounce(usize::MAX, &cfg.monitor), 100);
+        assert!(matches!(cfg.get_checksum_algorithm(0, cfg.monitor.clone()), ShaType::Keccak512));
+        assert!(matches!(cfg.get_checksum_algorithm(1, cfg.monitor.clone()), ShaType::Sha256));
+        assert_eq!(cfg.get_destination(0, cfg.monitor.clone()), String::from(FILE_MODE));
+        assert_eq!(cfg.get_destination(1, cfg.monitor.clone()), String::from(NETWORK_MODE));
+        assert_eq!(cfg.get_destination(0, cfg.audit.clone()), String::from(BOTH_MODE));
+        assert_eq!(cfg.get_path_destination("/var/lib/images/disk.img"), String::from(NETWORK_MODE));
+        assert_eq!(cfg.get_path_destination("/tmp/file.txt"), String::from(BOTH_MODE));
+        assert_eq!(cfg.get_path_destination("/opt/file.txt"), String::from(FILE_MODE));
+        assert_eq!(cfg.get_all_destinations(), String::from(BOTH_MODE));
+        assert_eq!(create_test_config("info", "network").get_all_destinations(), String::from(NETWORK_MODE));
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_new_config_linux_overrides_invalid() {
+        let errors = AppConfig::new("linux", Some("test/unit/config/linux/monitor_overrides_invalid.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 3);
+        assert_eq!(errors.errors[0].key, "monitor[1].destination");
+        assert_eq!(errors.errors[0].line, 13);
+        assert_eq!(errors.errors[1].key, "events.endpoint");
+        assert_eq!(errors.errors[1].message, "missing field `address`, required when destination is network or both");
+        assert_eq!(errors.errors[2].key, "events.endpoint.credentials");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_spool() {
+        let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert_eq!(cfg.spool_file, Path::new(&cfg.hashscanner_file).with_file_name("spool.db")
+            .to_string_lossy().to_string());
+        assert_eq!(cfg.spool_max_events, 100000);
+        assert_eq!(cfg.spool_overflow, "drop_oldest");
+        assert_eq!(cfg.spool_max_backoff, 300);
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_spool.yml")).unwrap();
+        assert_eq!(cfg.spool_file, "./test_spool.db");
+        assert_eq!(cfg.spool_max_events, 10);
+        assert_eq!(cfg.spool_overflow, "drop_newest");
+        assert_eq!(cfg.spool_max_backoff, 1);
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_spool_invalid.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 1);
+        assert_eq!(errors.errors[0].key, "events.spool.overflow");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_splunk() {
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+        assert_eq!(cfg.splunk_index, "fim_events");
+        assert_eq!(cfg.splunk_sourcetype, "_json");
+        assert_eq!(cfg.splunk_source, "FIM");
+        assert!(!cfg.splunk_ack);
+        assert!(uuid::Uuid::parse_str(&cfg.splunk_channel).is_ok());
+        assert_eq!(cfg.splunk_ack_timeout, 60);
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_splunk.yml")).unwrap();
+        assert_eq!(cfg.splunk_index, "fim");
+        assert_eq!(cfg.splunk_sourcetype, "fim:event");
+        assert!(cfg.splunk_ack);
+        assert_eq!(cfg.splunk_channel, "0f2a7f6c-3b1e-4d5a-9c2b-8e7d6f5a4b3c");
+        assert_eq!(cfg.splunk_ack_timeout, 2);
+        assert_eq!(cfg.get_path_splunk("/etc/hosts").index, Some(String::from("fim_etc")));
+        assert_eq!(cfg.get_path_splunk("/test/file"), SplunkPathConfig::default());
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_splunk_invalid.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 1);
+        assert_eq!(errors.errors[0].key, "events.endpoint.splunk.channel");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_elastic() {
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+        assert_eq!(cfg.elastic_index, "fim-%Y-%m-%d");
+        assert!(!cfg.elastic_data_stream);
+        assert_eq!(cfg.elastic_ilm, None);
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_elastic.yml")).unwrap();
+        assert_eq!(cfg.elastic_index, "fim-events");
+        assert!(cfg.elastic_data_stream);
+        assert_eq!(cfg.elastic_ilm, Some(IlmConfig {
+            rollover_size: Some(String::from("10gb")),
+            rollover_age: Some(String::from("30d")),
+            delete_after: Some(String::from("90d"))
+        }));
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_elastic_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.endpoint.elastic.index", "events.endpoint.elastic.ilm.rollover_size",
+            "events.endpoint.elastic.ilm.delete_after"]);
+        assert_eq!(errors.errors[1].message, "expected a number followed by one of b, kb, mb, gb, tb");
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_elastic_dated_stream.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 1);
+        assert_eq!(errors.errors[0].key, "events.endpoint.elastic.index");
+        assert_eq!(errors.errors[0].message, "a data stream name can't contain date tokens");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_tls() {
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_tls.yml")).unwrap();
+        assert_eq!(cfg.endpoint_ca_file, Some(String::from("test/unit/tls/ca.pem")));
+        assert_eq!(cfg.endpoint_client_cert, Some(String::from("test/unit/tls/client.pem")));
+        assert_eq!(cfg.endpoint_client_key, Some(String::from("test/unit/tls/client.key")));
+        assert_eq!(cfg.endpoint_pins, vec!["0fe49d50888b3cd3dcee140c39ccda312467555215bd026571986384c9404508"]);
+        assert!(!cfg.insecure);
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_tls_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.endpoint.ca_file", "events.endpoint", "events.endpoint.pins[0]"]);
+        assert_eq!(errors.errors[1].message, "missing field `client_key`, required with `client_cert`");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_tls_mismatch() {
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_tls_mismatch.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 1);
+        assert_eq!(errors.errors[0].key, "events.endpoint");
+        assert!(errors.errors[0].message.starts_with("invalid client certificate or key"));
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_webhooks() {
+        let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert!(cfg.webhooks.is_empty());
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_webhook.yml")).unwrap();
+        assert_eq!(cfg.webhooks.len(), 2);
+        assert_eq!(cfg.webhooks[0].name, Some(String::from("chatops")));
+        assert_eq!(cfg.webhooks[0].method, Some(String::from("POST")));
+        assert_eq!(cfg.webhooks[0].format, Some(String::from("json")));
+        assert_eq!(cfg.webhooks[0].credentials.clone().unwrap().token, Some(String::from("test_token")));
+        assert_eq!(cfg.webhooks[0].credentials.clone().unwrap().password, None);
+        assert_eq!(cfg.webhooks[0].filter.operations, Some(vec![String::from("CREATE"), String::from("REMOVE")]));
+        assert_eq!(cfg.webhooks[1].name, Some(String::from("webhook1")));
+        assert_eq!(cfg.webhooks[1].method, Some(String::from("PUT")));
+        assert_eq!(cfg.webhooks[1].filter.rules, Some(vec![1]));
+        assert_eq!(cfg.webhooks[1].ca_file, Some(String::from("test/unit/tls/ca.pem")));
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_webhook_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.webhooks[0].url", "events.webhooks[0].method", "events.webhooks[0].ca_file",
+            "events.webhooks[1].name", "events.webhooks[1].format", "events.webhooks[1].credentials.token"]);
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_syslog() {
+        let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert_eq!(cfg.syslog_address, "Not_defined");
+        assert_eq!(cfg.syslog_protocol, "udp");
+        assert_eq!(cfg.syslog_format, "rfc5424");
+        assert_eq!(cfg.syslog_framing, "octet-counting");
+        assert_eq!(cfg.syslog_facility, 1);
+        assert_eq!(cfg.syslog_severity, 5);
+        assert_eq!(cfg.syslog_app_name, "fim");
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog.yml")).unwrap();
+        assert_eq!(cfg.get_events_destination(), SYSLOG_MODE);
+        assert_eq!(cfg.get_path_destination("/etc/hosts"), FILE_MODE);
+        assert_eq!(cfg.get_all_destinations(), FILE_MODE);
+        assert_eq!(cfg.syslog_address, "127.0.0.1:5514");
+        assert_eq!(cfg.syslog_protocol, "tcp");
+        assert_eq!(cfg.syslog_facility, 16);
+        assert_eq!(cfg.syslog_severity, 6);
+        assert_eq!(cfg.syslog_app_name, "fim-test");
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.syslog", "events.syslog.protocol", "events.syslog.format",
+            "events.syslog.framing", "events.syslog.facility", "events.syslog.severity", "events.syslog.app_name"]);
+        assert_eq!(errors.errors[1].message, "unknown value, expected one of udp, tcp, tls");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_syslog_tls() {
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog_tls.yml")).unwrap();
+        assert_eq!(cfg.syslog_protocol, "tls");
+        assert_eq!(cfg.syslog_ca_file, Some(String::from("test/unit/tls/ca.pem")));
+        assert_eq!(cfg.syslog_client_cert, Some(String::from("test/unit/tls/client.pem")));
+        assert_eq!(cfg.syslog_client_key, Some(String::from("test/unit/tls/client.key")));
+        assert_eq!(cfg.syslog_pins, vec!["0fe49d50888b3cd3dcee140c39ccda312467555215bd026571986384c9404508"]);
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog_tls_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.syslog.ca_file", "events.syslog", "events.syslog.pins[0]"]);
+        assert_eq!(errors.errors[1].message, "missing field `client_key`, required with `client_cert`");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_format() {
+        let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert_eq!(cfg.format_file, "json");
+        assert_eq!(cfg.format_network, "json");
+        assert_eq!(cfg.format_syslog, "json");
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_format.yml")).unwrap();
+        assert_eq!(cfg.format_file, "cef");
+        assert_eq!(cfg.format_network, "leef");
+        assert_eq!(cfg.format_syslog, "ecs");
+
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_format_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.format.file", "events.format.network", "events.format.syslog"]);
+        assert_eq!(errors.errors[0].message, "unknown value, expected one of json, ecs, ocsf, cef, leef");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_chain() {
+        let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert!(!cfg.chain_enabled);
+        assert_eq!(cfg.chain_key, "Not_defined");
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_chain.yml")).unwrap();
+        assert!(cfg.chain_enabled);
+        assert_eq!(cfg.chain_key, "test_key");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_debounce_invalid() {
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_debounce_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.debounce", "monitor[1].debounce"]);
+        assert_eq!(errors.errors[0].message, "expected a window up to 60000 milliseconds");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_batch() {
+        let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert_eq!(cfg.batch_max_events, 500);
+        assert_eq!(cfg.batch_max_bytes, 5242880);
+        assert_eq!(cfg.batch_interval, 5);
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_spool.yml")).unwrap();
+        assert_eq!(cfg.batch_max_events, 50);
+        assert_eq!(cfg.batch_max_bytes, 5242880);
+        assert_eq!(cfg.batch_interval, 1);
+    }
+
+    // ------------------------------------------------------------------------
+
     #[test]
     fn test_read_config_unix() {
-        let yaml = read_config(String::from("config/linux/config.yml"));
+        let yaml = read_config(String::from("config/linux/config.yml")).unwrap();
 
-        assert_eq!(yaml[0]["node"].as_str().unwrap(), "FIM");
-        assert_eq!(yaml[0]["events"]["destination"].as_str().unwrap(), "file");
-        assert_eq!(yaml[0]["events"]["file"].as_str().unwrap(), "/var/lib/fim/events.json");
+        assert_eq!(yaml.node.unwrap(), "FIM");
+        assert_eq!(yaml.events.destination.unwrap(), "file");
+        assert_eq!(yaml.events.file.unwrap(), "/var/lib/fim/events.json");
 
-        assert_eq!(yaml[0]["monitor"][0]["path"].as_str().unwrap(), "/bin/");
-        assert_eq!(yaml[0]["monitor"][1]["path"].as_str().unwrap(), "/usr/bin/");
-        assert_eq!(yaml[0]["monitor"][1]["labels"][0].as_str().unwrap(), "usr/bin");
-        assert_eq!(yaml[0]["monitor"][1]["labels"][1].as_str().unwrap(), "linux");
-        assert_eq!(yaml[0]["monitor"][2]["path"].as_str().unwrap(), "/etc");
-        assert_eq!(yaml[0]["monitor"][2]["labels"][0].as_str().unwrap(), "etc");
-        assert_eq!(yaml[0]["monitor"][2]["labels"][1].as_str().unwrap(), "linux");
+        assert_eq!(yaml.monitor[0].path, "/bin/");
+        assert_eq!(yaml.monitor[1].path, "/usr/bin/");
+        assert_eq!(yaml.monitor[1].labels[0], "usr/bin");
+        assert_eq!(yaml.monitor[1].labels[1], "linux");
+        assert_eq!(yaml.monitor[2].path, "/etc");
+        assert_eq!(yaml.monitor[2].labels[0], "etc");
+        assert_eq!(yaml.monitor[2].labels[1], "linux");
 
-        assert_eq!(yaml[0]["log"]["file"].as_str().unwrap(), "/var/log/fim/fim.log");
-        assert_eq!(yaml[0]["log"]["level"].as_str().unwrap(), "info");
+        assert_eq!(yaml.log.file.unwrap(), "/var/log/fim/fim.log");
+        assert_eq!(yaml.log.level.unwrap(), "info");
     }
 
     // ------------------------------------------------------------------------
@@ -1193,37 +2030,152 @@ mod tests {
     fn test_read_config_windows() {
         let dir = utils::get_current_dir();
         let disk = dir.get(0..1).unwrap();
-        let yaml = read_config(String::from("config/windows/config.yml"));
+        let yaml = read_config(String::from("config/windows/config.yml")).unwrap();
+
+        assert_eq!(yaml.node.unwrap(), "FIM");
+        assert_eq!(yaml.events.destination.unwrap(), "file");
+        assert_eq!(yaml.events.file.unwrap(), format!("{}:\\ProgramData\\fim\\events.json", disk) );
+
+        assert_eq!(yaml.monitor[0].path, "C:\\Program Files\\");
+        assert_eq!(yaml.monitor[0].labels[0], "Program Files");
+        assert_eq!(yaml.monitor[0].labels[1], "windows");
+        assert_eq!(yaml.monitor[1].path, "C:\\Users\\" );
+        assert_eq!(yaml.monitor[1].labels[0], "Users");
+        assert_eq!(yaml.monitor[1].labels[1], "windows");
+
+        assert_eq!(yaml.log.file.unwrap(), format!("{}:\\ProgramData\\fim\\fim.log", disk) );
+        assert_eq!(yaml.log.level.unwrap(), "info");
+    }
+
+    // ------------------------------------------------------------------------
 
-        assert_eq!(yaml[0]["node"].as_str().unwrap(), "FIM");
-        assert_eq!(yaml[0]["events"]["destination"].as_str().unwrap(), "file");
-        assert_eq!(yaml[0]["events"]["file"].as_str().unwrap(), format!("{}:\\ProgramData\\fim\\events.json", disk) );
+    #[test]
+    fn test_read_config_not_found() {
+        let errors = read_config(String::from("NotFound")).unwrap_err();
+        assert_eq!(errors.errors.len(), 1);
+        assert!(errors.errors[0].message.contains("NotFound"));
+    }
 
-        assert_eq!(yaml[0]["monitor"][0]["path"].as_str().unwrap(), "C:\\Program Files\\");
-        assert_eq!(yaml[0]["monitor"][0]["labels"][0].as_str().unwrap(), "Program Files");
-        assert_eq!(yaml[0]["monitor"][0]["labels"][1].as_str().unwrap(), "windows");
-        assert_eq!(yaml[0]["monitor"][1]["path"].as_str().unwrap(), "C:\\Users\\" );
-        assert_eq!(yaml[0]["monitor"][1]["labels"][0].as_str().unwrap(), "Users");
-        assert_eq!(yaml[0]["monitor"][1]["labels"][1].as_str().unwrap(), "windows");
+    // ------------------------------------------------------------------------
 
-        assert_eq!(yaml[0]["log"]["file"].as_str().unwrap(), format!("{}:\\ProgramData\\fim\\fim.log", disk) );
-        assert_eq!(yaml[0]["log"]["level"].as_str().unwrap(), "info");
+    #[test]
+    fn test_read_config_not_config() {
+        let errors = read_config(String::from("README.md")).unwrap_err();
+        assert_eq!(errors.file, "README.md");
+        assert!(!errors.errors.is_empty());
     }
 
     // ------------------------------------------------------------------------
 
+    #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic(expected = "NotFound")]
-    fn test_read_config_panic() {
-        read_config(String::from("NotFound"));
+    fn test_new_config_linux_credentials_file() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        let secret = "test_credentials_file.txt";
+        let config = "test_credentials_file.yml";
+        fs::write(secret, "file_token\n").unwrap();
+        fs::set_permissions(secret, fs::Permissions::from_mode(0o600)).unwrap();
+        fs::write(config, format!("events:
+  destination: network
+  endpoint:
+    address: http://localhost:8088
+    credentials:
+      token_file: {}
+monitor:
+  - path: /etc
+hashscanner:
+  file: /var/lib/fim/fim.db
+log:
+  file: /var/log/fim/fim.log
+", secret)).unwrap();
+
+        let cfg = AppConfig::new("linux", Some(config)).unwrap();
+        assert_eq!(cfg.endpoint_token, "file_token");
+        assert_eq!(cfg.endpoint_type, "Splunk");
+
+        fs::set_permissions(secret, fs::Permissions::from_mode(0o644)).unwrap();
+        let errors = AppConfig::new("linux", Some(config)).err().unwrap();
+        assert_eq!(errors.errors[0].key, "events.endpoint.credentials.token_file");
+        assert_eq!(errors.errors[0].line, 6);
+        assert!(errors.errors[0].message.contains("too open"));
+
+        fs::remove_file(secret).unwrap();
+        fs::remove_file(config).unwrap();
     }
 
     // ------------------------------------------------------------------------
 
+    #[cfg(target_os = "linux")]
     #[test]
-    #[should_panic(expected = "ScanError")]
-    fn test_read_config_panic_not_config() {
-        read_config(String::from("README.md"));
+    fn test_new_config_linux_include() {
+        let cfg = AppConfig::new("linux", Some("test/unit/config/common/include/config.yml")).unwrap();
+        assert_eq!(cfg.monitor.len(), 3);
+        assert_eq!(cfg.audit.len(), 1);
+        assert_eq!(cfg.engine, String::from("audit"));
+        assert_eq!(cfg.node, String::from("FIM_NODE"));
+        assert_eq!(cfg.events_max_file_size, 256);
+        assert_eq!(cfg.rules.len(), 2);
+        assert_eq!(cfg.includes.len(), 4);
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_new_config_linux_match() {
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_match.yml")).unwrap();
+        assert_eq!(cfg.monitor[0].match_mode, MatchMode::Glob);
+        assert_eq!(cfg.monitor[2].match_mode, MatchMode::Regex);
+        assert_eq!(cfg.monitor[3].match_mode, MatchMode::Substring);
+        assert_eq!(cfg.monitor[0].root(), "/etc");
+        assert_eq!(cfg.monitor[1].root(), "/home");
+        assert_eq!(cfg.monitor[2].root(), "/var/log");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_new_config_linux_match_invalid() {
+        let errors = AppConfig::new("linux", Some("test/unit/config/linux/monitor_match_invalid.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 3);
+        assert_eq!(errors.errors[0].key, "monitor[0].path");
+        assert_eq!(errors.errors[0].line, 10);
+        assert!(errors.errors[0].message.starts_with("invalid glob pattern"));
+        assert_eq!(errors.errors[1].key, "monitor[1].path");
+        assert_eq!(errors.errors[1].message, "pattern must start with a fixed folder to watch");
+        assert_eq!(errors.errors[2].key, "monitor[2].ignore[0]");
+        assert_eq!(errors.errors[2].line, 14);
+        assert!(errors.errors[2].message.starts_with("invalid regular expression"));
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_new_config_linux_integration_condition() {
+        let errors = AppConfig::new("linux", Some("test/unit/config/linux/monitor_integration_condition.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 2);
+        assert_eq!(errors.errors[0].key, "monitor[0].integrations[0].condition[1]");
+        assert_eq!(errors.errors[0].line, 13);
+        assert_eq!(errors.errors[1].key, "monitor[0].integrations[1].condition");
+        assert_eq!(errors.errors[1].line, 17);
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_new_config_linux_errors_report() {
+        let errors = AppConfig::new("linux", Some("test/unit/config/linux/events_credentials_password_none.yml"))
+            .err().unwrap();
+        assert_eq!(errors.errors.len(), 1);
+        assert_eq!(errors.errors[0].key, "events.endpoint.credentials");
+        assert_eq!(errors.errors[0].line, 8);
+        assert!(format!("{}", errors).contains("line 8, column 5: events.endpoint.credentials"));
     }
 
     // ------------------------------------------------------------------------
@@ -1253,7 +2205,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_path_in_monitor() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
         assert!(cfg.path_in("/bin/", "", cfg.monitor.clone()));
         assert!(cfg.path_in("/bin", "", cfg.monitor.clone()));
         assert!(cfg.path_in("/bin/test", "", cfg.monitor.clone()));
@@ -1265,7 +2217,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_path_in_audit() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_allowed.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_allowed.yml")).unwrap();
         assert!(cfg.path_in("/tmp", "", cfg.audit.clone()));
         assert!(cfg.path_in("/tmp/", "", cfg.audit.clone()));
         assert!(cfg.path_in("./", "/tmp", cfg.audit.clone()));
@@ -1279,7 +2231,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_get_index_monitor() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
         assert_eq!(cfg.get_index("/bin/", "", cfg.monitor.clone()), 0);
         assert_eq!(cfg.get_index("./", "/bin", cfg.monitor.clone()), 0);
         assert_eq!(cfg.get_index("/usr/bin/", "", cfg.monitor.clone()), 1);
@@ -1293,7 +2245,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_get_index_audit() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_allowed.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_allowed.yml")).unwrap();
         assert_eq!(cfg.get_index("/tmp", "", cfg.audit.clone()), 0);
         assert_eq!(cfg.get_index("/test", "", cfg.audit.clone()), usize::MAX);
         assert_eq!(cfg.get_index("./", "/tmp", cfg.audit.clone()), 0);
@@ -1304,7 +2256,7 @@ mod tests {
 
     #[test]
     fn test_get_labels() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
         if utils::get_os() == "windows" {
             let labels = cfg.get_labels(0, cfg.monitor.clone());
             assert_eq!(labels[0], "Program Files");
@@ -1325,7 +2277,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_match_ignore_monitor() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
         assert!(cfg.match_ignore(3, "file.swp", cfg.monitor.clone()));
         assert!(!cfg.match_ignore(0, "file.txt", cfg.monitor.clone()));
     }
@@ -1335,7 +2287,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_match_ignore_audit() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_exclude.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_exclude.yml")).unwrap();
         assert!(cfg.match_ignore(0, "file.swp", cfg.audit.clone()));
         assert!(!cfg.match_ignore(0, "file.txt", cfg.audit.clone()));
     }
@@ -1345,25 +2297,57 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_match_exclude() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_exclude.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_exclude.yml")).unwrap();
         assert!(cfg.match_exclude(0, "/tmp/test", cfg.audit.clone()));
         assert!(!cfg.match_exclude(0, "/tmp/another", cfg.audit.clone()));
     }
 
     // ------------------------------------------------------------------------
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_index_match() {
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_match.yml")).unwrap();
+        assert_eq!(cfg.get_index("/etc/nginx.conf", "", cfg.monitor.clone()), 0);
+        assert_eq!(cfg.get_index("/etc/passwd", "", cfg.monitor.clone()), usize::MAX);
+        assert_eq!(cfg.get_index("/home/user/.ssh/authorized_keys", "", cfg.monitor.clone()), 1);
+        assert_eq!(cfg.get_index("/home/user/.bashrc", "", cfg.monitor.clone()), usize::MAX);
+        assert_eq!(cfg.get_index("/var/log/syslog.log", "", cfg.monitor.clone()), 2);
+        assert_eq!(cfg.get_index("/var/log/syslog.log.1", "", cfg.monitor.clone()), usize::MAX);
+        assert!(cfg.path_in("/home/user/.ssh/id_rsa.pub", "", cfg.monitor.clone()));
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_match_modes() {
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_match.yml")).unwrap();
+        assert!(cfg.match_ignore(0, "nginx.conf.swp", cfg.monitor.clone()));
+        assert!(!cfg.match_ignore(0, "my.swp.conf", cfg.monitor.clone()));
+        assert!(cfg.match_exclude(1, "/home/user/.ssh/cache", cfg.monitor.clone()));
+        assert!(cfg.match_exclude(1, "/home/user/.ssh/old/keys", cfg.monitor.clone()));
+        assert!(!cfg.match_exclude(1, "/home/user/.ssh/cached", cfg.monitor.clone()));
+        assert!(cfg.match_ignore(2, "syslog.log.gz", cfg.monitor.clone()));
+        assert!(!cfg.match_ignore(2, "syslog.gz.log", cfg.monitor.clone()));
+        assert!(cfg.match_ignore(3, "my.swp.conf", cfg.monitor.clone()));
+        assert!(cfg.match_allowed(3, "file.txt.bak", cfg.monitor.clone()));
+    }
+
+    // ------------------------------------------------------------------------
+
     #[test]
     fn test_match_allowed() {
         if utils::get_os() == "windows" {
-            let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/windows/monitor_allowed.yml"));
+            let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/windows/monitor_allowed.yml")).unwrap();
             assert!(!cfg.match_allowed(1, "file.swp", cfg.monitor.clone()));
             assert!(cfg.match_allowed(1, "file.txt", cfg.monitor.clone()));
         } else if utils::get_os() == "linux" {
-            let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/monitor_allowed.yml"));
+            let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/monitor_allowed.yml")).unwrap();
             assert!(!cfg.match_allowed(2, "file.swp", cfg.monitor.clone()));
             assert!(cfg.match_allowed(2, "file.txt", cfg.monitor.clone()));
 
-            let cfg_audit = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_allowed.yml"));
+            let cfg_audit = AppConfig::new(&utils::get_os(), Some("test/unit/config/linux/audit_allowed.yml")).unwrap();
             assert!(!cfg_audit.match_allowed(0, "file.swp", cfg_audit.audit.clone()));
             assert!(cfg_audit.match_allowed(0, "file.txt", cfg_audit.audit.clone()));
         }
@@ -1374,14 +2358,11 @@ mod tests {
     #[test]
     fn test_get_integrations() {
         let os = utils::get_os();
-        let cfg = AppConfig::new(&os,
+        let cfg = AppConfig::new(os,
             Some(format!("test/unit/config/{}/monitor_integration.yml", os)
                 .as_str())
-        );
-        if os == "windows" {
-            let integrations = cfg.get_integrations(2, cfg.monitor.clone());
-            assert_eq!(integrations.len(), 1);
-        }else if os == "macos"{
+        ).unwrap();
+        if os == "windows" || os == "macos" {
             let integrations = cfg.get_integrations(2, cfg.monitor.clone());
             assert_eq!(integrations.len(), 1);
         }else{
@@ -1398,8 +2379,28 @@ mod tests {
 
     #[test]
     fn test_new_config_watcher() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_watcher.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/events_watcher.yml")).unwrap();
         assert_eq!(cfg.events_watcher, "Poll");
+        assert_eq!(cfg.poll_interval, 30);
+        assert!(!cfg.poll_compare_contents);
+        assert_eq!(cfg.events_watch_limit, "Alert");
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_watcher_hybrid.yml")).unwrap();
+        assert_eq!(cfg.events_watcher, "Hybrid");
+        assert_eq!(cfg.poll_interval, 5);
+        assert!(cfg.poll_compare_contents);
+        assert_eq!(cfg.events_watch_limit, "Poll");
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_new_config_watcher_invalid() {
+        let errors = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_watcher_invalid.yml"))
+            .err().unwrap();
+        let keys: Vec<&str> = errors.errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["events.watcher", "events.poll.interval", "events.watch_limit"]);
+        assert_eq!(errors.errors[0].message, "expected one of recommended, poll, hybrid or fanotify");
     }
 
 }
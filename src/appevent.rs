@@ -1,16 +1,11 @@
 // Copyright (C) 2024, Achiefs.
 
-use crate::appconfig;
 use crate::appconfig::*;
 use crate::ruleset::*;
+use crate::output;
+use crate::output::Envelope;
 
-use log::*;
 use serde::Serialize;
-use serde_json::json;
-use reqwest::Client;
-use std::fs::OpenOptions;
-use std::time::Duration;
-use std::io::Write;
 
 #[derive(Clone, Serialize, Debug)]
 pub struct AppEvent {
@@ -28,115 +23,15 @@ pub struct AppEvent {
 
 impl AppEvent {
 
-  fn to_json(&self) -> String { serde_json::to_string(self).unwrap() }
-
-  // ------------------------------------------------------------------------
-
-  // Function to write the received events to file
-  fn log(&self, cfg: AppConfig) {
-    let file = cfg.events_lock.lock().unwrap();
-    let mut events_file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(file.as_str())
-        .expect("(log) Unable to open events log file.");
-
-    match writeln!(events_file, "{}", self.to_json() ) {
-        Ok(_d) => debug!("Event log written"),
-        Err(e) => error!("Event could not be written, Err: [{}]", e)
-    }
+  pub fn envelope(&self) -> Envelope {
+    Envelope::new(&self.id, serde_json::to_value(self).unwrap()).with_kind("app")
   }
 
   // ------------------------------------------------------------------------
 
-  // Function to send events through network
-  async fn send(&self, cfg: AppConfig) {
-        use time::OffsetDateTime;
-        let current_date = OffsetDateTime::now_utc();
-        let index = format!("fim-{}-{}-{}", current_date.year(), current_date.month() as u8, current_date.day() );
-        
-        // Splunk endpoint integration
-        if cfg.endpoint_type == "Splunk" {
-            let data = json!({
-                "source": self.node.clone(),
-                "sourcetype": "_json",
-                "event": json!({
-                    "timestamp": self.timestamp.clone(),
-                    "hostname": self.hostname.clone(),
-                    "node": self.node.clone(),
-                    "fpid": self.fpid.clone(),
-                    "version": self.version.clone(),
-                    "message": self.message.clone(),
-                    "system": self.system.clone()
-                }),
-                "index": "fim_events"
-            });
-            debug!("Sending received event to Splunk integration, event: {}", data);
-            let request_url = format!("{}/services/collector/event", cfg.endpoint_address);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .header("Authorization", format!("Splunk {}", cfg.endpoint_token))
-                .json(&data)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        // Elastic endpoint integration
-        } else {
-            let data = json!({
-                "timestamp": self.timestamp.clone(),
-                "hostname": self.hostname.clone(),
-                "node": self.node.clone(),
-                "fpid": self.fpid.clone(),
-                "version": self.version.clone(),
-                "message": self.message.clone(),
-                "system": self.system.clone()
-            });
-            let request_url = format!("{}/{}/_doc/{}", cfg.endpoint_address, index, self.id);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .basic_auth(cfg.endpoint_user, Some(cfg.endpoint_pass))
-                .json(&data)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        }
-
-    }
-
-    // ------------------------------------------------------------------------
-
-    // Function to manage event destination
-    pub async fn process(&self, cfg: AppConfig, _ruleset: Ruleset) {
-        route(self, cfg.clone()).await;
-    }
-}
-
-// ----------------------------------------------------------------------------
-
-pub async fn route(event: &AppEvent, cfg: AppConfig) {
-  match cfg.get_events_destination().as_str() {
-      appconfig::BOTH_MODE => {
-          event.log(cfg.clone());
-          event.send(cfg).await;
-      },
-      appconfig::NETWORK_MODE => {
-          event.send(cfg).await;
-      },
-      _ => event.log(cfg.clone())
+  // Function to manage event destination
+  pub async fn process(&self, cfg: AppConfig, _ruleset: Ruleset) {
+    output::route(&self.envelope(), &cfg.get_events_destination(), &cfg).await;
   }
 }
 
@@ -189,8 +84,8 @@ mod tests {
     #[test]
     fn test_send() {
         let evt = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        block_on( evt.send(cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
@@ -198,8 +93,8 @@ mod tests {
     #[test]
     fn test_send_splunk() {
         let evt = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml"));
-        block_on( evt.send(cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+        block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
@@ -207,8 +102,8 @@ mod tests {
     #[test]
     fn test_process() {
         let event = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        let ruleset = Ruleset::new(&utils::get_os(), None);  
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap();  
 
         block_on(event.process(cfg, ruleset));
     }
@@ -216,39 +111,39 @@ mod tests {
     // ------------------------------------------------------------------------
 
     #[test]
-    fn test_to_json() {
+    fn test_envelope() {
         let expected = "{\
-            \"id\":\"Test_id\",\
-            \"timestamp\":\"Timestamp\",\
+            \"fpid\":0,\
             \"hostname\":\"Hostname\",\
-            \"node\":\"FIM\",\
-            \"version\":\"x.x.x\",\
+            \"id\":\"Test_id\",\
             \"message\":\"TEST\",\
-            \"fpid\":0,\
-            \"system\":\"test\"\
+            \"node\":\"FIM\",\
+            \"system\":\"test\",\
+            \"timestamp\":\"Timestamp\",\
+            \"version\":\"x.x.x\"\
         }";
-        assert_eq!(create_test_event().to_json(), expected);
+        assert_eq!(create_test_event().envelope().to_json(), expected);
     }
 
     // ------------------------------------------------------------------------
 
     #[test]
     fn test_log() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log.yml")).unwrap();
         let filename = String::from("test_log.json");
         let evt = create_test_event();
 
-        evt.log(cfg.clone());
+        block_on( output::route(&evt.envelope(), FILE_MODE, &cfg) );
         let contents = fs::read_to_string(filename.clone());
         let expected = "{\
-            \"id\":\"Test_id\",\
-            \"timestamp\":\"Timestamp\",\
+            \"fpid\":0,\
             \"hostname\":\"Hostname\",\
-            \"node\":\"FIM\",\
-            \"version\":\"x.x.x\",\
+            \"id\":\"Test_id\",\
             \"message\":\"TEST\",\
-            \"fpid\":0,\
-            \"system\":\"test\"\
+            \"node\":\"FIM\",\
+            \"system\":\"test\",\
+            \"timestamp\":\"Timestamp\",\
+            \"version\":\"x.x.x\"\
         }\n";
         assert_eq!(contents.unwrap(), expected);
         remove_test_file(filename.clone());
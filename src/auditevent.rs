@@ -1,20 +1,17 @@
 // Copyright (C) 2022, Achiefs.
 
 use std::fmt;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::time::Duration;
-use log::*;
-use serde_json::{json, to_string};
-use reqwest::Client;
+use serde_json::json;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::appconfig;
 use crate::appconfig::*;
 use crate::ruleset::*;
 use crate::utils;
 use crate::hash;
+use crate::output;
+use crate::output::Envelope;
 
 // ----------------------------------------------------------------------------
 
@@ -136,7 +133,7 @@ impl Event {
             command,
             timestamp: clean_timestamp,
             hostname: utils::get_hostname(),
-            node: cfg.node,
+            node: cfg.node.clone(),
             version: String::from(appconfig::VERSION),
             labels,
             operation: utils::get_field(path.clone(), "nametype"),
@@ -145,8 +142,8 @@ impl Event {
             size: utils::get_file_size(path["name"].clone().as_str()),
             checksum: hash::get_checksum(
                 format!("{}/{}", parent["name"].clone(), path["name"].clone()),
-                cfg.events_max_file_checksum,
-                cfg.checksum_algorithm),
+                cfg.get_max_file_checksum(index, cfg.audit.clone()),
+                cfg.get_checksum_algorithm(index, cfg.audit.clone())),
             fpid: utils::get_pid(),
             system: String::from(utils::get_os()),
 
@@ -327,90 +324,17 @@ impl Event {
 
     // ------------------------------------------------------------------------
 
-    // Get formatted string with all required data
-    fn format_json(&self) -> String { to_string(&self.get_json()).unwrap() }
-
-    // ------------------------------------------------------------------------
-
-    // Function to write the received events to file
-    pub fn log(&self, cfg: AppConfig){
-        let file = cfg.events_lock.lock().unwrap();
-        let mut events_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(file.as_str())
-            .expect("(auditevent::log) Unable to open events log file.");
-
-            match writeln!(events_file, "{}", self.format_json()) {
-                Ok(_d) => debug!("Audit event log written"),
-                Err(e) => error!("Audit event could not be written, Err: [{}]", e)
-            };
-    }
-
-    // ------------------------------------------------------------------------
-
-    // Function to send events through network
-    pub async fn send(&self, index: String, cfg: AppConfig) {
-        let event = self.get_json();
-        
-        // Splunk endpoint integration
-        if cfg.endpoint_type == "Splunk" {
-            let data = json!({
-                "source": self.node.clone(),
-                "sourcetype": "_json",
-                "event": event,
-                "index": "fim_events"
-            });
-            debug!("Sending received event to Splunk integration, event: {}", data);
-            let request_url = format!("{}/services/collector/event", cfg.endpoint_address);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .header("Authorization", format!("Splunk {}", cfg.endpoint_token))
-                .json(&data)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        // Elastic endpoint integration
-        } else {
-            let request_url = format!("{}/{}/_doc/{}", cfg.endpoint_address, index, self.id);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .basic_auth(cfg.endpoint_user, Some(cfg.endpoint_pass))
-                .json(&event)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        }
+    pub fn envelope(&self) -> Envelope {
+        // Relative paths are resolved from the process working directory
+        let path = Path::new(&self.cwd).join(&self.path).join(&self.file);
+        Envelope::new(&self.id, self.get_json()).with_kind("audit").with_path(&path.to_string_lossy())
     }
 
     // ------------------------------------------------------------------------
 
     // Function to manage event destination
-    pub async fn process(&self, destination: &str, index_name: String, cfg: AppConfig, ruleset: Ruleset){
-        match destination {
-            appconfig::BOTH_MODE => {
-                self.log(cfg.clone());
-                self.send(index_name, cfg.clone()).await;
-            },
-            appconfig::NETWORK_MODE => {
-                self.send(index_name, cfg.clone()).await;
-            },
-            _ => self.log(cfg.clone())
-        }
+    pub async fn process(&self, destination: &str, cfg: AppConfig, ruleset: Ruleset){
+        output::route(&self.envelope(), destination, &cfg).await;
         let filepath = PathBuf::from(self.path.clone());
         ruleset.match_rule(cfg, filepath.join(self.file.clone()), self.id.clone()).await;
     }
@@ -591,7 +515,7 @@ mod tests {
     fn test_from() {
         if utils::get_os() == "linux" {
             let cfg = AppConfig::new(&utils::get_os(),
-                Some("test/unit/config/linux/audit_from_test.yml"));
+                Some("test/unit/config/linux/audit_from_test.yml")).unwrap();
             let syscall = HashMap::<String, String>::from([
                 (String::from("syscall"), String::from("syscall")),
                 (String::from("ppid"), String::from("ppid")),
@@ -878,8 +802,8 @@ mod tests {
     // ------------------------------------------------------------------------
 
     #[test]
-    fn test_format_json() {
-        let json = create_test_event().format_json();
+    fn test_envelope() {
+        let json = create_test_event().envelope().to_json();
         let string = String::from("{\"a0\":\"A0\",\"a1\":\"A1\",\"a2\":\"A2\",\
             \"a3\":\"A3\",\"arch\":\"ARCH\",\"auid\":\"AUID\",\"cap_fe\":\"CAP_FE\",\
             \"cap_fi\":\"CAP_FI\",\"cap_fp\":\"CAP_FP\",\"cap_frootid\":\"CAP_FROOTID\",\
@@ -903,10 +827,10 @@ mod tests {
 
     #[test]
     fn test_log() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log_auditevent.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log_auditevent.yml")).unwrap();
         let filename = "test_auditevent.json";
         let event = create_test_event();
-        event.log(cfg.clone());
+        block_on( output::route(&event.envelope(), FILE_MODE, &cfg) );
 
         let expected = "{\"a0\":\"A0\",\"a1\":\"A1\",\"a2\":\"A2\",\"a3\":\"A3\",\
             \"arch\":\"ARCH\",\"auid\":\"AUID\",\"cap_fe\":\"CAP_FE\",\
@@ -938,8 +862,8 @@ mod tests {
     #[test]
     fn test_send() {
         let event = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        block_on( event.send(String::from("test"), cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        block_on( output::route(&event.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
@@ -947,21 +871,21 @@ mod tests {
     #[test]
     fn test_send_splunk() {
         let event = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml"));
-        block_on( event.send(String::from("test"), cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+        block_on( output::route(&event.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
 
     #[test]
     fn test_process() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        let ruleset = Ruleset::new(&utils::get_os(), None);  
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap();  
         let event = create_test_event();
 
-        block_on(event.process(appconfig::NETWORK_MODE, String::from("test"), cfg.clone(), ruleset.clone()));
-        block_on(event.process(appconfig::FILE_MODE, String::from("test2"), cfg.clone(), ruleset.clone()));
-        block_on(event.process(appconfig::BOTH_MODE, String::from("test3"), cfg.clone(), ruleset.clone()));
+        block_on(event.process(appconfig::NETWORK_MODE, cfg.clone(), ruleset.clone()));
+        block_on(event.process(appconfig::FILE_MODE, cfg.clone(), ruleset.clone()));
+        block_on(event.process(appconfig::BOTH_MODE, cfg.clone(), ruleset.clone()));
     }
 
     // ------------------------------------------------------------------------
@@ -0,0 +1,284 @@
+// Copyright (C) 2026, Achiefs.
+
+// To chain the events file records and verify the chain
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+
+use crate::appconfig::AppConfig;
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// Hash linked by the first record of a chain
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// ----------------------------------------------------------------------------
+
+/// Sequence number and hash of a chained record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    pub seq: u64,
+    pub hash: String
+}
+
+impl Link {
+    /// Link before the first record of a chain.
+    pub fn genesis() -> Self {
+        Link { seq: 0, hash: String::from(GENESIS) }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Link of the record following this one.
+    pub fn next(&self, record: &str, key: Option<&[u8]>) -> Self {
+        Link { seq: self.seq + 1, hash: get_hash(key, &self.hash, self.seq + 1, record) }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// HMAC-SHA256 as described in RFC 2104, as hex
+fn get_hmac(key: &[u8], message: &[u8]) -> String {
+    let mut block = [0u8; 64];
+    match key.len() > block.len() {
+        true => block[..32].copy_from_slice(&Sha256::digest(key)[..]),
+        false => block[..key.len()].copy_from_slice(key)
+    }
+    let inner = Sha256::new().chain_update(block.map(|b| b ^ 0x36)).chain_update(message).finalize();
+    hex::encode(Sha256::new().chain_update(block.map(|b| b ^ 0x5c)).chain_update(&inner[..]).finalize())
+}
+
+// ----------------------------------------------------------------------------
+
+/// Hash of a record, covering the previous hash and its sequence number so
+/// records can't be removed or moved without breaking the chain.
+pub fn get_hash(key: Option<&[u8]>, previous: &str, seq: u64, record: &str) -> String {
+    let message = format!("{}\n{}\n{}", previous, seq, record);
+    match key {
+        Some(key) => get_hmac(key, message.as_bytes()),
+        None => hex::encode(Sha256::digest(message.as_bytes()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Key of the chain, None when `events.chain.key` is not set.
+pub fn get_key(cfg: &AppConfig) -> Option<&[u8]> {
+    match cfg.chain_key.as_str() {
+        "Not_defined" => None,
+        key => Some(key.as_bytes())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Record line with its link. JSON objects get the `seq` and `hash` fields,
+/// text formats the same attributes with their separator, tabs for LEEF.
+pub fn seal(record: &str, link: &Link) -> String {
+    match record.strip_suffix('}').filter(|_| record.starts_with('{')) {
+        Some("{") => format!("{{\"seq\":{},\"hash\":\"{}\"}}", link.seq, link.hash),
+        Some(fields) => format!("{},\"seq\":{},\"hash\":\"{}\"}}", fields, link.seq, link.hash),
+        None => {
+            let separator = if record.starts_with("LEEF:") { '\t' } else { ' ' };
+            format!("{}{}seq={}{}hash={}", record, separator, link.seq, separator, link.hash)
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Record and link of a sealed line, None if the line carries no link.
+pub fn open(line: &str) -> Option<(String, Link)> {
+    let (rest, hash, record) = match line.strip_suffix("\"}").filter(|_| line.starts_with('{')) {
+        Some(rest) => {
+            let (rest, hash) = rest.rsplit_once(",\"hash\":\"")?;
+            let (fields, seq) = rest.rsplit_once("\"seq\":")?;
+            let record = match fields {
+                "{" => String::from("{}"),
+                fields => format!("{}}}", fields.strip_suffix(',')?)
+            };
+            (seq, hash, record)
+        },
+        None => {
+            let (rest, hash) = line.rsplit_once("hash=")?;
+            let separator = rest.chars().last().filter(|c| *c == ' ' || *c == '\t')?;
+            let (record, seq) = rest[..rest.len() - 1].rsplit_once(&format!("{}seq=", separator))?;
+            (seq, hash, String::from(record))
+        }
+    };
+    let seq = rest.parse().ok()?;
+    match hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => Some((record, Link { seq, hash: String::from(hash) })),
+        false => None
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// File keeping the last link, it isn't rotated with the events file
+fn get_state_file(events_file: &str) -> String {
+    format!("{}.chain", events_file)
+}
+
+// ----------------------------------------------------------------------------
+
+// Link saved in the state file, if any
+fn read_state(events_file: &str) -> Option<Link> {
+    let state = fs::read_to_string(get_state_file(events_file)).ok()?;
+    let (seq, hash) = state.trim().split_once(' ')?;
+    Some(Link { seq: seq.parse().ok()?, hash: String::from(hash) })
+}
+
+// ----------------------------------------------------------------------------
+
+/// Last link written, read from the state file or, if missing, from the last
+/// sealed record of the events file. Call it holding the events lock.
+pub fn get_last(events_file: &str) -> Link {
+    if let Some(link) = read_state(events_file) {
+        return link;
+    }
+    match File::open(events_file) {
+        Ok(file) => BufReader::new(file).lines().map_while(Result::ok)
+            .filter_map(|line| open(&line).map(|(_, link)| link))
+            .last().unwrap_or(Link::genesis()),
+        Err(_) => Link::genesis()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Saves the last link written, so the chain goes on in the new events file
+/// after a rotation or a restart.
+pub fn save(events_file: &str, link: &Link) -> Result<(), String> {
+    let path = get_state_file(events_file);
+    fs::write(&path, format!("{} {}\n", link.seq, link.hash))
+        .map_err(|e| format!("unable to write chain state file '{}', {}", path, e))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Checks the chain of records read in order, records following a broken
+/// link start a new check so every problem is reported.
+pub struct Verifier<'a> {
+    key: Option<&'a [u8]>,
+    pub last: Option<Link>,
+    pub records: u64,
+    pub errors: Vec<String>
+}
+
+impl<'a> Verifier<'a> {
+    pub fn new(key: Option<&'a [u8]>) -> Self {
+        Verifier { key, last: None, records: 0, errors: Vec::new() }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Checks the next line, `position` is used to report its errors.
+    pub fn check(&mut self, position: &str, line: &str) {
+        let (record, link) = match open(line) {
+            Some(opened) => opened,
+            None => {
+                self.errors.push(format!("{}: record without sequence number and hash", position));
+                return;
+            }
+        };
+        self.records += 1;
+        // The first record verified only links the genesis hash when it starts the chain
+        let previous = match &self.last {
+            Some(last) => last.clone(),
+            None if link.seq == 1 => Link::genesis(),
+            None => Link { seq: link.seq.saturating_sub(1), hash: String::new() }
+        };
+        // Records out of place don't move the chain, the following ones are checked against the last in order
+        if link.seq <= previous.seq {
+            self.errors.push(format!("{}: record {} found after record {}, records were reordered or duplicated",
+                position, link.seq, previous.seq));
+            return;
+        }
+        if link.seq > previous.seq + 1 {
+            self.errors.push(format!("{}: records {} to {} are missing", position, previous.seq + 1, link.seq - 1));
+        } else if !previous.hash.is_empty() && get_hash(self.key, &previous.hash, link.seq, &record) != link.hash {
+            self.errors.push(format!("{}: record {} doesn't match its hash, it was modified or the key is wrong",
+                position, link.seq));
+        }
+        self.last = Some(link);
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Checks the last record verified is the last one written.
+    pub fn check_end(&mut self, written: &Link) {
+        let last = self.last.as_ref().map(|link| link.seq).unwrap_or(0);
+        if written.seq > last {
+            self.errors.push(format!("records {} to {} are missing at the end of the events file",
+                last + 1, written.seq));
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Lines of an events file or of a tar.gz archive created by the rotator
+fn read_lines(path: &str) -> Result<Vec<String>, String> {
+    let error = |e: std::io::Error| format!("unable to read '{}', {}", path, e);
+    let mut text = String::new();
+    #[cfg(not(windows))]
+    if path.ends_with(".tar.gz") {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(File::open(path).map_err(error)?));
+        let mut entry = archive.entries().and_then(|mut entries| entries.next().transpose()).map_err(error)?
+            .ok_or(format!("archive '{}' is empty", path))?;
+        entry.read_to_string(&mut text).map_err(error)?;
+        return Ok(text.lines().map(String::from).collect());
+    }
+    File::open(path).and_then(|mut file| file.read_to_string(&mut text)).map_err(error)?;
+    Ok(text.lines().map(String::from).collect())
+}
+
+// ----------------------------------------------------------------------------
+
+/// Verify the chain of the events file, `fim verify-events [-c config.yml]
+/// [file...]`. Files are read in the given order, archives first, and the
+/// events file of the configuration is used when none is given.
+pub fn verify_events(args: &[String]) -> i32 {
+    let (config, files) = match args.first().map(|a| a.as_str()) {
+        Some("-c") | Some("--config") => (args.get(1).map(|p| p.as_str()), args.get(2..).unwrap_or_default()),
+        _ => (None, args)
+    };
+    let cfg = match AppConfig::new(utils::get_os(), config) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            println!("[ERROR] {}", e);
+            return 1;
+        }
+    };
+    let files = match files.is_empty() {
+        true => vec![cfg.events_file.clone()],
+        false => files.to_vec()
+    };
+    let mut verifier = Verifier::new(get_key(&cfg));
+    for file in files.iter() {
+        match read_lines(file) {
+            Ok(lines) => lines.iter().enumerate().filter(|(_, line)| !line.is_empty())
+                .for_each(|(i, line)| verifier.check(&format!("{}:{}", file, i + 1), line)),
+            Err(e) => verifier.errors.push(e)
+        }
+    }
+    // Records removed from the end are only noticed with the saved state
+    if files.last() == Some(&cfg.events_file) {
+        if let Some(written) = read_state(&cfg.events_file) {
+            verifier.check_end(&written);
+        }
+    }
+    for error in verifier.errors.iter() {
+        println!("[ERROR] {}", error);
+    }
+    match verifier.errors.is_empty() {
+        true => {
+            println!("[INFO] Chain is valid, {} record(s) verified.", verifier.records);
+            0
+        },
+        false => 1
+    }
+}
@@ -0,0 +1,142 @@
+use super::*;
+use serde_json::json;
+use tokio_test::block_on;
+
+use crate::output::{Envelope, FileSink, OutputSink};
+
+// ----------------------------------------------------------------------------
+
+fn create_test_config() -> AppConfig {
+    AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_chain.yml")).unwrap()
+}
+
+// ----------------------------------------------------------------------------
+
+// Lines of a chain of `count` records
+fn create_test_lines(count: u64, key: Option<&[u8]>) -> Vec<String> {
+    let mut link = Link::genesis();
+    (1..=count).map(|i| {
+        let record = format!("{{\"id\":\"{}\"}}", i);
+        link = link.next(&record, key);
+        seal(&record, &link)
+    }).collect()
+}
+
+// ----------------------------------------------------------------------------
+
+// Errors found verifying the given lines
+fn verify(lines: &[String], key: Option<&[u8]>) -> Vec<String> {
+    let mut verifier = Verifier::new(key);
+    for (i, line) in lines.iter().enumerate() {
+        verifier.check(&format!("events.json:{}", i + 1), line);
+    }
+    verifier.errors
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the keyed hash matches the RFC 4231 test vectors
+fn test_get_hmac() {
+    assert_eq!(get_hmac(b"Jefe", b"what do ya want for nothing?"),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    assert_eq!(get_hmac(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First"),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+    assert_ne!(get_hash(None, GENESIS, 1, "{}"), get_hash(Some(b"key"), GENESIS, 1, "{}"));
+    assert_ne!(get_hash(None, GENESIS, 1, "{}"), get_hash(None, GENESIS, 2, "{}"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check sealed lines give back the record and its link in every format
+fn test_seal_open() {
+    let link = Link::genesis().next("record", None);
+    for record in ["{\"id\":\"1\",\"path\":\"/etc\"}", "{}", "CEF:0|Achiefs|FIM|0.6.4|1|Create|3|cat=monitor",
+        "LEEF:1.0|Achiefs|FIM|0.6.4|1|cat=monitor\tsev=3"] {
+        assert_eq!(open(&seal(record, &link)), Some((String::from(record), link.clone())));
+    }
+    let line = seal("{\"id\":\"1\"}", &link);
+    assert!(serde_json::from_str::<serde_json::Value>(&line).is_ok());
+    assert_eq!(line, format!("{{\"id\":\"1\",\"seq\":1,\"hash\":\"{}\"}}", link.hash));
+    assert!(seal("LEEF:1.0|Achiefs|FIM", &link).ends_with(&format!("\tseq=1\thash={}", link.hash)));
+    assert_eq!(open("{\"id\":\"1\"}"), None);
+    assert_eq!(open("CEF:0|Achiefs|FIM seq=1 hash=abc"), None);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check an untouched chain is valid with its key only
+fn test_verifier() {
+    let lines = create_test_lines(5, Some(b"key"));
+    assert!(verify(&lines, Some(b"key")).is_empty());
+    assert_eq!(verify(&lines, None).len(), 5);
+    // Verification may start at an archived record
+    assert!(verify(&lines[2..], Some(b"key")).is_empty());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check removed, moved and modified records are reported
+fn test_verifier_errors() {
+    let lines = create_test_lines(6, None);
+    let mut removed = lines.clone();
+    removed.remove(2);
+    assert_eq!(verify(&removed, None), vec!["events.json:3: records 3 to 3 are missing"]);
+
+    let mut moved = lines.clone();
+    moved.swap(2, 3);
+    assert_eq!(verify(&moved, None), vec!["events.json:3: records 3 to 3 are missing",
+        "events.json:4: record 3 found after record 4, records were reordered or duplicated"]);
+
+    let mut modified = lines.clone();
+    modified[1] = modified[1].replace("\"id\":\"2\"", "\"id\":\"7\"");
+    assert_eq!(verify(&modified, None),
+        vec!["events.json:2: record 2 doesn't match its hash, it was modified or the key is wrong"]);
+
+    let mut unsealed = lines.clone();
+    unsealed.insert(1, String::from("{\"id\":\"7\"}"));
+    assert_eq!(verify(&unsealed, None), vec!["events.json:2: record without sequence number and hash"]);
+
+    let mut verifier = Verifier::new(None);
+    verifier.check("events.json:1", &lines[0]);
+    verifier.check_end(&Link { seq: 3, hash: String::new() });
+    assert_eq!(verifier.errors, vec!["records 2 to 3 are missing at the end of the events file"]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the chain goes on after the events file is truncated by a rotation
+fn test_file_sink_chain() {
+    let cfg = create_test_config();
+    let sink = FileSink::new(&cfg);
+    let envelope = Envelope::new("Test_id", json!({ "id": "Test_id", "node": "FIM" }));
+    block_on(sink.write(&envelope)).unwrap();
+    let archived = fs::read_to_string("test_chain.json").unwrap();
+    fs::write("test_chain.json", "").unwrap();
+    block_on(sink.write(&envelope)).unwrap();
+    block_on(sink.write(&envelope)).unwrap();
+    let current = fs::read_to_string("test_chain.json").unwrap();
+    assert_eq!(get_last("test_chain.json").seq, 3);
+
+    let mut verifier = Verifier::new(get_key(&cfg));
+    for line in archived.lines().chain(current.lines()) {
+        verifier.check("test_chain.json", line);
+    }
+    verifier.check_end(&get_last("test_chain.json"));
+    assert!(verifier.errors.is_empty());
+    assert_eq!(verifier.records, 3);
+    let args = [String::from("-c"), String::from("test/unit/config/common/test_chain.yml")];
+    assert_eq!(verify_events(&args), 0);
+    fs::write("test_chain.json", current.replace("Test_id", "Other_id")).unwrap();
+    assert_eq!(verify_events(&args), 1);
+
+    // Without the state file the last record of the events file is used
+    fs::remove_file("test_chain.json.chain").unwrap();
+    assert_eq!(get_last("test_chain.json").seq, 3);
+    fs::remove_file("test_chain.json").unwrap();
+    assert_eq!(get_last("test_chain.json"), Link::genesis());
+}
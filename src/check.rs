@@ -0,0 +1,96 @@
+// Copyright (C) 2026, Achiefs.
+
+// To check monitored paths existence
+use std::path::Path;
+
+use crate::appconfig::AppConfig;
+use crate::chain;
+use crate::configfile::*;
+use crate::ruleset;
+use crate::ruleset::Ruleset;
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// ----------------------------------------------------------------------------
+
+/// Dispatch the validation subcommands, `fim check-config [config.yml]`,
+/// `fim check-rules [rules.yml]` and `fim verify-events [file...]`. Returns the exit code of the executed
+/// subcommand or None if arguments don't request any.
+pub fn run(args: &[String]) -> Option<i32> {
+    let path = args.get(2).map(|p| p.as_str());
+    match args.get(1).map(|a| a.as_str()) {
+        Some("check-config") => Some(check_config(path)),
+        Some("check-rules") => Some(check_rules(path)),
+        Some("verify-events") => Some(chain::verify_events(&args[2..])),
+        _ => None
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Load config.yml as the service would and check every monitored path exists.
+pub fn check_config(path: Option<&str>) -> i32 {
+    let cfg = match AppConfig::new(utils::get_os(), path) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            println!("[ERROR] {}", e);
+            return 1;
+        }
+    };
+    // Read again to get the position of the monitored paths
+    let mut errors = match read_config(cfg.path.clone()) {
+        Ok(file) => check_paths(&cfg, &file),
+        Err(e) => e.errors
+    };
+    // Rules from configuration files can't reuse the ids of the ruleset loaded with them
+    if !cfg.rules.is_empty() {
+        let rules_path = ruleset::get_config_ruleset_path(utils::get_os(), path);
+        if let Err(e) = Ruleset::new(utils::get_os(), Some(&rules_path)).and_then(|mut ruleset| ruleset.merge(&cfg.rules)) {
+            errors.extend(e.errors);
+        }
+    }
+    match errors.is_empty() {
+        true => {
+            println!("[INFO] Configuration file '{}' is valid.", cfg.path);
+            0
+        },
+        false => {
+            println!("[ERROR] {}", ConfigErrors::new(&cfg.path, errors));
+            1
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Load rules.yml as the service would, rule ids and regexes are checked on load.
+pub fn check_rules(path: Option<&str>) -> i32 {
+    match Ruleset::new(utils::get_os(), path) {
+        Ok(ruleset) => {
+            println!("[INFO] Ruleset is valid, {} rule(s) loaded.", ruleset.rules.len());
+            0
+        },
+        Err(e) => {
+            println!("[ERROR] {}", e);
+            1
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Returns an error for each monitor or audit path not found in the filesystem.
+pub fn check_paths(cfg: &AppConfig, file: &ConfigFile) -> Vec<ConfigError> {
+    let mut errors = Vec::new();
+    for (section, paths) in [("monitor", &cfg.monitor), ("audit", &cfg.audit)] {
+        for (index, element) in paths.iter().enumerate() {
+            if !Path::new(&element.root()).exists() {
+                errors.push(file.error(&format!("{}[{}].path", section, index),
+                    &format!("path '{}' does not exist", element.root())));
+            }
+        }
+    }
+    errors
+}
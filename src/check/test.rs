@@ -0,0 +1,50 @@
+use super::*;
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check arguments without a validation subcommand are ignored
+fn test_run_no_subcommand() {
+    assert_eq!(run(&[String::from("fim")]), None);
+    assert_eq!(run(&[String::from("fim"), String::from("--foreground")]), None);
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(target_os = "linux")]
+#[test]
+/// Check a valid configuration and ruleset return a success exit code
+fn test_run() {
+    let args = |cmd: &str, path: &str| vec![String::from("fim"), String::from(cmd), String::from(path)];
+    assert_eq!(run(&args("check-config", "config/linux/config.yml")), Some(0));
+    assert_eq!(run(&args("check-rules", "config/linux/rules.yml")), Some(0));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check invalid files return a failure exit code
+fn test_run_errors() {
+    let args = |cmd: &str| vec![String::from("fim"), String::from(cmd), String::from("NotFound")];
+    assert_eq!(run(&args("check-config")), Some(1));
+    assert_eq!(run(&args("check-rules")), Some(1));
+    assert_eq!(run(&args("verify-events")), Some(1));
+    assert_eq!(check_config(Some("test/unit/config/linux/monitor_integration_condition.yml")), 1);
+    // Rule 5 is only defined in the rules.yml next to this configuration
+    assert_eq!(check_config(Some("test/unit/config/common/check_rules/config.yml")), 1);
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(target_os = "linux")]
+#[test]
+/// Check missing monitored paths are reported with their position
+fn test_check_paths() {
+    let path = "test/unit/config/linux/monitor_integration.yml";
+    let cfg = AppConfig::new("linux", Some(path)).unwrap();
+    let errors = check_paths(&cfg, &read_config(String::from(path)).unwrap());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].key, "monitor[3].path");
+    assert_eq!(errors[0].message, "path '/etc2' does not exist");
+    assert_eq!((errors[0].line, errors[0].column), (27, 5));
+}
@@ -0,0 +1,1072 @@
+// Copyright (C) 2026, Achiefs.
+
+// Sections accepted at the top level of config.yml
+const SECTIONS: [&str; 8] = ["node", "events", "monitor", "audit", "hashscanner", "log", "rules", "include"];
+// Folder next to config.yml where drop-in files are loaded from
+const DROPIN_DIR: &str = "conf.d";
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use yaml_rust::parser::{Event as YamlEvent, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::hash::ShaType;
+use crate::integration::Integration;
+use crate::matcher::{self, MatchMode, Matcher};
+
+#[cfg(test)]
+mod test;
+
+// ----------------------------------------------------------------------------
+
+/// Line and column of every node, indexed by its dotted key.
+pub type Locations = HashMap<String, (usize, usize)>;
+
+/// Single problem found while loading the configuration.
+/// `key` is the dotted path of the offending node, line and column are 1-based
+/// and set to 0 when the position is unknown. `file` is only set when the
+/// error comes from an included file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigError {
+    pub file: String,
+    pub key: String,
+    pub message: String,
+    pub line: usize,
+    pub column: usize
+}
+
+/// All the errors found in a configuration file, reported at once.
+pub struct ConfigErrors {
+    pub file: String,
+    pub errors: Vec<ConfigError>
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CredentialsConfig {
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub password_file: Option<String>,
+    pub token: Option<String>,
+    pub token_file: Option<String>
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EndpointConfig {
+    pub address: Option<String>,
+    pub insecure: Option<bool>,
+    pub ca_file: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub pins: Option<Vec<String>>,
+    pub credentials: Option<CredentialsConfig>,
+    pub splunk: Option<SplunkConfig>,
+    pub elastic: Option<ElasticConfig>
+}
+
+/// Elasticsearch index and lifecycle settings.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticConfig {
+    pub index: Option<String>,
+    pub data_stream: Option<bool>,
+    pub ilm: Option<IlmConfig>
+}
+
+/// Lifecycle policy applied to the FIM indices, rollover is only used with
+/// data streams.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct IlmConfig {
+    pub rollover_size: Option<String>,
+    pub rollover_age: Option<String>,
+    pub delete_after: Option<String>
+}
+
+/// Splunk HTTP event collector settings.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SplunkConfig {
+    pub index: Option<String>,
+    pub sourcetype: Option<String>,
+    pub source: Option<String>,
+    pub ack: Option<bool>,
+    pub channel: Option<String>,
+    pub ack_timeout: Option<u64>
+}
+
+/// Splunk metadata of the events of a `monitor` or `audit` entry.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SplunkPathConfig {
+    pub index: Option<String>,
+    pub sourcetype: Option<String>,
+    pub source: Option<String>
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EventsConfig {
+    pub destination: Option<String>,
+    pub watcher: Option<String>,
+    pub file: Option<String>,
+    pub max_file_checksum: Option<usize>,
+    pub max_file_size: Option<usize>,
+    pub checksum_algorithm: Option<ShaType>,
+    pub debounce: Option<u64>,
+    pub poll: Option<PollConfig>,
+    pub watch_limit: Option<String>,
+    pub endpoint: Option<EndpointConfig>,
+    pub spool: Option<SpoolConfig>,
+    pub batch: Option<BatchConfig>,
+    pub syslog: Option<SyslogConfig>,
+    pub format: Option<FormatConfig>,
+    pub chain: Option<ChainConfig>,
+    pub webhooks: Option<Vec<WebhookConfig>>
+}
+
+/// Syslog collector where events are sent when destination is syslog.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SyslogConfig {
+    pub address: Option<String>,
+    pub protocol: Option<String>,
+    pub format: Option<String>,
+    pub framing: Option<String>,
+    pub facility: Option<String>,
+    pub severity: Option<String>,
+    pub app_name: Option<String>,
+    pub insecure: Option<bool>,
+    pub ca_file: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub pins: Option<Vec<String>>
+}
+
+/// Format of the events written to each destination, json, cef or leef.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FormatConfig {
+    pub file: Option<String>,
+    pub network: Option<String>,
+    pub syslog: Option<String>
+}
+
+/// Options of the poll watcher, used for every path or for the network file
+/// systems in hybrid mode.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PollConfig {
+    pub interval: Option<u64>,
+    pub compare_contents: Option<bool>
+}
+
+/// Sequence number and hash chaining each record of the events file to the
+/// previous one, keyed with HMAC when a key is set.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    pub enabled: Option<bool>,
+    pub key: Option<String>,
+    pub key_file: Option<String>
+}
+
+/// HTTP endpoint receiving the events that pass its filter, whatever the
+/// events destination is.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub name: Option<String>,
+    pub url: String,
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub credentials: Option<CredentialsConfig>,
+    pub insecure: Option<bool>,
+    pub ca_file: Option<String>,
+    pub format: Option<String>,
+    pub payload: Option<Value>,
+    #[serde(default)]
+    pub filter: WebhookFilterConfig
+}
+
+/// Events sent to a webhook, every list set must match the event.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookFilterConfig {
+    pub operations: Option<Vec<String>>,
+    pub labels: Option<Vec<String>>,
+    pub rules: Option<Vec<usize>>
+}
+
+/// Storage of network events pending to be delivered.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SpoolConfig {
+    pub file: Option<String>,
+    pub max_events: Option<usize>,
+    pub overflow: Option<String>,
+    pub max_backoff: Option<u64>
+}
+
+/// Limits of the network events sent together in one request.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BatchConfig {
+    pub max_events: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub interval: Option<u64>
+}
+
+/// Entry of the `monitor` or `audit` lists.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PathConfig {
+    pub path: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub ignore: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub allowed: Option<Vec<String>>,
+    #[serde(default, rename = "match")]
+    pub match_mode: MatchMode,
+    pub checksum_algorithm: Option<ShaType>,
+    pub max_file_checksum: Option<usize>,
+    pub debounce: Option<u64>,
+    pub destination: Option<String>,
+    pub splunk: Option<SplunkPathConfig>,
+    pub rule: Option<String>,
+    #[serde(default)]
+    pub integrations: Vec<Integration>,
+    #[serde(skip)]
+    pub matchers: CompiledMatchers
+}
+
+impl PathConfig {
+    /// Folder to watch or to hand to auditctl, `path` without its pattern part.
+    pub fn root(&self) -> String {
+        matcher::get_root(self.match_mode, &self.path)
+    }
+
+    /// Patterns of the entry, compiled on first use. The configuration
+    /// compiles them when it is loaded.
+    pub fn matchers(&self) -> &Matchers {
+        self.matchers.0.get_or_init(|| Matchers {
+            path: Matcher::path(self.match_mode, &self.path),
+            ignore: self.ignore.iter().flatten().map(|p| Matcher::new(self.match_mode, p)).collect(),
+            exclude: self.exclude.iter().flatten().map(|p| Matcher::new(self.match_mode, p)).collect(),
+            allowed: self.allowed.as_ref().map(|allowed| allowed.iter()
+                .map(|p| Matcher::new(self.match_mode, p)).collect())
+        })
+    }
+}
+
+/// Compiled `path`, `ignore`, `exclude` and `allowed` of a path entry.
+#[derive(Clone, Debug)]
+pub struct Matchers {
+    pub path: Matcher,
+    pub ignore: Vec<Matcher>,
+    pub exclude: Vec<Matcher>,
+    pub allowed: Option<Vec<Matcher>>
+}
+
+/// Cache of the compiled patterns, made from the other fields of the entry
+/// so it is left out of comparisons.
+#[derive(Clone, Debug, Default)]
+pub struct CompiledMatchers(OnceLock<Matchers>);
+
+impl PartialEq for CompiledMatchers {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HashscannerConfig {
+    pub file: Option<String>,
+    pub enabled: Option<bool>,
+    pub interval: Option<usize>,
+    pub algorithm: Option<ShaType>
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LogConfig {
+    pub file: Option<String>,
+    pub level: Option<String>,
+    pub max_file_size: Option<usize>
+}
+
+/// Entry of the `rules` list in rules.yml or in configuration files.
+/// `file` is the file where the rule is defined.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConfig {
+    pub id: usize,
+    pub path: String,
+    pub rule: String,
+    pub message: String,
+    /// CEF/LEEF severity of the rule events, from 0 to 10.
+    pub severity: Option<u8>,
+    #[serde(skip)]
+    pub file: String
+}
+
+// ----------------------------------------------------------------------------
+
+/// Typed representation of config.yml, with the position of every node
+/// so semantic checks can point the user to the right line.
+/// Nodes merged from included files are tracked in `origins`, indexed by
+/// their key in the merged configuration, with the file and key they come from.
+#[derive(Default, Clone, Debug)]
+pub struct ConfigFile {
+    pub node: Option<String>,
+    pub events: EventsConfig,
+    pub monitor: Vec<PathConfig>,
+    pub audit: Option<Vec<PathConfig>>,
+    pub hashscanner: HashscannerConfig,
+    pub log: LogConfig,
+    pub rules: Vec<RuleConfig>,
+    pub include: Vec<String>,
+    pub includes: Vec<String>,
+    pub locations: Locations,
+    pub origins: HashMap<String, (String, String)>,
+    pub fragments: HashMap<String, Locations>
+}
+
+impl ConfigFile {
+    /// Build an error pointing to the closest known position of `key`,
+    /// inside the included file that defines it if any.
+    pub fn error(&self, key: &str, message: &str) -> ConfigError {
+        let mut current = key;
+        loop {
+            if let Some((file, source)) = self.origins.get(current) {
+                let source_key = format!("{}{}", source, &key[current.len()..]);
+                let locations = self.fragments.get(file).cloned().unwrap_or_default();
+                let (line, column) = get_location(&locations, &source_key);
+                return ConfigError {
+                    file: file.clone(),
+                    key: source_key,
+                    message: String::from(message),
+                    line,
+                    column
+                };
+            }
+            match current.rfind(['.', '[']) {
+                Some(pos) => current = &current[..pos],
+                None => break
+            }
+        }
+        let (line, column) = get_location(&self.locations, key);
+        ConfigError {
+            file: String::new(),
+            key: String::from(key),
+            message: String::from(message),
+            line,
+            column
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Merge an included file. Lists are appended in order, scalar values
+    /// already defined with a different value are reported as conflicts.
+    pub fn merge(&mut self, file: &str, fragment: ConfigFile) -> Vec<ConfigError> {
+        let mut merger = Merger {
+            file,
+            fragment: &fragment,
+            origins: &mut self.origins,
+            errors: Vec::new()
+        };
+        if !fragment.include.is_empty() {
+            merger.conflict("include", "include is only allowed in the main configuration file");
+        }
+
+        merger.value(&mut self.node, &fragment.node, "node");
+
+        let (events, other) = (&mut self.events, &fragment.events);
+        merger.value(&mut events.destination, &other.destination, "events.destination");
+        merger.value(&mut events.watcher, &other.watcher, "events.watcher");
+        merger.value(&mut events.file, &other.file, "events.file");
+        merger.value(&mut events.max_file_checksum, &other.max_file_checksum, "events.max_file_checksum");
+        merger.value(&mut events.max_file_size, &other.max_file_size, "events.max_file_size");
+        merger.value(&mut events.checksum_algorithm, &other.checksum_algorithm, "events.checksum_algorithm");
+        merger.value(&mut events.debounce, &other.debounce, "events.debounce");
+        merger.value(&mut events.watch_limit, &other.watch_limit, "events.watch_limit");
+        if let Some(other) = &other.poll {
+            let poll = events.poll.get_or_insert_with(Default::default);
+            merger.value(&mut poll.interval, &other.interval, "events.poll.interval");
+            merger.value(&mut poll.compare_contents, &other.compare_contents, "events.poll.compare_contents");
+        }
+        if let Some(other) = &other.endpoint {
+            let endpoint = events.endpoint.get_or_insert_with(Default::default);
+            merger.value(&mut endpoint.address, &other.address, "events.endpoint.address");
+            merger.value(&mut endpoint.insecure, &other.insecure, "events.endpoint.insecure");
+            merger.value(&mut endpoint.ca_file, &other.ca_file, "events.endpoint.ca_file");
+            merger.value(&mut endpoint.client_cert, &other.client_cert, "events.endpoint.client_cert");
+            merger.value(&mut endpoint.client_key, &other.client_key, "events.endpoint.client_key");
+            merger.value(&mut endpoint.pins, &other.pins, "events.endpoint.pins");
+            if let Some(other) = &other.credentials {
+                let credentials = endpoint.credentials.get_or_insert_with(Default::default);
+                merger.value(&mut credentials.user, &other.user, "events.endpoint.credentials.user");
+                merger.value(&mut credentials.password, &other.password, "events.endpoint.credentials.password");
+                merger.value(&mut credentials.token, &other.token, "events.endpoint.credentials.token");
+                merger.value(&mut credentials.password_file, &other.password_file, "events.endpoint.credentials.password_file");
+                merger.value(&mut credentials.token_file, &other.token_file, "events.endpoint.credentials.token_file");
+            }
+            if let Some(other) = &other.splunk {
+                let splunk = endpoint.splunk.get_or_insert_with(Default::default);
+                merger.value(&mut splunk.index, &other.index, "events.endpoint.splunk.index");
+                merger.value(&mut splunk.sourcetype, &other.sourcetype, "events.endpoint.splunk.sourcetype");
+                merger.value(&mut splunk.source, &other.source, "events.endpoint.splunk.source");
+                merger.value(&mut splunk.ack, &other.ack, "events.endpoint.splunk.ack");
+                merger.value(&mut splunk.channel, &other.channel, "events.endpoint.splunk.channel");
+                merger.value(&mut splunk.ack_timeout, &other.ack_timeout, "events.endpoint.splunk.ack_timeout");
+            }
+            if let Some(other) = &other.elastic {
+                let elastic = endpoint.elastic.get_or_insert_with(Default::default);
+                merger.value(&mut elastic.index, &other.index, "events.endpoint.elastic.index");
+                merger.value(&mut elastic.data_stream, &other.data_stream, "events.endpoint.elastic.data_stream");
+                if let Some(other) = &other.ilm {
+                    let ilm = elastic.ilm.get_or_insert_with(Default::default);
+                    merger.value(&mut ilm.rollover_size, &other.rollover_size, "events.endpoint.elastic.ilm.rollover_size");
+                    merger.value(&mut ilm.rollover_age, &other.rollover_age, "events.endpoint.elastic.ilm.rollover_age");
+                    merger.value(&mut ilm.delete_after, &other.delete_after, "events.endpoint.elastic.ilm.delete_after");
+                }
+            }
+        }
+        if let Some(other) = &other.spool {
+            let spool = events.spool.get_or_insert_with(Default::default);
+            merger.value(&mut spool.file, &other.file, "events.spool.file");
+            merger.value(&mut spool.max_events, &other.max_events, "events.spool.max_events");
+            merger.value(&mut spool.overflow, &other.overflow, "events.spool.overflow");
+            merger.value(&mut spool.max_backoff, &other.max_backoff, "events.spool.max_backoff");
+        }
+        if let Some(other) = &other.batch {
+            let batch = events.batch.get_or_insert_with(Default::default);
+            merger.value(&mut batch.max_events, &other.max_events, "events.batch.max_events");
+            merger.value(&mut batch.max_bytes, &other.max_bytes, "events.batch.max_bytes");
+            merger.value(&mut batch.interval, &other.interval, "events.batch.interval");
+        }
+        if let Some(other) = &other.syslog {
+            let syslog = events.syslog.get_or_insert_with(Default::default);
+            merger.value(&mut syslog.address, &other.address, "events.syslog.address");
+            merger.value(&mut syslog.protocol, &other.protocol, "events.syslog.protocol");
+            merger.value(&mut syslog.format, &other.format, "events.syslog.format");
+            merger.value(&mut syslog.framing, &other.framing, "events.syslog.framing");
+            merger.value(&mut syslog.facility, &other.facility, "events.syslog.facility");
+            merger.value(&mut syslog.severity, &other.severity, "events.syslog.severity");
+            merger.value(&mut syslog.app_name, &other.app_name, "events.syslog.app_name");
+            merger.value(&mut syslog.insecure, &other.insecure, "events.syslog.insecure");
+            merger.value(&mut syslog.ca_file, &other.ca_file, "events.syslog.ca_file");
+            merger.value(&mut syslog.client_cert, &other.client_cert, "events.syslog.client_cert");
+            merger.value(&mut syslog.client_key, &other.client_key, "events.syslog.client_key");
+            merger.value(&mut syslog.pins, &other.pins, "events.syslog.pins");
+        }
+        if let Some(other) = &other.format {
+            let format = events.format.get_or_insert_with(Default::default);
+            merger.value(&mut format.file, &other.file, "events.format.file");
+            merger.value(&mut format.network, &other.network, "events.format.network");
+            merger.value(&mut format.syslog, &other.syslog, "events.format.syslog");
+        }
+
+        if let Some(other) = &fragment.events.chain {
+            let chain = self.events.chain.get_or_insert_with(Default::default);
+            merger.value(&mut chain.enabled, &other.enabled, "events.chain.enabled");
+            merger.value(&mut chain.key, &other.key, "events.chain.key");
+            merger.value(&mut chain.key_file, &other.key_file, "events.chain.key_file");
+        }
+        if let Some(other) = &fragment.events.webhooks {
+            let webhooks = self.events.webhooks.get_or_insert_with(Vec::new);
+            for (index, webhook) in other.iter().enumerate() {
+                merger.origin(&format!("events.webhooks[{}]", webhooks.len()), &format!("events.webhooks[{}]", index));
+                webhooks.push(webhook.clone());
+            }
+        }
+
+        let (hashscanner, other) = (&mut self.hashscanner, &fragment.hashscanner);
+        merger.value(&mut hashscanner.file, &other.file, "hashscanner.file");
+        merger.value(&mut hashscanner.enabled, &other.enabled, "hashscanner.enabled");
+        merger.value(&mut hashscanner.interval, &other.interval, "hashscanner.interval");
+        merger.value(&mut hashscanner.algorithm, &other.algorithm, "hashscanner.algorithm");
+
+        let (log, other) = (&mut self.log, &fragment.log);
+        merger.value(&mut log.file, &other.file, "log.file");
+        merger.value(&mut log.level, &other.level, "log.level");
+        merger.value(&mut log.max_file_size, &other.max_file_size, "log.max_file_size");
+
+        merger.paths(&mut self.monitor, &fragment.monitor, "monitor");
+        if let Some(other) = &fragment.audit {
+            let audit = self.audit.get_or_insert_with(Vec::new);
+            merger.paths(audit, other, "audit");
+        }
+        for (index, rule) in fragment.rules.iter().enumerate() {
+            merger.origin(&format!("rules[{}]", self.rules.len()), &format!("rules[{}]", index));
+            self.rules.push(rule.clone());
+        }
+
+        let errors = merger.errors;
+        self.fragments.insert(String::from(file), fragment.locations);
+        self.includes.push(String::from(file));
+        errors
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Keeps track of the file that defines each merged node
+struct Merger<'a> {
+    file: &'a str,
+    fragment: &'a ConfigFile,
+    origins: &'a mut HashMap<String, (String, String)>,
+    errors: Vec<ConfigError>
+}
+
+impl Merger<'_> {
+    fn origin(&mut self, key: &str, source: &str) {
+        self.origins.insert(String::from(key), (String::from(self.file), String::from(source)));
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Where the node `key` of the merged configuration was defined
+    fn defined_in(&self, key: &str) -> String {
+        match self.origins.get(key) {
+            Some((file, _)) => format!("'{}'", file),
+            None => String::from("the main configuration file")
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn conflict(&mut self, key: &str, message: &str) {
+        let mut error = self.fragment.error(key, message);
+        error.file = String::from(self.file);
+        self.errors.push(error);
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn value<T: Clone + PartialEq>(&mut self, target: &mut Option<T>, value: &Option<T>, key: &str) {
+        if let Some(value) = value {
+            match target {
+                Some(current) if current != value => {
+                    let message = format!("conflicting value, already set in {}", self.defined_in(key));
+                    self.conflict(key, &message);
+                },
+                Some(_) => (),
+                None => {
+                    *target = Some(value.clone());
+                    self.origin(key, key);
+                }
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn paths(&mut self, target: &mut Vec<PathConfig>, paths: &[PathConfig], section: &str) {
+        for (index, element) in paths.iter().enumerate() {
+            let source = format!("{}[{}]", section, index);
+            match target.iter().position(|p| p.path == element.path) {
+                Some(position) => {
+                    let message = format!("path '{}' already defined in {}", element.path,
+                        self.defined_in(&format!("{}[{}]", section, position)));
+                    self.conflict(&format!("{}.path", source), &message);
+                },
+                None => {
+                    self.origin(&format!("{}[{}]", section, target.len()), &source);
+                    target.push(element.clone());
+                }
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl ConfigErrors {
+    pub fn new(file: &str, errors: Vec<ConfigError>) -> Self {
+        ConfigErrors {
+            file: String::from(file),
+            errors
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.file.is_empty() {
+            write!(f, "{}: ", self.file)?;
+        }
+        if self.line != 0 {
+            write!(f, "line {}, column {}: ", self.line, self.column)?;
+        }
+        if !self.key.is_empty() {
+            write!(f, "{}: ", self.key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Found {} error(s) in configuration file '{}':", self.errors.len(), self.file)?;
+        for error in &self.errors {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl fmt::Debug for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        f.debug_tuple("")
+        .field(&self.file)
+        .field(&self.errors)
+        .finish()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Nesting level tracked while walking the YAML events
+enum Frame {
+    Mapping(String, Option<String>, (usize, usize)),
+    Sequence(String, usize)
+}
+
+#[derive(Default)]
+struct LocationReceiver {
+    stack: Vec<Frame>,
+    locations: Locations
+}
+
+impl LocationReceiver {
+    // Key of the next node depending on the container that holds it
+    fn next_key(&mut self) -> String {
+        match self.stack.last_mut() {
+            Some(Frame::Mapping(parent, key, _)) => join_key(parent, &key.take().unwrap_or_default()),
+            Some(Frame::Sequence(parent, index)) => {
+                let key = format!("{}[{}]", parent, index);
+                *index += 1;
+                key
+            },
+            None => String::new()
+        }
+    }
+}
+
+impl MarkedEventReceiver for LocationReceiver {
+    fn on_event(&mut self, event: YamlEvent, mark: Marker) {
+        let location = (mark.line(), mark.col() + 1);
+        match event {
+            YamlEvent::MappingEnd => {
+                if let Some(Frame::Mapping(path, _, start)) = self.stack.pop() {
+                    self.locations.entry(path).or_insert(start);
+                }
+            },
+            YamlEvent::SequenceEnd => { self.stack.pop(); },
+            YamlEvent::Scalar(value, ..) => {
+                if let Some(Frame::Mapping(parent, key @ None, _)) = self.stack.last_mut() {
+                    // Block mappings are marked after their first key, use the key position
+                    self.locations.entry(parent.clone()).or_insert(location);
+                    // Scalar in key position, the value will be located at its key
+                    self.locations.entry(join_key(parent, &value)).or_insert(location);
+                    *key = Some(value);
+                }else{
+                    let key = self.next_key();
+                    self.locations.entry(key).or_insert(location);
+                }
+            },
+            YamlEvent::Alias(_) => {
+                let key = self.next_key();
+                self.locations.entry(key).or_insert(location);
+            },
+            YamlEvent::MappingStart(_) => {
+                let key = self.next_key();
+                self.stack.push(Frame::Mapping(key, None, location));
+            },
+            YamlEvent::SequenceStart(_) => {
+                let key = self.next_key();
+                self.locations.entry(key.clone()).or_insert(location);
+                self.stack.push(Frame::Sequence(key, 0));
+            },
+            _ => ()
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn join_key(parent: &str, key: &str) -> String {
+    if parent.is_empty() { String::from(key) }else{ format!("{}.{}", parent, key) }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Returns the line and column of `key` or of its closest known ancestor.
+pub fn get_location(locations: &Locations, key: &str) -> (usize, usize) {
+    let mut current = key;
+    loop {
+        if let Some(location) = locations.get(current) {
+            return *location;
+        }
+        match current.rfind(['.', '[']) {
+            Some(pos) => current = &current[..pos],
+            None => return *locations.get("").unwrap_or(&(0, 0))
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Map every node of the YAML document to its line and column.
+pub fn get_locations(contents: &str) -> Locations {
+    let mut receiver = LocationReceiver::default();
+    let mut parser = Parser::new(contents.chars());
+    // Syntax errors are reported by serde_yaml, positions found so far are kept
+    let _ = parser.load(&mut receiver, false);
+    receiver.locations
+}
+
+// ----------------------------------------------------------------------------
+
+/// Deserialize a single node recording the exact position of any error.
+pub fn parse_node<T: DeserializeOwned>(value: Value, key: &str,
+    locations: &Locations, errors: &mut Vec<ConfigError>) -> Option<T> {
+    match serde_path_to_error::deserialize(value) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            let inner = e.path().to_string();
+            let full_key = match inner.as_str() {
+                "." => String::from(key),
+                _ if inner.starts_with('[') => format!("{}{}", key, inner),
+                _ => join_key(key, &inner)
+            };
+            let (line, column) = get_location(locations, &full_key);
+            errors.push(ConfigError {
+                file: String::new(),
+                key: full_key,
+                message: e.into_inner().to_string(),
+                line,
+                column
+            });
+            None
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Deserialize a list node entry by entry so every wrong entry is reported.
+pub fn parse_list<T: DeserializeOwned>(value: Value, key: &str,
+    locations: &Locations, errors: &mut Vec<ConfigError>) -> Vec<T> {
+    match value {
+        Value::Sequence(entries) => entries.into_iter().enumerate().filter_map(|(index, entry)| {
+            parse_node(entry, &format!("{}[{}]", key, index), locations, errors)
+        }).collect(),
+        Value::Null => Vec::new(),
+        _ => {
+            let (line, column) = get_location(locations, key);
+            errors.push(ConfigError {
+                file: String::new(),
+                key: String::from(key),
+                message: String::from("invalid type, expected a list"),
+                line,
+                column
+            });
+            Vec::new()
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Parse YAML contents into its top level mapping and the position of every
+/// node. Keys not listed in `sections` are pushed to `errors`.
+pub fn load_document(file: &str, contents: &str, sections: &[&str],
+    errors: &mut Vec<ConfigError>) -> Result<(Mapping, Locations), ConfigErrors> {
+    let document: Value = match serde_yaml::from_str(contents) {
+        Ok(value) => value,
+        Err(e) => {
+            let (line, column) = match e.location() {
+                Some(location) => (location.line(), location.column()),
+                None => (0, 0)
+            };
+            return Err(ConfigErrors::new(file, vec![ConfigError {
+                file: String::new(),
+                key: String::new(),
+                message: e.to_string(),
+                line,
+                column
+            }]));
+        }
+    };
+    let locations = get_locations(contents);
+
+    let root = match document {
+        Value::Mapping(map) => map,
+        Value::Null => Mapping::new(),
+        _ => {
+            let (line, column) = get_location(&locations, "");
+            return Err(ConfigErrors::new(file, vec![ConfigError {
+                file: String::new(),
+                key: String::new(),
+                message: String::from("invalid type, expected a mapping of configuration sections"),
+                line,
+                column
+            }]));
+        }
+    };
+
+    for key in root.keys() {
+        let name = key.as_str().unwrap_or_default();
+        if !sections.contains(&name) {
+            let (line, column) = get_location(&locations, name);
+            errors.push(ConfigError {
+                file: String::new(),
+                key: String::from(name),
+                message: format!("unknown section, expected one of {}", sections.join(", ")),
+                line,
+                column
+            });
+        }
+    }
+    Ok((root, locations))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Replace `${NAME}` references with the value of the environment variable
+/// NAME, `$${` is kept as a literal `${`.
+pub fn expand_env(text: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        result.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => return Err(String::from("unclosed environment variable reference, expected `}`"))
+        };
+        let name = &rest[start + 2..end];
+        let valid = name.chars().enumerate().all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+        if name.is_empty() || !valid {
+            return Err(format!("invalid environment variable name '{}'", name));
+        }
+        match std::env::var(name) {
+            Ok(value) => result.push_str(&value),
+            Err(_) => return Err(format!("environment variable '{}' not defined", name))
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+// ----------------------------------------------------------------------------
+
+// Expand environment variables in every string value of a node.
+fn interpolate(value: &mut Value, key: &str, locations: &Locations, errors: &mut Vec<ConfigError>) {
+    match value {
+        Value::String(text) => match expand_env(text) {
+            Ok(expanded) => *text = expanded,
+            Err(message) => {
+                let (line, column) = get_location(locations, key);
+                errors.push(ConfigError {
+                    file: String::new(),
+                    key: String::from(key),
+                    message,
+                    line,
+                    column
+                });
+            }
+        },
+        Value::Sequence(entries) => for (index, entry) in entries.iter_mut().enumerate() {
+            interpolate(entry, &format!("{}[{}]", key, index), locations, errors);
+        },
+        Value::Mapping(map) => for (name, entry) in map.iter_mut() {
+            interpolate(entry, &join_key(key, name.as_str().unwrap_or_default()), locations, errors);
+        },
+        _ => ()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Parse the contents of a configuration file into its typed representation.
+/// Every section is deserialized on its own so all errors are collected.
+pub fn parse_config(file: &str, contents: &str) -> Result<ConfigFile, ConfigErrors> {
+    let mut errors = Vec::new();
+    let (mut root, locations) = load_document(file, contents, &SECTIONS, &mut errors)?;
+    for (key, value) in root.iter_mut() {
+        let name = key.as_str().unwrap_or_default();
+        interpolate(value, name, &locations, &mut errors);
+    }
+
+    let mut section = |name: &str| root.remove(name).unwrap_or(Value::Null);
+    let node = section("node");
+    let events = section("events");
+    let monitor = section("monitor");
+    let audit = section("audit");
+    let hashscanner = section("hashscanner");
+    let log = section("log");
+    let rules = section("rules");
+    let include = match section("include") {
+        Value::String(pattern) => vec![pattern],
+        value => parse_list(value, "include", &locations, &mut errors)
+    };
+
+    let config = ConfigFile {
+        node: parse_node(node, "node", &locations, &mut errors).unwrap_or_default(),
+        events: parse_node(events, "events", &locations, &mut errors).unwrap_or_default(),
+        monitor: parse_list(monitor, "monitor", &locations, &mut errors),
+        audit: match audit {
+            Value::Null => None,
+            value => Some(parse_list(value, "audit", &locations, &mut errors))
+        },
+        hashscanner: parse_node(hashscanner, "hashscanner", &locations, &mut errors).unwrap_or_default(),
+        log: parse_node(log, "log", &locations, &mut errors).unwrap_or_default(),
+        rules: parse_list(rules, "rules", &locations, &mut errors).into_iter()
+            .map(|rule| RuleConfig { file: String::from(file), ..rule }).collect(),
+        include,
+        locations,
+        ..Default::default()
+    };
+
+    match errors.is_empty() {
+        true => Ok(config),
+        false => Err(ConfigErrors::new(file, errors))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Read the whole contents of a YAML file, reporting read failures as errors.
+pub fn read_file(path: &str) -> Result<String, ConfigErrors> {
+    fs::read_to_string(path).map_err(|e| ConfigErrors::new(path, vec![ConfigError {
+        file: String::new(),
+        key: String::new(),
+        message: format!("Unable to read file, {:?}", e.kind()),
+        line: 0,
+        column: 0
+    }]))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Returns the drop-in folder of a configuration file, `conf.d` next to it.
+pub fn get_dropin_dir(config_path: &str) -> PathBuf {
+    Path::new(config_path).parent().unwrap_or(Path::new("")).join(DROPIN_DIR)
+}
+
+// ----------------------------------------------------------------------------
+
+// Sorted YAML files matching a glob pattern
+fn glob_files(pattern: &str) -> Result<Vec<String>, String> {
+    match glob::glob(pattern) {
+        Ok(paths) => {
+            let mut files: Vec<String> = paths.filter_map(|p| p.ok())
+                .filter(|p| p.is_file())
+                .map(|p| String::from(p.to_str().unwrap_or_default())).collect();
+            files.sort();
+            Ok(files)
+        },
+        Err(e) => Err(e.to_string())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Returns the files to merge into the configuration, in order: the `include`
+/// entries as listed, each glob sorted by name, then the drop-in folder files.
+/// Relative paths are resolved from the configuration file folder.
+pub fn get_includes(config_path: &str, config: &ConfigFile) -> (Vec<String>, Vec<ConfigError>) {
+    let base = Path::new(config_path).parent().unwrap_or(Path::new(""));
+    let mut files: Vec<String> = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, entry) in config.include.iter().enumerate() {
+        let key = match config.include.len() {
+            1 => String::from("include"),
+            _ => format!("include[{}]", index)
+        };
+        let path = base.join(entry);
+        let pattern = match path.is_dir() {
+            true => path.join("*.yml"),
+            false => path
+        };
+        let pattern = pattern.to_str().unwrap_or_default();
+        if !pattern.contains(['*', '?', '[']) && !Path::new(pattern).exists() {
+            errors.push(config.error(&key, &format!("included file '{}' not found", pattern)));
+            continue;
+        }
+        match glob_files(pattern) {
+            Ok(found) => files.extend(found),
+            Err(e) => errors.push(config.error(&key, &format!("invalid include pattern, {}", e)))
+        }
+    }
+
+    let dropin = get_dropin_dir(config_path).join("*.yml");
+    if let Ok(found) = glob_files(dropin.to_str().unwrap_or_default()) {
+        files.extend(found);
+    }
+
+    // Each file is merged once, the main file is never included again
+    let main = fs::canonicalize(config_path).ok();
+    let mut seen = Vec::new();
+    files.retain(|file| {
+        let canonical = fs::canonicalize(file).ok();
+        if canonical == main || seen.contains(&canonical) {
+            false
+        }else{
+            seen.push(canonical);
+            true
+        }
+    });
+    (files, errors)
+}
+
+// ----------------------------------------------------------------------------
+
+/// To read the YAML configuration file into its typed representation,
+/// merging the included files and the drop-in folder.
+pub fn read_config(path: String) -> Result<ConfigFile, ConfigErrors> {
+    let mut config = parse_config(&path, &read_file(&path)?)?;
+    let (files, mut errors) = get_includes(&path, &config);
+    for file in files {
+        match read_file(&file).and_then(|contents| parse_config(&file, &contents)) {
+            Ok(fragment) => errors.extend(config.merge(&file, fragment)),
+            Err(e) => errors.extend(e.errors.into_iter().map(|error| ConfigError {
+                file: file.clone(),
+                ..error
+            }))
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(config),
+        false => Err(ConfigErrors::new(&path, errors))
+    }
+}
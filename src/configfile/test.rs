@@ -0,0 +1,388 @@
+use super::*;
+
+// ----------------------------------------------------------------------------
+
+const CONFIG: &str = "node: \"FIM\"
+events:
+  destination: file
+  file: /var/lib/fim/events.json
+  max_file_checksum: 32
+monitor:
+  - path: /bin/
+  - path: /etc
+    labels: [\"etc\"]
+    integrations:
+      - name: rmfile
+        condition: [\"operation\", \"==\", \"CREATE\"]
+        binary: bash
+        script: /tmp/remover.sh
+hashscanner:
+  file: /var/lib/fim/fim.db
+log:
+  file: /var/log/fim/fim.log
+";
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a valid configuration is loaded into typed sections
+fn test_parse_config() {
+    let config = parse_config("test.yml", CONFIG).unwrap();
+    assert_eq!(config.node, Some(String::from("FIM")));
+    assert_eq!(config.events.destination, Some(String::from("file")));
+    assert_eq!(config.events.max_file_checksum, Some(32));
+    assert_eq!(config.events.checksum_algorithm, None);
+    assert_eq!(config.monitor.len(), 2);
+    assert_eq!(config.monitor[0].path, "/bin/");
+    assert!(config.monitor[0].labels.is_empty());
+    assert_eq!(config.monitor[1].labels, vec![String::from("etc")]);
+    assert_eq!(config.monitor[1].integrations[0].name, "rmfile");
+    assert_eq!(config.monitor[1].integrations[0].parameters, "");
+    assert_eq!(config.audit, None);
+    assert_eq!(config.hashscanner.file, Some(String::from("/var/lib/fim/fim.db")));
+    assert_eq!(config.log.level, None);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check every error is collected with its position
+fn test_parse_config_errors() {
+    let contents = "events:
+  destination: file
+  max_file_checksum: big
+monitor:
+  - labels: [\"tmp\"]
+  - path: /etc
+    ignroe: [\".swp\"]
+  - path: /usr
+    integrations:
+      - name: missing
+        condition: [\"a\", \"==\", \"b\"]
+        script: test.sh
+logs:
+  file: /tmp/fim.log
+";
+    let errors = parse_config("test.yml", contents).unwrap_err().errors;
+    assert_eq!(errors.len(), 5);
+
+    assert_eq!(errors[0].key, "logs");
+    assert_eq!((errors[0].line, errors[0].column), (13, 1));
+
+    assert_eq!(errors[1].key, "events.max_file_checksum");
+    assert_eq!((errors[1].line, errors[1].column), (3, 3));
+
+    assert_eq!(errors[2].key, "monitor[0]");
+    assert!(errors[2].message.contains("missing field `path`"));
+    assert_eq!((errors[2].line, errors[2].column), (5, 5));
+
+    assert_eq!(errors[3].key, "monitor[1].ignroe");
+    assert!(errors[3].message.contains("unknown field `ignroe`"));
+    assert_eq!((errors[3].line, errors[3].column), (7, 5));
+
+    assert_eq!(errors[4].key, "monitor[2].integrations[0]");
+    assert!(errors[4].message.contains("missing field `binary`"));
+    assert_eq!((errors[4].line, errors[4].column), (10, 9));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check an invalid checksum algorithm is rejected
+fn test_parse_config_algorithm() {
+    let errors = parse_config("test.yml", "events:\n  checksum_algorithm: md5\n")
+        .unwrap_err().errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].key, "events.checksum_algorithm");
+    assert!(errors[0].message.contains("unknown algorithm 'md5'"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check syntax errors keep the position given by the YAML parser
+fn test_parse_config_syntax() {
+    let errors = parse_config("test.yml", "monitor:\n  - path: [/tmp\n").unwrap_err().errors;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].line > 0);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check documents that are not a mapping are rejected
+fn test_parse_config_not_mapping() {
+    let errors = parse_config("test.yml", "- monitor\n").unwrap_err().errors;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message.contains("expected a mapping"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check node positions are indexed by their dotted key
+fn test_get_locations() {
+    let locations = get_locations(CONFIG);
+    assert_eq!(locations.get("node"), Some(&(1, 1)));
+    assert_eq!(locations.get("events.file"), Some(&(4, 3)));
+    assert_eq!(locations.get("monitor[1]"), Some(&(8, 5)));
+    assert_eq!(locations.get("monitor[1].labels"), Some(&(9, 5)));
+    assert_eq!(locations.get("monitor[1].integrations[0].binary"), Some(&(13, 9)));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check unknown keys fall back to their closest ancestor
+fn test_get_location() {
+    let locations = get_locations(CONFIG);
+    assert_eq!(get_location(&locations, "monitor[1].ignore"), (8, 5));
+    assert_eq!(get_location(&locations, "events.endpoint.address"), (2, 1));
+    assert_eq!(get_location(&HashMap::new(), "events"), (0, 0));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the error report format
+fn test_fmt() {
+    let errors = ConfigErrors::new("config.yml", vec![
+        ConfigError {
+            file: String::new(),
+            key: String::from("log"),
+            message: String::from("missing field `file`"),
+            line: 3,
+            column: 1
+        },
+        ConfigError {
+            file: String::new(),
+            key: String::new(),
+            message: String::from("Unable to read file"),
+            line: 0,
+            column: 0
+        },
+        ConfigError {
+            file: String::from("conf.d/web.yml"),
+            key: String::from("node"),
+            message: String::from("conflicting value, already set in the main configuration file"),
+            line: 1,
+            column: 1
+        }
+    ]);
+    assert_eq!(format!("{}", errors), "Found 3 error(s) in configuration file 'config.yml':\n  \
+        - line 3, column 1: log: missing field `file`\n  \
+        - Unable to read file\n  \
+        - conf.d/web.yml: line 1, column 1: node: conflicting value, already set in the main configuration file");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check included files and the drop-in folder are merged in order
+fn test_read_config_include() {
+    let base = "test/unit/config/common/include";
+    let config = read_config(format!("{}/config.yml", base)).unwrap();
+    let paths: Vec<&str> = config.monitor.iter().map(|p| p.path.as_str()).collect();
+    assert_eq!(paths, vec!["/etc", "/usr", "/var"]);
+    assert_eq!(config.monitor[1].labels, vec![String::from("usr")]);
+    assert_eq!(config.audit.unwrap()[0].path, "/tmp");
+    assert_eq!(config.node, Some(String::from("FIM_NODE")));
+    assert_eq!(config.events.max_file_size, Some(256));
+    assert_eq!(config.events.destination, Some(String::from("file")));
+
+    assert_eq!(config.rules.len(), 2);
+    assert_eq!(config.rules[0].id, 100);
+    assert_eq!(config.rules[0].file, format!("{}/extra/web.yml", base));
+    assert_eq!(config.rules[1].id, 101);
+    assert_eq!(config.rules[1].file, format!("{}/conf.d/10-rules.yml", base));
+
+    assert_eq!(config.includes, vec![
+        format!("{}/extra.yml", base),
+        format!("{}/extra/web.yml", base),
+        format!("{}/conf.d/10-rules.yml", base),
+        format!("{}/conf.d/20-node.yml", base)
+    ]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check errors on merged nodes point to the included file that defines them
+fn test_error_include() {
+    let base = "test/unit/config/common/include";
+    let config = read_config(format!("{}/config.yml", base)).unwrap();
+
+    let error = config.error("monitor[2].path", "path does not exist");
+    assert_eq!(error.file, format!("{}/extra/web.yml", base));
+    assert_eq!(error.key, "monitor[0].path");
+    assert_eq!((error.line, error.column), (2, 5));
+
+    let error = config.error("monitor[0].path", "path does not exist");
+    assert_eq!(error.file, "");
+    assert_eq!((error.line, error.column), (10, 5));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check conflicting values and duplicated paths are reported in the drop-in file
+fn test_read_config_include_conflict() {
+    let base = "test/unit/config/common/include_conflict";
+    let errors = read_config(format!("{}/config.yml", base)).unwrap_err().errors;
+    let file = format!("{}/conf.d/10-conflict.yml", base);
+    assert_eq!(errors.len(), 3);
+
+    assert_eq!(errors[0].file, file);
+    assert_eq!(errors[0].key, "include");
+    assert_eq!((errors[0].line, errors[0].column), (10, 1));
+
+    assert_eq!(errors[1].file, file);
+    assert_eq!(errors[1].key, "events.file");
+    assert_eq!(errors[1].message, "conflicting value, already set in the main configuration file");
+    assert_eq!((errors[1].line, errors[1].column), (3, 3));
+
+    assert_eq!(errors[2].file, file);
+    assert_eq!(errors[2].key, "monitor[1].path");
+    assert_eq!(errors[2].message, "path '/etc' already defined in the main configuration file");
+    assert_eq!((errors[2].line, errors[2].column), (7, 5));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the Elasticsearch settings of a drop-in file are merged
+fn test_read_config_include_elastic() {
+    let config = read_config(String::from("test/unit/config/common/include_network/config.yml")).unwrap();
+    let endpoint = config.events.endpoint.unwrap();
+    assert_eq!(endpoint.address, Some(String::from("https://127.0.0.1:9200")));
+    let elastic = endpoint.elastic.unwrap();
+    assert_eq!(elastic.index, Some(String::from("fim-events")));
+    assert_eq!(elastic.data_stream, Some(true));
+    assert_eq!(elastic.ilm, Some(IlmConfig { rollover_size: Some(String::from("10gb")),
+        rollover_age: Some(String::from("1d")), delete_after: Some(String::from("30d")) }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the TLS settings of the endpoint are merged from a drop-in file
+fn test_read_config_include_tls() {
+    let config = read_config(String::from("test/unit/config/common/include_network/config.yml")).unwrap();
+    let endpoint = config.events.endpoint.unwrap();
+    assert_eq!(endpoint.ca_file, Some(String::from("/etc/fim/ca.pem")));
+    assert_eq!(endpoint.client_cert, Some(String::from("/etc/fim/client.pem")));
+    assert_eq!(endpoint.client_key, Some(String::from("/etc/fim/client.key")));
+    assert_eq!(endpoint.pins, Some(vec![String::from("sha256//AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=")]));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check webhooks of a drop-in file are appended and located in that file
+fn test_read_config_include_webhooks() {
+    let base = "test/unit/config/common/include_network";
+    let config = read_config(format!("{}/config.yml", base)).unwrap();
+    let webhooks = config.events.webhooks.clone().unwrap();
+    assert_eq!(webhooks.len(), 2);
+    assert_eq!(webhooks[0].name, Some(String::from("chatops")));
+    assert_eq!(webhooks[1].name, Some(String::from("alerts")));
+    assert_eq!(webhooks[1].method, Some(String::from("PUT")));
+
+    let error = config.error("events.webhooks[1].url", "invalid url");
+    assert_eq!(error.file, format!("{}/conf.d/30-webhooks.yml", base));
+    assert_eq!(error.key, "events.webhooks[0].url");
+    assert_eq!((error.line, error.column), (4, 7));
+    assert_eq!(config.error("events.webhooks[0].url", "invalid url").file, "");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the events file chain can be enabled from a drop-in file
+fn test_read_config_include_chain() {
+    let config = read_config(String::from("test/unit/config/common/include_network/config.yml")).unwrap();
+    assert_eq!(config.events.chain, Some(ChainConfig { enabled: Some(true), key: None,
+        key_file: Some(String::from("/etc/fim/chain.key")) }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check network and chain settings defined twice are reported at the drop-in file
+fn test_read_config_include_network_conflict() {
+    let base = "test/unit/config/common/include_network_conflict";
+    let errors = read_config(format!("{}/config.yml", base)).unwrap_err().errors;
+    let file = format!("{}/conf.d/10-conflict.yml", base);
+    assert_eq!(errors.len(), 2);
+
+    assert_eq!(errors[0].file, file);
+    assert_eq!(errors[0].key, "events.endpoint.ca_file");
+    assert_eq!(errors[0].message, "conflicting value, already set in the main configuration file");
+    assert_eq!((errors[0].line, errors[0].column), (3, 5));
+
+    assert_eq!(errors[1].file, file);
+    assert_eq!(errors[1].key, "events.chain.enabled");
+    assert_eq!(errors[1].message, "conflicting value, already set in the main configuration file");
+    assert_eq!((errors[1].line, errors[1].column), (5, 5));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check missing included files are reported at the include entry
+fn test_get_includes_not_found() {
+    let config = parse_config("config.yml", "include:\n  - extra.yml\n  - missing.yml\n").unwrap();
+    let (files, errors) = get_includes("test/unit/config/common/include/config.yml", &config);
+    assert_eq!(files.len(), 3);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].key, "include[1]");
+    assert_eq!((errors[0].line, errors[0].column), (3, 5));
+    assert!(errors[0].message.contains("missing.yml' not found"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the drop-in folder is next to the configuration file
+fn test_get_dropin_dir() {
+    assert_eq!(get_dropin_dir("/etc/fim/config.yml"), PathBuf::from("/etc/fim/conf.d"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check environment variables are expanded in string values
+fn test_expand_env() {
+    std::env::set_var("FIM_TEST_EXPAND_USER", "admin");
+    assert_eq!(expand_env("${FIM_TEST_EXPAND_USER}"), Ok(String::from("admin")));
+    assert_eq!(expand_env("user-${FIM_TEST_EXPAND_USER}-1"), Ok(String::from("user-admin-1")));
+    assert_eq!(expand_env("$${FIM_TEST_EXPAND_USER}"), Ok(String::from("${FIM_TEST_EXPAND_USER}")));
+    assert_eq!(expand_env("/tmp/$HOME"), Ok(String::from("/tmp/$HOME")));
+    assert!(expand_env("${FIM_TEST_EXPAND_MISSING}").unwrap_err().contains("not defined"));
+    assert!(expand_env("${FIM TEST}").unwrap_err().contains("invalid environment variable name"));
+    assert!(expand_env("${FIM_TEST").unwrap_err().contains("unclosed"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check environment variables are expanded while parsing and missing ones are reported
+fn test_parse_config_env() {
+    std::env::set_var("FIM_TEST_PARSE_TOKEN", "secret");
+    let contents = "events:
+  endpoint:
+    credentials:
+      token: ${FIM_TEST_PARSE_TOKEN}
+monitor:
+  - path: ${FIM_TEST_PARSE_MISSING}/etc
+";
+    let errors = parse_config("test.yml", contents).unwrap_err().errors;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].key, "monitor[0].path");
+    assert_eq!((errors[0].line, errors[0].column), (6, 5));
+
+    let config = parse_config("test.yml", &contents.replace("${FIM_TEST_PARSE_MISSING}", "")).unwrap();
+    let credentials = config.events.endpoint.unwrap().credentials.unwrap();
+    assert_eq!(credentials.token, Some(String::from("secret")));
+    assert_eq!(config.monitor[0].path, "/etc");
+}
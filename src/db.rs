@@ -204,6 +204,28 @@ impl DB {
 
     // ------------------------------------------------------------------------
 
+    /// Move the files stored under `from` to `to`, a file or a directory with
+    /// everything inside it. Their ids and hashes are kept.
+    pub fn rename_path(&self, from: &str, to: &str) -> Result<usize, DBFileError> {
+        let connection = self.open();
+        let query = "UPDATE files SET path = ?2 || substr(path, length(?1) + 1) \
+            WHERE path = ?1 OR substr(path, 1, length(?1) + 1) = ?1 || ?3";
+        let result = connection.execute(query, params![from, to, std::path::MAIN_SEPARATOR.to_string()]);
+        self.close(connection);
+        match result {
+            Ok(count) => {
+                debug!("Moved {} file(s) from '{}' to '{}' in database.", count, from, to);
+                Ok(count)
+            },
+            Err(e) => {
+                error!("Cannot move files from '{}' to '{}' in database, Error: {:?}", from, to, e);
+                Err(DBFileError::from(e))
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
     /// Delete information inside db related to the given DBFile
     pub fn delete_file(&self, dbfile: DBFile) -> Result<u8, DBFileError>{
         let connection = self.open();
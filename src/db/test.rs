@@ -36,7 +36,7 @@ fn get_dbfile() -> DBFile {
 #[serial]
 /// Check new instance creation, the instance should match the expected DB path.
 fn test_new() {
-    let cfg = AppConfig::new(&utils::get_os(), None);
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
     let tdb = DB::new(&cfg.hashscanner_file);
 
     assert_eq!(tdb.path, cfg.hashscanner_file);
@@ -224,7 +224,7 @@ fn test_get_file_list() {
 /// Check present DBFile update.
 /// It should differ in calculated fields (timestamp, hash, size, permissions)
 fn test_update_file() {
-    let cfg = AppConfig::new(&utils::get_os(), None);
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
     let tdb = DB::new(&cfg.hashscanner_file);
     let original_dbfile = get_dbfile();
     let new_dbfile = DBFile {
@@ -294,4 +294,28 @@ fn test_delete_file() {
     );
 
     assert_eq!(result, Err(QueryReturnedNoRows));
-}
\ No newline at end of file
+}
+
+// ------------------------------------------------------------------------
+
+#[test]
+#[serial]
+/// Check files moved with their directory keep their information under the new path
+fn test_rename_path() {
+    let db_path = "fim.db";
+    let tdb = DB::new(db_path);
+    let sep = std::path::MAIN_SEPARATOR;
+    let path = |p: &str| p.replace('/', &sep.to_string());
+
+    remove_db(db_path);
+    tdb.create_table();
+    for (id, file) in [("1", "/dir/a"), ("2", "/dir/sub/b"), ("3", "/dir2/c")] {
+        tdb.insert_file(DBFile { id: String::from(id), path: path(file), ..get_dbfile() });
+    }
+    assert_eq!(tdb.rename_path(&path("/dir"), &path("/moved")).unwrap(), 2);
+    assert_eq!(tdb.get_file_by_path(path("/moved/sub/b")).unwrap().id, "2");
+    assert_eq!(tdb.get_file_by_path(path("/dir2/c")).unwrap().id, "3");
+    assert_eq!(tdb.rename_path(&path("/moved/a"), &path("/a")).unwrap(), 1);
+    assert_eq!(tdb.get_file_by_path(path("/a")).unwrap().hash, "HASH");
+    assert!(tdb.get_file_by_path(path("/dir/a")).is_err());
+}
@@ -0,0 +1,121 @@
+// Copyright (C) 2026, Achiefs.
+
+// To merge the bursts of events of a path into a single event
+use notify::Event;
+use notify::event::EventKind;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod test;
+
+// A burst is sent after this many windows even if events keep coming
+pub const MAX_WINDOWS: u32 = 10;
+
+// ----------------------------------------------------------------------------
+
+/// Events received for a path until it is quiet for a whole window.
+#[derive(Clone, Debug)]
+pub struct Burst {
+    pub event: Event,
+    pub kinds: Vec<EventKind>,
+    first: Instant,
+    last: Instant,
+    window: Duration
+}
+
+impl Burst {
+    pub fn new(event: Event, window: Duration) -> Self {
+        let now = Instant::now();
+        Burst { kinds: vec![event.kind], event, first: now, last: now, window }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Adds an event of the same path, repeated kinds are listed once.
+    pub fn add(&mut self, event: &Event) {
+        if !self.kinds.contains(&event.kind) {
+            self.kinds.push(event.kind);
+        }
+        self.last = Instant::now();
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Kind reported for the burst. A removal wins if the file is gone at the
+    /// end, then a creation, otherwise the first kind received.
+    pub fn kind(&self) -> EventKind {
+        let exists = self.event.paths.first().map(|path| path.exists()).unwrap_or(false);
+        let removed = self.kinds.iter().rev().find(|kind| kind.is_remove());
+        let created = self.kinds.iter().find(|kind| kind.is_create());
+        match (removed, created) {
+            (Some(kind), _) if !exists => *kind,
+            (_, Some(kind)) => *kind,
+            _ => self.kinds[0]
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Instant when the burst has to be sent
+    fn deadline(&self) -> Instant {
+        (self.last + self.window).min(self.first + self.window * MAX_WINDOWS)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Bursts in progress, one per path.
+#[derive(Default)]
+pub struct Bursts {
+    bursts: HashMap<PathBuf, Burst>
+}
+
+impl Bursts {
+    pub fn new() -> Self {
+        Bursts::default()
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Adds an event to the burst of its path, a new one is started with
+    /// `window` if there is none.
+    pub fn add(&mut self, event: Event, window: Duration) {
+        let path = match event.paths.first() {
+            Some(path) => path.clone(),
+            None => return
+        };
+        match self.bursts.get_mut(&path) {
+            Some(burst) => burst.add(&event),
+            None => { self.bursts.insert(path, Burst::new(event, window)); }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Removes the burst of a path before its window ends.
+    pub fn take(&mut self, path: &Path) -> Option<Burst> {
+        self.bursts.remove(path)
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Bursts whose window ended, in the order they started.
+    pub fn expire(&mut self) -> Vec<Burst> {
+        let now = Instant::now();
+        let paths: Vec<PathBuf> = self.bursts.iter()
+            .filter(|(_, burst)| burst.deadline() <= now)
+            .map(|(path, _)| path.clone()).collect();
+        let mut expired: Vec<Burst> = paths.iter().filter_map(|path| self.bursts.remove(path)).collect();
+        expired.sort_by_key(|burst| burst.first);
+        expired
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Time until the next burst has to be sent, None without bursts.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.bursts.values().map(|burst| burst.deadline().saturating_duration_since(Instant::now())).min()
+    }
+}
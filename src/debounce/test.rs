@@ -0,0 +1,79 @@
+use super::*;
+use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+use std::thread;
+
+// ----------------------------------------------------------------------------
+
+fn create_event(kind: EventKind, path: &str) -> Event {
+    Event::new(kind).add_path(PathBuf::from(path))
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check events of the same path are merged listing each kind once
+fn test_add() {
+    let mut bursts = Bursts::new();
+    let window = Duration::from_millis(50);
+    let modify = EventKind::Modify(ModifyKind::Data(DataChange::Any));
+    bursts.add(create_event(EventKind::Create(CreateKind::File), "/tmp/fim_burst"), window);
+    bursts.add(create_event(modify, "/tmp/fim_burst"), window);
+    bursts.add(create_event(modify, "/tmp/fim_burst"), window);
+    bursts.add(create_event(modify, "/tmp/fim_other"), window);
+    assert!(bursts.expire().is_empty());
+    assert!(bursts.timeout().unwrap() <= window);
+
+    let burst = bursts.take(Path::new("/tmp/fim_burst")).unwrap();
+    assert_eq!(burst.kinds, vec![EventKind::Create(CreateKind::File), modify]);
+    assert!(bursts.take(Path::new("/tmp/fim_burst")).is_none());
+    thread::sleep(window);
+    let expired = bursts.expire();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].event.paths, vec![PathBuf::from("/tmp/fim_other")]);
+    assert_eq!(bursts.timeout(), None);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the kind of a burst depends on the file existing at the end
+fn test_kind() {
+    let window = Duration::from_millis(50);
+    let create = EventKind::Create(CreateKind::File);
+    let modify = EventKind::Modify(ModifyKind::Data(DataChange::Any));
+    let remove = EventKind::Remove(RemoveKind::File);
+
+    let mut burst = Burst::new(create_event(create, "/tmp/fim_burst_missing"), window);
+    burst.add(&create_event(modify, "/tmp/fim_burst_missing"));
+    burst.add(&create_event(remove, "/tmp/fim_burst_missing"));
+    assert_eq!(burst.kind(), remove);
+
+    // Removed and created again, the file is there
+    let mut burst = Burst::new(create_event(remove, "test/unit/config/common/test_debounce_invalid.yml"), window);
+    burst.add(&create_event(create, "test/unit/config/common/test_debounce_invalid.yml"));
+    assert_eq!(burst.kind(), create);
+
+    let mut burst = Burst::new(create_event(modify, "/tmp/fim_burst_missing"), window);
+    burst.add(&create_event(EventKind::Any, "/tmp/fim_burst_missing"));
+    assert_eq!(burst.kind(), modify);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a burst that keeps receiving events is sent after MAX_WINDOWS windows
+fn test_expire_max_windows() {
+    let mut bursts = Bursts::new();
+    let window = Duration::from_millis(10);
+    let modify = EventKind::Modify(ModifyKind::Data(DataChange::Any));
+    bursts.add(create_event(modify, "/tmp/fim_burst"), window);
+    let start = Instant::now();
+    let mut expired = Vec::new();
+    while expired.is_empty() {
+        thread::sleep(window / 2);
+        bursts.add(create_event(modify, "/tmp/fim_burst"), window);
+        expired = bursts.expire();
+    }
+    assert!(start.elapsed() >= window * (MAX_WINDOWS - 1));
+    assert_eq!(expired[0].kinds, vec![modify]);
+}
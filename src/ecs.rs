@@ -0,0 +1,257 @@
+// Copyright (C) 2026, Achiefs.
+
+// To build the ECS documents
+use serde_json::{json, Map, Value};
+use time::OffsetDateTime;
+use std::path::Path;
+
+use crate::appconfig::AppConfig;
+use crate::hash::ShaType;
+use crate::output::Envelope;
+
+#[cfg(test)]
+mod test;
+
+// Elastic Common Schema version of the documents
+pub const ECS_VERSION: &str = "8.11.0";
+// Audit record fields kept as they are under auditd.data
+const AUDIT_DATA: [&str; 16] = ["syscall", "arch", "a0", "a1", "a2", "a3", "exit", "tty", "items", "item",
+    "rdev", "cap_fver", "cap_fp", "cap_fe", "cap_fi", "cap_frootid"];
+
+// ----------------------------------------------------------------------------
+
+// ISO 8601 time of a millisecond timestamp
+pub fn get_time(timestamp: &Value) -> Value {
+    let time = timestamp.as_str().and_then(|timestamp| timestamp.parse::<i128>().ok())
+        .and_then(|timestamp| OffsetDateTime::from_unix_timestamp_nanos(timestamp * 1000000).ok());
+    match time {
+        Some(time) => Value::String(format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            time.year(), time.month() as u8, time.day(), time.hour(), time.minute(), time.second(), time.millisecond())),
+        None => Value::Null
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Numeric fields stored as strings, like audit process ids
+pub fn get_number(value: &Value) -> Value {
+    match value.as_str().and_then(|value| value.parse::<i64>().ok()) {
+        Some(number) => Value::from(number),
+        None => value.clone()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// ECS event type of an operation
+fn get_type(operation: &str) -> &str {
+    match operation {
+        "CREATE" => "creation",
+        "WRITE" | "NORMAL" | "MOVE" => "change",
+        "REMOVE" | "DELETE" => "deletion",
+        "ACCESS" => "access",
+        _ => "info"
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Unix mode as stored by the hash scanner, octal digits as a number
+fn get_mode(permissions: &Value) -> Value {
+    match permissions.as_u64() {
+        Some(permissions) if permissions > 0 => Value::String(format!("{:04}", permissions % 10000)),
+        _ => Value::Null
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_file(path: &Value, size: &Value, checksum: &Value, algorithm: &ShaType) -> Value {
+    let file = Path::new(path.as_str().unwrap_or_default());
+    let mut hash = Map::new();
+    hash.insert(String::from(algorithm.name()), checksum.clone());
+    json!({
+        "path": path,
+        "name": file.file_name().map(|name| name.to_string_lossy()),
+        "directory": file.parent().map(|directory| directory.to_string_lossy()),
+        "extension": file.extension().map(|extension| extension.to_string_lossy()),
+        "size": size,
+        "hash": hash
+    })
+}
+
+// ----------------------------------------------------------------------------
+
+// Add the fields of `source` to `target`, nested objects are merged
+pub fn merge(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        },
+        (_, Value::Null) => (),
+        (target, source) => *target = source
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Remove null and empty values, documents only carry the fields set
+pub fn clean(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(fields.into_iter()
+            .map(|(key, value)| (key, clean(value)))
+            .filter(|(_, value)| !is_empty(value)).collect()),
+        Value::Array(values) => Value::Array(values.into_iter().map(clean)
+            .filter(|value| !is_empty(value)).collect()),
+        value => value
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(value) => value.is_empty(),
+        Value::Array(values) => values.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => false
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_audit(envelope: &Envelope, cfg: &AppConfig) -> Value {
+    let data = &envelope.data;
+    let path = Value::from(envelope.path.clone());
+    let algorithm = cfg.get_path_checksum_algorithm(path.as_str().unwrap_or_default());
+    let mut file = get_file(&path, &data["file_size"], &data["checksum"], &algorithm);
+    merge(&mut file, json!({
+        "inode": data["inode"],
+        "mode": data["mode"],
+        "uid": data["ouid"],
+        "gid": data["ogid"],
+        "device": data["dev"]
+    }));
+    let audit_data: Map<String, Value> = AUDIT_DATA.iter()
+        .map(|key| (String::from(*key), data[*key].clone())).collect();
+    json!({
+        "event": {
+            "provider": data["source"],
+            "outcome": match data["success"].as_str() {
+                Some("yes") => "success",
+                Some("no") => "failure",
+                _ => "unknown"
+            }
+        },
+        "file": file,
+        "process": {
+            "pid": get_number(&data["pid"]),
+            "parent": { "pid": get_number(&data["ppid"]) },
+            "name": data["comm"],
+            "executable": data["exe"],
+            "title": data["proctitle"],
+            "command_line": data["command"],
+            "working_directory": data["cwd"]
+        },
+        "user": {
+            "id": data["uid"],
+            "group": { "id": data["gid"] },
+            "audit": { "id": data["auid"] },
+            "effective": { "id": data["euid"], "group": { "id": data["egid"] } },
+            "saved": { "id": data["suid"], "group": { "id": data["sgid"] } },
+            "filesystem": { "id": data["fsuid"], "group": { "id": data["fsgid"] } }
+        },
+        "auditd": {
+            "session": data["ses"],
+            "key": data["key"],
+            "paths": data["paths"],
+            "data": audit_data
+        }
+    })
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_hash(envelope: &Envelope, cfg: &AppConfig) -> Value {
+    let data = &envelope.data;
+    let algorithm = &cfg.hashscanner_algorithm;
+    let mut file = get_file(&data["dbfile.path"], &data["dbfile.size"], &data["dbfile.hash"], algorithm);
+    merge(&mut file, json!({ "mode": get_mode(&data["dbfile.permissions"]) }));
+    let mut previous = get_file(&data["previous_dbfile.path"], &data["previous_dbfile.size"],
+        &data["previous_dbfile.hash"], algorithm);
+    merge(&mut previous, json!({ "mode": get_mode(&data["previous_dbfile.permissions"]) }));
+    json!({
+        "@timestamp": get_time(&data["dbfile.timestamp"]),
+        "event": { "id": data["dbfile.id"] },
+        "file": file,
+        "fim": { "previous": { "file": match data["previous_dbfile.path"].is_null() {
+            true => Value::Null,
+            false => previous
+        }}}
+    })
+}
+
+// ----------------------------------------------------------------------------
+
+/// ECS document of an event. Each event type maps its fields to the nested
+/// ECS fields, the ones without an ECS equivalent are kept under `fim`.
+pub fn to_ecs(envelope: &Envelope, cfg: &AppConfig) -> Value {
+    let data = &envelope.data;
+    let kind = envelope.kind.as_deref().unwrap_or("event");
+    let operation = data["operation"].as_str().unwrap_or_default();
+    let mut document = json!({
+        "@timestamp": get_time(&data["timestamp"]),
+        "ecs": { "version": ECS_VERSION },
+        "event": {
+            "id": envelope.id,
+            "kind": "event",
+            "module": "fim",
+            "dataset": format!("fim.{}", kind),
+            "category": ["file"],
+            "type": [get_type(operation)],
+            "action": operation.to_lowercase()
+        },
+        "host": {
+            "name": data["hostname"],
+            "hostname": data["hostname"],
+            "os": { "type": data["system"] }
+        },
+        "agent": {
+            "type": "fim",
+            "name": data["node"],
+            "version": data["version"]
+        },
+        "tags": data["labels"],
+        "fim": { "pid": data["fpid"] }
+    });
+    let path = Value::from(envelope.path.clone());
+    let fields = match kind {
+        "monitor" => {
+            let algorithm = cfg.get_path_checksum_algorithm(path.as_str().unwrap_or_default());
+            json!({
+                "file": get_file(&path, &data["file_size"], &data["checksum"], &algorithm),
+                "process": { "pid": get_number(&data["pid"]), "name": data["comm"], "executable": data["exe"] },
+                "user": { "id": data["uid"] },
+                "fim": { "detailed_operation": data["detailed_operation"], "source_path": data["source_path"] }
+            })
+        },
+        "audit" => get_audit(envelope, cfg),
+        "hash" => get_hash(envelope, cfg),
+        "rule" => json!({
+            "event": { "kind": "alert", "action": "rule-match", "severity": data["severity"] },
+            "message": data["message"],
+            "rule": { "id": data["id"], "description": data["message"] },
+            "file": { "path": path, "name": Path::new(path.as_str().unwrap_or_default()).file_name()
+                .map(|name| name.to_string_lossy()) },
+            "fim": { "rule": { "pattern": data["rule"] }, "parent_id": data["parent_id"] }
+        }),
+        "app" => json!({
+            "event": { "category": ["process"] },
+            "message": data["message"]
+        }),
+        _ => Value::Null
+    };
+    merge(&mut document, fields);
+    clean(document)
+}
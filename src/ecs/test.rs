@@ -0,0 +1,184 @@
+use super::*;
+
+use crate::utils;
+
+// ----------------------------------------------------------------------------
+
+fn create_test_config() -> AppConfig {
+    AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_format.yml")).unwrap()
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check monitor events become nested file documents
+fn test_to_ecs_monitor() {
+    let envelope = Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "timestamp": "1699063400250",
+        "hostname": "Hostname",
+        "node": "Node",
+        "fpid": 10,
+        "version": "x.x.x",
+        "labels": ["etc"],
+        "operation": "WRITE",
+        "detailed_operation": "MODIFY_DATA",
+        "file": "/etc/hosts.conf",
+        "file_size": 10,
+        "checksum": "abc",
+        "system": "linux"
+    })).with_kind("monitor").with_path("/etc/hosts.conf");
+    assert_eq!(to_ecs(&envelope, &create_test_config()), json!({
+        "@timestamp": "2023-11-04T02:03:20.250Z",
+        "ecs": { "version": ECS_VERSION },
+        "event": {
+            "id": "Test_id",
+            "kind": "event",
+            "module": "fim",
+            "dataset": "fim.monitor",
+            "category": ["file"],
+            "type": ["change"],
+            "action": "write"
+        },
+        "host": { "name": "Hostname", "hostname": "Hostname", "os": { "type": "linux" } },
+        "agent": { "type": "fim", "name": "Node", "version": "x.x.x" },
+        "tags": ["etc"],
+        "file": {
+            "path": "/etc/hosts.conf",
+            "name": "hosts.conf",
+            "directory": "/etc",
+            "extension": "conf",
+            "size": 10,
+            "hash": { "sha256": "abc" }
+        },
+        "fim": { "pid": 10, "detailed_operation": "MODIFY_DATA" }
+    }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check monitor events of the fanotify watcher carry their process
+fn test_to_ecs_monitor_process() {
+    let envelope = Envelope::new("Test_id", json!({
+        "operation": "WRITE",
+        "file": "/etc/hosts",
+        "pid": "10",
+        "exe": "/usr/bin/vim",
+        "comm": "vim",
+        "uid": "1000"
+    })).with_kind("monitor").with_path("/etc/hosts");
+    let document = to_ecs(&envelope, &create_test_config());
+    assert_eq!(document["process"], json!({ "pid": 10, "name": "vim", "executable": "/usr/bin/vim" }));
+    assert_eq!(document["user"], json!({ "id": "1000" }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check audit records map to process, user and auditd fields
+fn test_to_ecs_audit() {
+    let envelope = Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "operation": "DELETE",
+        "file": "test",
+        "inode": "1234",
+        "ouid": "0",
+        "pid": "4321",
+        "ppid": "1",
+        "comm": "rm",
+        "exe": "/usr/bin/rm",
+        "cwd": "/tmp",
+        "uid": "1000",
+        "euid": "0",
+        "auid": "1000",
+        "success": "yes",
+        "syscall": "263",
+        "a0": "ffffff9c",
+        "ses": "3",
+        "source": "audit"
+    })).with_kind("audit").with_path("/tmp/test");
+    let document = to_ecs(&envelope, &create_test_config());
+    assert_eq!(document["event"]["type"], json!(["deletion"]));
+    assert_eq!(document["event"]["outcome"], "success");
+    assert_eq!(document["event"]["provider"], "audit");
+    assert_eq!(document["file"]["path"], "/tmp/test");
+    assert_eq!(document["file"]["inode"], "1234");
+    assert_eq!(document["file"]["uid"], "0");
+    assert_eq!(document["process"], json!({
+        "pid": 4321,
+        "parent": { "pid": 1 },
+        "name": "rm",
+        "executable": "/usr/bin/rm",
+        "working_directory": "/tmp"
+    }));
+    assert_eq!(document["user"], json!({ "id": "1000", "audit": { "id": "1000" }, "effective": { "id": "0" } }));
+    assert_eq!(document["auditd"], json!({ "session": "3", "data": { "syscall": "263", "a0": "ffffff9c" } }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check hash events use the scanner algorithm and keep the previous file
+fn test_to_ecs_hash() {
+    let envelope = Envelope::new("Db_id", json!({
+        "previous_dbfile.path": "/etc/passwd",
+        "previous_dbfile.hash": "old",
+        "previous_dbfile.permissions": 100600,
+        "dbfile.id": "Db_id",
+        "dbfile.timestamp": "1699063400250",
+        "dbfile.hash": "new",
+        "dbfile.path": "/etc/passwd",
+        "dbfile.size": 10,
+        "dbfile.permissions": 100644,
+        "operation": "WRITE"
+    })).with_kind("hash").with_path("/etc/passwd");
+    let document = to_ecs(&envelope, &create_test_config());
+    assert_eq!(document["@timestamp"], "2023-11-04T02:03:20.250Z");
+    assert_eq!(document["event"]["id"], "Db_id");
+    assert_eq!(document["event"]["dataset"], "fim.hash");
+    assert_eq!(document["file"]["hash"], json!({ "sha256": "new" }));
+    assert_eq!(document["file"]["mode"], "0644");
+    assert_eq!(document["fim"]["previous"]["file"]["hash"], json!({ "sha256": "old" }));
+    assert_eq!(document["fim"]["previous"]["file"]["mode"], "0600");
+
+    let envelope = Envelope::new("Db_id", json!({ "dbfile.path": "/etc/passwd", "operation": "CREATE" }))
+        .with_kind("hash");
+    assert!(to_ecs(&envelope, &create_test_config())["fim"].get("previous").is_none());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check rule events are alerts with the rule severity and their own event id
+fn test_to_ecs_rule() {
+    let envelope = Envelope::new("0000-1111-2222", json!({
+        "id": 1,
+        "rule": "\\.sh$",
+        "timestamp": "1699063400250",
+        "message": "Shell script",
+        "severity": 9,
+        "parent_id": "0000"
+    })).with_kind("rule").with_path("/etc/test.sh");
+    let document = to_ecs(&envelope, &create_test_config());
+    assert_eq!(document["event"]["kind"], "alert");
+    assert_eq!(document["event"]["severity"], 9);
+    assert_eq!(document["event"]["id"], "0000-1111-2222");
+    assert_eq!(document["message"], "Shell script");
+    assert_eq!(document["rule"], json!({ "id": 1, "description": "Shell script" }));
+    assert_eq!(document["file"], json!({ "path": "/etc/test.sh", "name": "test.sh" }));
+    assert_eq!(document["fim"], json!({ "rule": { "pattern": "\\.sh$" }, "parent_id": "0000" }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check empty values are removed and nested objects merged
+fn test_merge_clean() {
+    let mut target = json!({ "a": { "b": 1, "c": 2 }, "d": "" });
+    merge(&mut target, json!({ "a": { "c": 3, "e": null }, "f": [] }));
+    assert_eq!(clean(target), json!({ "a": { "b": 1, "c": 3 } }));
+    assert_eq!(get_time(&json!("Timestamp")), Value::Null);
+    assert_eq!(get_number(&json!("12")), json!(12));
+    assert_eq!(get_number(&json!("0x12")), json!("0x12"));
+}
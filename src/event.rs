@@ -2,13 +2,16 @@
 
 use crate::appconfig::*;
 use crate::ruleset::*;
+use crate::output::Envelope;
 use notify::event::*;
 
+// Operation of a rename with both paths known
+pub const MOVE: &str = "MOVE";
+
 pub trait Event {
     fn format_json(&self) -> String;
     fn clone(&self) -> Self;
-    fn log(&self, cfg: AppConfig);
-    async fn send(&self, cfg: AppConfig);
+    fn envelope(&self) -> Envelope;
     async fn process(&self, cfg: AppConfig, _ruleset: Ruleset);
     fn get_string(&self, field: String) -> String;
 }
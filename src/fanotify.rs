@@ -0,0 +1,284 @@
+// Copyright (C) 2026, Achiefs.
+
+// To watch whole file systems with fanotify and know the process of each change
+use notify::event::{AccessKind, AccessMode, CreateKind, DataChange, EventKind, MetadataKind,
+    ModifyKind, RemoveKind, RenameMode};
+use notify::Event;
+use log::{debug, error, warn};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::{ptr, thread};
+
+use crate::process::Process;
+
+#[cfg(test)]
+mod test;
+
+// Changes reported, of files and folders
+const MASK: u64 = libc::FAN_CREATE | libc::FAN_DELETE | libc::FAN_MODIFY | libc::FAN_ATTRIB |
+    libc::FAN_CLOSE_WRITE | libc::FAN_MOVED_FROM | libc::FAN_MOVED_TO | libc::FAN_ONDIR;
+// Size of struct fanotify_event_metadata
+const METADATA_LEN: usize = 24;
+
+// File system id as reported by statfs and the fanotify records
+type Fsid = [i32; 2];
+
+// ----------------------------------------------------------------------------
+
+// Record of a change, the folder is a file handle to resolve in its file system
+#[derive(Clone, Debug, PartialEq)]
+struct Record {
+    mask: u64,
+    pid: i32,
+    fsid: Fsid,
+    handle: Vec<u8>,
+    name: String
+}
+
+// ----------------------------------------------------------------------------
+
+// Monitored root, with its real path as the one of the events
+#[derive(Debug)]
+struct Root {
+    fsid: Fsid,
+    real: PathBuf
+}
+
+// Roots watched and an open folder of each marked file system
+#[derive(Default)]
+struct State {
+    roots: HashMap<PathBuf, Root>,
+    mounts: HashMap<Fsid, File>
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_u32(buffer: &[u8], offset: usize) -> Option<u32> {
+    buffer.get(offset..offset + 4).map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+fn get_i32(buffer: &[u8], offset: usize) -> Option<i32> {
+    get_u32(buffer, offset).map(|value| value as i32)
+}
+
+// ----------------------------------------------------------------------------
+
+// Records of a read buffer, the ones without folder and name are skipped
+fn get_records(buffer: &[u8]) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset + METADATA_LEN <= buffer.len() {
+        let event = &buffer[offset..];
+        let event_len = get_u32(event, 0).unwrap_or(0) as usize;
+        if event_len < METADATA_LEN || event_len > event.len() {
+            break;
+        }
+        let event = &event[..event_len];
+        offset += event_len;
+        let mask = u64::from_ne_bytes(event[8..16].try_into().unwrap());
+        if mask & libc::FAN_Q_OVERFLOW != 0 {
+            warn!("Fanotify queue overflow, some changes were not received.");
+            continue;
+        }
+        let metadata_len = u16::from_ne_bytes(event[6..8].try_into().unwrap()) as usize;
+        // Header, file system id, then the file handle and the name
+        let info = match event.get(metadata_len..) {
+            Some(info) if info.len() > 20 && info[0] == libc::FAN_EVENT_INFO_TYPE_DFID_NAME => info,
+            _ => continue
+        };
+        let handle_len = 8 + get_u32(info, 12).unwrap_or(0) as usize;
+        let name = match info.get(12 + handle_len..) {
+            Some(name) => name.split(|byte| *byte == 0).next().unwrap_or_default(),
+            None => continue
+        };
+        records.push(Record {
+            mask,
+            pid: get_i32(event, 20).unwrap_or(0),
+            fsid: [get_i32(info, 4).unwrap_or(0), get_i32(info, 8).unwrap_or(0)],
+            handle: info[12..12 + handle_len].to_vec(),
+            name: String::from_utf8_lossy(name).into_owned()
+        });
+    }
+    records
+}
+
+// ----------------------------------------------------------------------------
+
+// Kinds of the changes of a record, several changes may be merged in one
+fn get_kinds(mask: u64) -> Vec<EventKind> {
+    let folder = mask & libc::FAN_ONDIR != 0;
+    let kinds = [
+        (libc::FAN_CREATE, EventKind::Create(if folder { CreateKind::Folder } else { CreateKind::File })),
+        (libc::FAN_MOVED_TO, EventKind::Modify(ModifyKind::Name(RenameMode::To))),
+        (libc::FAN_MODIFY, EventKind::Modify(ModifyKind::Data(DataChange::Any))),
+        (libc::FAN_ATTRIB, EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any))),
+        (libc::FAN_CLOSE_WRITE, EventKind::Access(AccessKind::Close(AccessMode::Write))),
+        (libc::FAN_MOVED_FROM, EventKind::Modify(ModifyKind::Name(RenameMode::From))),
+        (libc::FAN_DELETE, EventKind::Remove(if folder { RemoveKind::Folder } else { RemoveKind::File }))];
+    kinds.iter().filter(|(bit, _)| mask & bit != 0).map(|(_, kind)| *kind).collect()
+}
+
+// ----------------------------------------------------------------------------
+
+// Path of a folder file handle, read from the descriptor opened with it
+fn get_folder(handle: &[u8], mount: &File) -> Option<PathBuf> {
+    // struct file_handle needs the alignment of its integer fields
+    let mut aligned = vec![0u32; handle.len().div_ceil(4)];
+    unsafe { ptr::copy_nonoverlapping(handle.as_ptr(), aligned.as_mut_ptr() as *mut u8, handle.len()) };
+    let fd = unsafe { libc::open_by_handle_at(mount.as_raw_fd(), aligned.as_mut_ptr() as *mut libc::file_handle, libc::O_PATH) };
+    if fd < 0 {
+        return None;
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    fs::read_link(format!("/proc/self/fd/{}", fd.as_raw_fd())).ok()
+}
+
+// ----------------------------------------------------------------------------
+
+// Path of a record under the monitored root that holds it, as configured
+fn get_path(record: &Record, state: &State) -> Option<PathBuf> {
+    let folder = get_folder(&record.handle, state.mounts.get(&record.fsid)?)?;
+    let real = match record.name.as_str() {
+        "." | "" => folder,
+        name => folder.join(name)
+    };
+    state.roots.iter().filter(|(_, root)| root.fsid == record.fsid)
+        .find_map(|(path, root)| real.strip_prefix(&root.real).ok().map(|rest| match rest.as_os_str().is_empty() {
+            true => path.clone(),
+            false => path.join(rest)
+        }))
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_fsid(path: &Path) -> io::Result<Fsid> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    match unsafe { libc::statfs(path.as_ptr(), &mut stat) } {
+        0 => Ok(unsafe { std::mem::transmute::<libc::fsid_t, Fsid>(stat.f_fsid) }),
+        _ => Err(io::Error::last_os_error())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Add or remove the mark of the file system holding an open folder
+fn mark(fd: RawFd, flags: libc::c_uint, folder: &File) -> io::Result<()> {
+    match unsafe { libc::fanotify_mark(fd, flags | libc::FAN_MARK_FILESYSTEM, MASK, folder.as_raw_fd(), ptr::null()) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Send the changes under the monitored roots, with their process, until
+// the receiver is gone
+fn read_events(mut file: File, state: Arc<Mutex<State>>, tx: mpsc::Sender<Result<Event, notify::Error>>) {
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let len = match file.read(&mut buffer) {
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                error!("Could not read fanotify events, description: {}", e);
+                return;
+            }
+        };
+        // Only records of the same read share a process, a pid seen in a
+        // previous read may belong to another program after exec or reuse
+        let mut last = Process::default();
+        for record in get_records(&buffer[..len]) {
+            let path = match get_path(&record, &state.lock().unwrap()) {
+                Some(path) => path,
+                None => continue
+            };
+            // Read now, the process may end before the event is processed, its
+            // following records in this read reuse it as it may be gone for them
+            if last.pid != record.pid.to_string() {
+                last = Process::new(record.pid as u32);
+            }
+            let process = &last;
+            for kind in get_kinds(record.mask) {
+                let event = Event::new(kind).add_path(path.clone())
+                    .set_process_id(record.pid as u32).set_info(&process.to_info());
+                if tx.send(Ok(event)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Watches the whole file systems holding the monitored roots, so there is
+/// no watch per folder, and reports the process of each change. Changes out
+/// of the roots are dropped. It requires Linux 5.9 and CAP_SYS_ADMIN.
+pub struct Fanotify {
+    fd: OwnedFd,
+    state: Arc<Mutex<State>>
+}
+
+impl Fanotify {
+    pub fn new(tx: mpsc::Sender<Result<Event, notify::Error>>) -> io::Result<Self> {
+        let flags = libc::FAN_CLOEXEC | libc::FAN_CLASS_NOTIF | libc::FAN_REPORT_DFID_NAME;
+        let fd = unsafe { libc::fanotify_init(flags, (libc::O_RDONLY | libc::O_LARGEFILE) as libc::c_uint) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let state = Arc::new(Mutex::new(State::default()));
+        let reader = File::from(fd.try_clone()?);
+        let reader_state = state.clone();
+        thread::Builder::new().name("FIM_Fanotify".to_string())
+            .spawn(move || read_events(reader, reader_state, tx))?;
+        Ok(Fanotify { fd, state })
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn is_watched(&self, path: &Path) -> bool {
+        self.state.lock().unwrap().roots.contains_key(path)
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Watches a root, its file system is marked if it isn't yet.
+    pub fn watch(&self, path: &Path) -> io::Result<()> {
+        let fsid = get_fsid(path)?;
+        let real = path.canonicalize()?;
+        let mut state = self.state.lock().unwrap();
+        if let Entry::Vacant(entry) = state.mounts.entry(fsid) {
+            let folder = File::open(path)?;
+            mark(self.fd.as_raw_fd(), libc::FAN_MARK_ADD, &folder)?;
+            debug!("Fanotify mark added to the file system of '{}'.", path.display());
+            entry.insert(folder);
+        }
+        state.roots.insert(path.to_path_buf(), Root { fsid, real });
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Stops watching a root, its file system mark is removed with the last
+    /// root in it.
+    pub fn unwatch(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let root = state.roots.remove(path)
+            .ok_or(io::Error::new(io::ErrorKind::NotFound, "path is not watched"))?;
+        if !state.roots.values().any(|other| other.fsid == root.fsid) {
+            if let Some(folder) = state.mounts.remove(&root.fsid) {
+                mark(self.fd.as_raw_fd(), libc::FAN_MARK_REMOVE, &folder)?;
+            }
+        }
+        Ok(())
+    }
+}
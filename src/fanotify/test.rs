@@ -0,0 +1,105 @@
+use super::*;
+use std::time::Duration;
+
+// ----------------------------------------------------------------------------
+
+// Record as written by the kernel, with its folder file handle and name
+fn create_test_record(mask: u64, pid: i32, handle: &[u8], name: &str) -> Vec<u8> {
+    let mut info = vec![libc::FAN_EVENT_INFO_TYPE_DFID_NAME, 0, 0, 0];
+    info.extend(7i32.to_ne_bytes());
+    info.extend(9i32.to_ne_bytes());
+    info.extend((handle.len() as u32).to_ne_bytes());
+    info.extend(1i32.to_ne_bytes());
+    info.extend(handle);
+    info.extend(name.as_bytes());
+    info.push(0);
+    while info.len() % 4 != 0 {
+        info.push(0);
+    }
+    let info_len = info.len() as u16;
+    info[2..4].copy_from_slice(&info_len.to_ne_bytes());
+
+    let mut record = ((METADATA_LEN + info.len()) as u32).to_ne_bytes().to_vec();
+    record.extend([3, 0]);
+    record.extend((METADATA_LEN as u16).to_ne_bytes());
+    record.extend(mask.to_ne_bytes());
+    record.extend(libc::FAN_NOFD.to_ne_bytes());
+    record.extend(pid.to_ne_bytes());
+    record.extend(info);
+    record
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check records are read with their file system, folder handle and name
+fn test_get_records() {
+    let mut buffer = create_test_record(libc::FAN_CREATE, 10, &[1, 2, 3, 4, 5, 6, 7, 8], "file.txt");
+    buffer.extend(create_test_record(libc::FAN_Q_OVERFLOW, 0, &[], ""));
+    buffer.extend(create_test_record(libc::FAN_DELETE | libc::FAN_ONDIR, 11, &[9; 12], "folder"));
+    let records = get_records(&buffer);
+    assert_eq!(records, vec![
+        Record { mask: libc::FAN_CREATE, pid: 10, fsid: [7, 9], name: String::from("file.txt"),
+            handle: [8u32.to_ne_bytes(), 1i32.to_ne_bytes()].concat().into_iter().chain([1, 2, 3, 4, 5, 6, 7, 8]).collect() },
+        Record { mask: libc::FAN_DELETE | libc::FAN_ONDIR, pid: 11, fsid: [7, 9], name: String::from("folder"),
+            handle: [12u32.to_ne_bytes(), 1i32.to_ne_bytes()].concat().into_iter().chain([9; 12]).collect() }
+    ]);
+    // A truncated record is not read
+    assert!(get_records(&buffer[..METADATA_LEN + 2]).is_empty());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check merged changes give one kind each, folders included
+fn test_get_kinds() {
+    assert_eq!(get_kinds(libc::FAN_CREATE | libc::FAN_MODIFY | libc::FAN_CLOSE_WRITE), vec![
+        EventKind::Create(CreateKind::File),
+        EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+        EventKind::Access(AccessKind::Close(AccessMode::Write))]);
+    assert_eq!(get_kinds(libc::FAN_DELETE | libc::FAN_ONDIR), vec![EventKind::Remove(RemoveKind::Folder)]);
+    assert_eq!(get_kinds(libc::FAN_MOVED_FROM | libc::FAN_ATTRIB), vec![
+        EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any)),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From))]);
+    assert!(get_kinds(libc::FAN_ONDIR).is_empty());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check changes of a watched root are received with their process, it
+/// needs CAP_SYS_ADMIN and a file system supporting fanotify marks
+fn test_watch() {
+    let (tx, rx) = mpsc::channel();
+    let fanotify = match Fanotify::new(tx) {
+        Ok(fanotify) => fanotify,
+        Err(e) => {
+            println!("Fanotify not available, {}", e);
+            return;
+        }
+    };
+    let root = Path::new("./test_fanotify");
+    let _ = fs::remove_dir_all(root);
+    fs::create_dir(root).unwrap();
+    if let Err(e) = fanotify.watch(root) {
+        println!("Fanotify mark not available, {}", e);
+        fs::remove_dir(root).unwrap();
+        return;
+    }
+    assert!(fanotify.is_watched(root));
+    fs::write(root.join("file.txt"), "test").unwrap();
+    let event = loop {
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        if event.kind == EventKind::Create(CreateKind::File) {
+            break event;
+        }
+    };
+    assert_eq!(event.paths, vec![root.join("file.txt")]);
+    assert_eq!(event.attrs.process_id(), Some(std::process::id()));
+    let process = Process::from_event(&event).unwrap();
+    assert_eq!(process.exe, std::env::current_exe().unwrap().to_str().unwrap());
+
+    assert!(fanotify.unwatch(root).is_ok());
+    assert!(fanotify.unwatch(root).is_err());
+    fs::remove_dir_all(root).unwrap();
+}
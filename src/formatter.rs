@@ -0,0 +1,256 @@
+// Copyright (C) 2026, Achiefs.
+
+// To read the event fields
+use serde_json::Value;
+// To format LEEF device time
+use time::OffsetDateTime;
+use std::path::Path;
+
+use crate::appconfig;
+use crate::appconfig::AppConfig;
+use crate::ecs;
+use crate::ocsf;
+use crate::output::Envelope;
+use crate::ruleset;
+use crate::syslog;
+
+#[cfg(test)]
+mod test;
+
+// Event formats a destination can use
+pub const FORMATS: [&str; 5] = ["json", "ecs", "ocsf", "cef", "leef"];
+const VENDOR: &str = "Achiefs";
+const PRODUCT: &str = "FIM";
+// Severity of file events, rule events take the one of the matched rule
+const FILE_SEVERITY: u8 = 3;
+const APP_SEVERITY: u8 = 1;
+const LEEF_TIME_FORMAT: &str = "MMM dd yyyy HH:mm:ss.SSS z";
+
+// Event field, CEF key and LEEF key of each event kind
+const COMMON_FIELDS: [(&str, &str, &str); 5] = [
+    ("id", "externalId", "externalId"),
+    ("hostname", "dvchost", "identHostName"),
+    ("node", "deviceExternalId", "node"),
+    ("fpid", "dvcpid", "fpid"),
+    ("labels", "cs1", "labels")];
+const MONITOR_FIELDS: [(&str, &str, &str); 8] = [
+    ("operation", "act", "action"),
+    ("file_size", "fsize", "fileSize"),
+    ("checksum", "fileHash", "fileHash"),
+    ("source_path", "oldFilePath", "oldFilePath"),
+    ("uid", "suid", "uid"),
+    ("pid", "spid", "pid"),
+    ("comm", "sproc", "proc"),
+    ("detailed_operation", "cs2", "detailedOperation")];
+const AUDIT_FIELDS: [(&str, &str, &str); 11] = [
+    ("operation", "act", "action"),
+    ("file_size", "fsize", "fileSize"),
+    ("checksum", "fileHash", "fileHash"),
+    ("inode", "fileId", "fileId"),
+    ("mode", "filePermission", "filePermission"),
+    ("uid", "suid", "uid"),
+    ("pid", "spid", "pid"),
+    ("comm", "sproc", "proc"),
+    ("command", "cs3", "command"),
+    ("cwd", "cs4", "cwd"),
+    ("success", "outcome", "outcome")];
+const HASH_FIELDS: [(&str, &str, &str); 9] = [
+    ("dbfile.id", "externalId", "externalId"),
+    ("operation", "act", "action"),
+    ("dbfile.size", "fsize", "fileSize"),
+    ("dbfile.hash", "fileHash", "fileHash"),
+    ("dbfile.permissions", "filePermission", "filePermission"),
+    ("previous_dbfile.path", "oldFilePath", "oldFilePath"),
+    ("previous_dbfile.size", "oldFileSize", "oldFileSize"),
+    ("previous_dbfile.hash", "oldFileHash", "oldFileHash"),
+    ("previous_dbfile.permissions", "oldFilePermission", "oldFilePermission")];
+const RULE_FIELDS: [(&str, &str, &str); 3] = [
+    ("message", "msg", "msg"),
+    ("rule", "cs5", "rule"),
+    ("parent_id", "cs6", "parentId")];
+const APP_FIELDS: [(&str, &str, &str); 1] = [
+    ("message", "msg", "msg")];
+
+// ----------------------------------------------------------------------------
+
+/// Event field with its CEF and LEEF keys. CEF custom strings `csN` are
+/// labeled with the LEEF key.
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub cef: &'static str,
+    pub leef: &'static str,
+    pub value: String
+}
+
+/// Header values and fields shared by the CEF and LEEF formats.
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub signature: String,
+    pub name: String,
+    pub severity: u8,
+    pub category: String,
+    pub timestamp: Option<i128>,
+    pub fields: Vec<Field>
+}
+
+// ----------------------------------------------------------------------------
+
+// Scalar or list value of an event field, empty values are skipped
+fn get_value(data: &Value, key: &str) -> Option<String> {
+    match data.get(key)? {
+        Value::String(value) if !value.is_empty() => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        Value::Array(values) if !values.is_empty() => Some(values.iter()
+            .map(|value| value.as_str().map(String::from).unwrap_or(value.to_string()))
+            .collect::<Vec<String>>().join(",")),
+        _ => None
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Event name from its operation, file and audit events use different names
+fn get_name(operation: &str) -> String {
+    String::from(match operation {
+        "CREATE" => "File created",
+        "WRITE" | "NORMAL" => "File modified",
+        "REMOVE" | "DELETE" => "File removed",
+        "MOVE" => "File moved",
+        "ACCESS" => "File accessed",
+        _ => "File changed"
+    })
+}
+
+// ----------------------------------------------------------------------------
+
+/// Map the event fields of an envelope to their CEF and LEEF keys, the
+/// event kind selects which fields are used.
+pub fn get_record(envelope: &Envelope) -> Record {
+    let data = &envelope.data;
+    let kind = envelope.kind.clone().unwrap_or(String::from("event"));
+    let operation = get_value(data, "operation").unwrap_or_default();
+    let (signature, name, severity, kind_fields): (String, String, u8, &[(&str, &str, &str)]) = match kind.as_str() {
+        "monitor" => (format!("monitor:{}", operation), get_name(&operation), FILE_SEVERITY, &MONITOR_FIELDS),
+        "audit" => (format!("audit:{}", operation), get_name(&operation), FILE_SEVERITY, &AUDIT_FIELDS),
+        "hash" => (format!("hash:{}", operation), get_name(&operation), FILE_SEVERITY, &HASH_FIELDS),
+        "rule" => (format!("rule:{}", get_value(data, "id").unwrap_or_default()),
+            get_value(data, "message").unwrap_or(String::from("Rule matched")),
+            data["severity"].as_u64().map(|severity| severity.min(10) as u8).unwrap_or(ruleset::DEFAULT_SEVERITY),
+            &RULE_FIELDS),
+        "app" => (String::from("app"), get_value(data, "message").unwrap_or_default(), APP_SEVERITY, &APP_FIELDS),
+        _ => (kind.clone(), get_name(&operation), FILE_SEVERITY, &[])
+    };
+
+    let mut fields = Vec::new();
+    if let Some(path) = &envelope.path {
+        fields.push(Field { cef: "filePath", leef: "filePath", value: path.clone() });
+        if let Some(file) = Path::new(path).file_name() {
+            fields.push(Field { cef: "fname", leef: "fileName", value: file.to_string_lossy().to_string() });
+        }
+    }
+    // Rule ids are not unique event ids, rule events only keep their specific fields
+    let common: &[(&str, &str, &str)] = match kind.as_str() {
+        "rule" => &COMMON_FIELDS[1..],
+        _ => &COMMON_FIELDS
+    };
+    for (key, cef, leef) in kind_fields.iter().chain(common) {
+        if fields.iter().any(|field| field.cef == *cef) { continue; }
+        if let Some(value) = get_value(data, key) {
+            fields.push(Field { cef, leef, value });
+        }
+    }
+
+    let timestamp = get_value(data, "timestamp").or(get_value(data, "dbfile.timestamp"))
+        .and_then(|timestamp| timestamp.parse().ok());
+    Record { signature, name, severity, category: kind, timestamp, fields }
+}
+
+// ----------------------------------------------------------------------------
+
+// Escape the characters with a meaning in CEF and LEEF headers
+fn escape_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+// ----------------------------------------------------------------------------
+
+// Escape the characters with a meaning in CEF extension values
+fn escape_cef(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('=', "\\=").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+// ----------------------------------------------------------------------------
+
+// Escape the LEEF attribute delimiter and line breaks
+fn escape_leef(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+// ----------------------------------------------------------------------------
+
+/// CEF line of an event, custom string fields get their label.
+pub fn to_cef(envelope: &Envelope) -> String {
+    let record = get_record(envelope);
+    let mut extension = Vec::new();
+    if let Some(timestamp) = record.timestamp {
+        extension.push(format!("rt={}", timestamp));
+    }
+    extension.push(format!("cat={}", escape_cef(&record.category)));
+    for field in &record.fields {
+        extension.push(format!("{}={}", field.cef, escape_cef(&field.value)));
+        if field.cef.starts_with("cs") {
+            extension.push(format!("{}Label={}", field.cef, field.leef));
+        }
+    }
+    format!("CEF:0|{}|{}|{}|{}|{}|{}|{}", VENDOR, PRODUCT, appconfig::VERSION,
+        escape_header(&record.signature), escape_header(&record.name), record.severity, extension.join(" "))
+}
+
+// ----------------------------------------------------------------------------
+
+/// LEEF 1.0 line of an event, attributes are separated by tabs.
+pub fn to_leef(envelope: &Envelope) -> String {
+    let record = get_record(envelope);
+    let mut attributes = vec![
+        format!("cat={}", escape_leef(&record.category)),
+        format!("sev={}", record.severity),
+        format!("name={}", escape_leef(&record.name))];
+    if let Some(time) = record.timestamp.and_then(|timestamp| OffsetDateTime::from_unix_timestamp_nanos(timestamp * 1000000).ok()) {
+        attributes.push(format!("devTime={} {:02} {} {:02}:{:02}:{:02}.{:03} UTC",
+            syslog::MONTHS[time.month() as usize - 1], time.day(), time.year(),
+            time.hour(), time.minute(), time.second(), time.millisecond()));
+        attributes.push(format!("devTimeFormat={}", LEEF_TIME_FORMAT));
+    }
+    for field in &record.fields {
+        attributes.push(format!("{}={}", field.leef, escape_leef(&field.value)));
+    }
+    format!("LEEF:1.0|{}|{}|{}|{}|{}", VENDOR, PRODUCT, appconfig::VERSION,
+        escape_header(&record.signature), attributes.join("\t"))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Event as a line of the given format, json, ecs, ocsf, cef or leef.
+pub fn format(format: &str, envelope: &Envelope, cfg: &AppConfig) -> String {
+    match format {
+        "ecs" => ecs::to_ecs(envelope, cfg).to_string(),
+        "ocsf" => ocsf::to_ocsf(envelope, cfg).to_string(),
+        "cef" => to_cef(envelope),
+        "leef" => to_leef(envelope),
+        _ => envelope.to_json()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Event as a JSON value, the event fields, its ECS document, its OCSF
+/// event or the formatted line.
+pub fn format_value(format: &str, envelope: &Envelope, cfg: &AppConfig) -> Value {
+    match format {
+        "ecs" => ecs::to_ecs(envelope, cfg),
+        "ocsf" => ocsf::to_ocsf(envelope, cfg),
+        "cef" | "leef" => Value::String(self::format(format, envelope, cfg)),
+        _ => envelope.data.clone()
+    }
+}
@@ -0,0 +1,185 @@
+use super::*;
+use serde_json::json;
+
+use crate::utils;
+
+// ----------------------------------------------------------------------------
+
+fn create_monitor_envelope() -> Envelope {
+    Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "timestamp": "1699063400250",
+        "hostname": "Hostname",
+        "node": "Node",
+        "fpid": 0,
+        "version": "x.x.x",
+        "labels": ["etc", "conf"],
+        "operation": "CREATE",
+        "detailed_operation": "CREATE_FILE",
+        "file": "/etc/a=b.conf",
+        "file_size": 10,
+        "checksum": "",
+        "system": "test"
+    })).with_kind("monitor").with_path("/etc/a=b.conf")
+}
+
+// ----------------------------------------------------------------------------
+
+fn create_rule_envelope(severity: Value) -> Envelope {
+    Envelope::new("1", json!({
+        "id": 1,
+        "rule": "\\.sh$",
+        "timestamp": "1699063400250",
+        "hostname": "Hostname",
+        "fpid": 0,
+        "version": "x.x.x",
+        "system": "test",
+        "message": "Shell | script",
+        "severity": severity,
+        "parent_id": "0000"
+    })).with_source("FIM_RULESET").with_kind("rule").with_path("/etc/test.sh")
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check monitor events map to file fields and empty values are skipped
+fn test_get_record_monitor() {
+    let record = get_record(&create_monitor_envelope());
+    assert_eq!(record.signature, "monitor:CREATE");
+    assert_eq!(record.name, "File created");
+    assert_eq!(record.severity, FILE_SEVERITY);
+    assert_eq!(record.category, "monitor");
+    assert_eq!(record.timestamp, Some(1699063400250));
+    let keys: Vec<&str> = record.fields.iter().map(|field| field.cef).collect();
+    assert_eq!(keys, vec!["filePath", "fname", "act", "fsize", "cs2", "externalId",
+        "dvchost", "deviceExternalId", "dvcpid", "cs1"]);
+    assert_eq!(record.fields[1].value, "a=b.conf");
+    assert_eq!(record.fields[9].value, "etc,conf");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check rule events take the severity and message of the matched rule
+fn test_get_record_rule() {
+    let record = get_record(&create_rule_envelope(json!(9)));
+    assert_eq!(record.signature, "rule:1");
+    assert_eq!(record.name, "Shell | script");
+    assert_eq!(record.severity, 9);
+    assert!(record.fields.contains(&Field { cef: "cs5", leef: "rule", value: String::from("\\.sh$") }));
+    assert!(record.fields.contains(&Field { cef: "cs6", leef: "parentId", value: String::from("0000") }));
+    assert!(!record.fields.iter().any(|field| field.cef == "externalId"));
+
+    assert_eq!(get_record(&create_rule_envelope(Value::Null)).severity, ruleset::DEFAULT_SEVERITY);
+    assert_eq!(get_record(&create_rule_envelope(json!(50))).severity, 10);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check hash events keep the previous file values and the database id
+fn test_get_record_hash() {
+    let envelope = Envelope::new("Db_id", json!({
+        "previous_dbfile.hash": "old",
+        "previous_dbfile.size": 5,
+        "dbfile.id": "Db_id",
+        "dbfile.timestamp": "1699063400250",
+        "dbfile.hash": "new",
+        "dbfile.path": "/etc/passwd",
+        "dbfile.size": 10,
+        "dbfile.permissions": 420,
+        "operation": "WRITE"
+    })).with_kind("hash").with_path("/etc/passwd");
+    let record = get_record(&envelope);
+    assert_eq!(record.signature, "hash:WRITE");
+    assert_eq!(record.name, "File modified");
+    assert_eq!(record.timestamp, Some(1699063400250));
+    assert!(record.fields.contains(&Field { cef: "externalId", leef: "externalId", value: String::from("Db_id") }));
+    assert!(record.fields.contains(&Field { cef: "fileHash", leef: "fileHash", value: String::from("new") }));
+    assert!(record.fields.contains(&Field { cef: "oldFileHash", leef: "oldFileHash", value: String::from("old") }));
+    assert!(record.fields.contains(&Field { cef: "oldFileSize", leef: "oldFileSize", value: String::from("5") }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check audit events map the process fields
+fn test_get_record_audit() {
+    let envelope = Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "operation": "DELETE",
+        "file": "test",
+        "uid": "1000",
+        "pid": "1234",
+        "comm": "rm",
+        "cwd": "/tmp",
+        "success": "yes"
+    })).with_kind("audit").with_path("/tmp/test");
+    let record = get_record(&envelope);
+    assert_eq!(record.signature, "audit:DELETE");
+    assert_eq!(record.name, "File removed");
+    let fields: Vec<(&str, &str)> = record.fields.iter().map(|field| (field.cef, field.value.as_str())).collect();
+    assert_eq!(fields, vec![("filePath", "/tmp/test"), ("fname", "test"), ("act", "DELETE"), ("suid", "1000"),
+        ("spid", "1234"), ("sproc", "rm"), ("cs4", "/tmp"), ("outcome", "yes"), ("externalId", "Test_id")]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check app events and envelopes without a kind
+fn test_get_record_other() {
+    let record = get_record(&Envelope::new("Test_id", json!({ "message": "Started" })).with_kind("app"));
+    assert_eq!((record.signature.as_str(), record.name.as_str(), record.severity), ("app", "Started", APP_SEVERITY));
+
+    let record = get_record(&Envelope::new("Test_id", json!({ "id": "Test_id" })));
+    assert_eq!(record.category, "event");
+    assert_eq!(record.name, "File changed");
+    assert_eq!(record.timestamp, None);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check CEF header and extension escaping and custom string labels
+fn test_to_cef() {
+    assert_eq!(to_cef(&create_monitor_envelope()), format!("CEF:0|Achiefs|FIM|{}|monitor:CREATE|File created|3|\
+        rt=1699063400250 cat=monitor filePath=/etc/a\\=b.conf fname=a\\=b.conf act=CREATE fsize=10 \
+        cs2=CREATE_FILE cs2Label=detailedOperation externalId=Test_id dvchost=Hostname deviceExternalId=Node \
+        dvcpid=0 cs1=etc,conf cs1Label=labels", appconfig::VERSION));
+    let cef = to_cef(&create_rule_envelope(json!(9)));
+    assert!(cef.contains("|rule:1|Shell \\| script|9|"));
+    assert!(cef.contains(" msg=Shell | script cs5=\\\\.sh$ cs5Label=rule "));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check LEEF attributes are tab separated and the device time format
+fn test_to_leef() {
+    assert_eq!(to_leef(&create_monitor_envelope()), format!("LEEF:1.0|Achiefs|FIM|{}|monitor:CREATE|\
+        cat=monitor\tsev=3\tname=File created\tdevTime=Nov 04 2023 02:03:20.250 UTC\t\
+        devTimeFormat=MMM dd yyyy HH:mm:ss.SSS z\tfilePath=/etc/a=b.conf\tfileName=a=b.conf\taction=CREATE\t\
+        fileSize=10\tdetailedOperation=CREATE_FILE\texternalId=Test_id\tidentHostName=Hostname\tnode=Node\t\
+        fpid=0\tlabels=etc,conf", appconfig::VERSION));
+    let envelope = Envelope::new("Test_id", json!({ "message": "a\tb\nc" })).with_kind("app");
+    assert!(to_leef(&envelope).ends_with("\tmsg=a\\tb\\nc"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check JSON keeps the event fields and other formats produce a line
+fn test_format() {
+    let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+    let envelope = create_monitor_envelope();
+    assert_eq!(format("json", &envelope, &cfg), envelope.to_json());
+    assert_eq!(format("ecs", &envelope, &cfg), ecs::to_ecs(&envelope, &cfg).to_string());
+    assert_eq!(format("ocsf", &envelope, &cfg), ocsf::to_ocsf(&envelope, &cfg).to_string());
+    assert_eq!(format("cef", &envelope, &cfg), to_cef(&envelope));
+    assert_eq!(format("leef", &envelope, &cfg), to_leef(&envelope));
+    assert_eq!(format_value("json", &envelope, &cfg), envelope.data);
+    assert_eq!(format_value("ecs", &envelope, &cfg), ecs::to_ecs(&envelope, &cfg));
+    assert_eq!(format_value("ocsf", &envelope, &cfg), ocsf::to_ocsf(&envelope, &cfg));
+    assert_eq!(format_value("leef", &envelope, &cfg), Value::String(to_leef(&envelope)));
+}
@@ -18,12 +18,16 @@ use std::fs::File;
 use std::path::Path;
 // To read file content
 use std::io::{BufRead, BufReader};
+// To load the algorithm from config
+use serde::Deserialize;
 
 // ----------------------------------------------------------------------------
 
 #[derive(Debug)]
 #[derive(PartialEq)]
 #[derive(Clone)]
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
 pub enum ShaType {
     Sha224,
     Sha256,
@@ -35,6 +39,41 @@ pub enum ShaType {
     Keccak512
 }
 
+impl TryFrom<String> for ShaType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "sha224"|"224"|"SHA224"|"Sha224" => Ok(ShaType::Sha224),
+            "sha256"|"256"|"SHA256"|"Sha256" => Ok(ShaType::Sha256),
+            "sha384"|"384"|"SHA384"|"Sha384" => Ok(ShaType::Sha384),
+            "sha512"|"512"|"SHA512"|"Sha512" => Ok(ShaType::Sha512),
+            "keccak224"|"K224"|"KECCAK224"|"Keccak224" => Ok(ShaType::Keccak224),
+            "keccak256"|"K256"|"KECCAK256"|"Keccak256" => Ok(ShaType::Keccak256),
+            "keccak384"|"K384"|"KECCAK384"|"Keccak384" => Ok(ShaType::Keccak384),
+            "keccak512"|"K512"|"KECCAK512"|"Keccak512" => Ok(ShaType::Keccak512),
+            _ => Err(format!("unknown algorithm '{}', expected one of sha224, sha256, \
+                sha384, sha512, keccak224, keccak256, keccak384, keccak512", value))
+        }
+    }
+}
+
+impl ShaType {
+    /// Lowercase name of the algorithm, as used in configuration files.
+    pub fn name(&self) -> &str {
+        match self {
+            ShaType::Sha224 => "sha224",
+            ShaType::Sha256 => "sha256",
+            ShaType::Sha384 => "sha384",
+            ShaType::Sha512 => "sha512",
+            ShaType::Keccak224 => "keccak224",
+            ShaType::Keccak256 => "keccak256",
+            ShaType::Keccak384 => "keccak384",
+            ShaType::Keccak512 => "keccak512"
+        }
+    }
+}
+
 #[cfg(test)]
 mod test;
 
@@ -274,4 +274,13 @@ fn test_hex_to_ascii() {
 #[test]
 fn test_hex_to_ascii_bad() {
     assert_eq!(hex_to_ascii(String::from("ABC")), "");
-}
\ No newline at end of file
+}
+
+// ------------------------------------------------------------------------
+
+#[test]
+fn test_sha_type_name() {
+    for name in ["sha224", "sha256", "sha384", "sha512", "keccak224", "keccak256", "keccak384", "keccak512"] {
+        assert_eq!(ShaType::try_from(String::from(name)).unwrap().name(), name);
+    }
+}
@@ -31,7 +31,7 @@ fn create_test_event() -> HashEvent {
 #[test]
 /// Check new instance creation, the instance should match the given values
 fn test_new() {
-    let cfg = AppConfig::new(&utils::get_os(), None);
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
     let previous_dbfile = DBFile::new(cfg.clone(), "LICENSE", None);
     let dbfile = DBFile::new(cfg.clone(), "README.md", None);
     let event = HashEvent::new(Some(previous_dbfile.clone()), dbfile.clone(), String::from("NEW"));
@@ -62,11 +62,11 @@ fn test_new() {
 #[test]
 /// Check the hashEvent JSON log write, it should match with expected one
 fn test_log() {
-    let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log_hashevent.yml"));
+    let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log_hashevent.yml")).unwrap();
     let filename = String::from("test_hashevent.json");
     let event = create_test_event();
 
-    event.log(cfg.clone());
+    block_on( output::route(&event.envelope(), FILE_MODE, &cfg) );
     let contents = fs::read_to_string(filename.clone());
     let expected = "{\"dbfile.hash\":\"HASHC\",\"dbfile.id\":\"CURRENT\",\
         \"dbfile.path\":\"PATHC\",\"dbfile.permissions\":1,\"dbfile.size\":1234,\
@@ -84,8 +84,8 @@ fn test_log() {
 /// Check event sending to ElasticSearch, it should not fail
 fn test_send() {
     let evt = create_test_event();
-    let cfg = AppConfig::new(&utils::get_os(), None);
-    block_on( evt.send(cfg) );
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+    block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
 }
 
 // ------------------------------------------------------------------------
@@ -94,8 +94,8 @@ fn test_send() {
 /// Check event sending to Splunk, it should not fail
 fn test_send_splunk() {
     let evt = create_test_event();
-    let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml"));
-    block_on( evt.send(cfg) );
+    let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+    block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
 }
 
 // ------------------------------------------------------------------------
@@ -104,7 +104,7 @@ fn test_send_splunk() {
 /// Check default event process, it should not fail
 fn test_process() {
     let event = create_test_event();
-    let cfg = AppConfig::new(&utils::get_os(), None); 
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap(); 
 
     block_on(event.process(cfg));
 }
@@ -113,14 +113,14 @@ fn test_process() {
 
 #[test]
 /// Check JSON output format, it should match the expected output
-fn test_format_json() {
+fn test_envelope() {
     let expected = "{\"dbfile.hash\":\"HASHC\",\"dbfile.id\":\"CURRENT\",\
     \"dbfile.path\":\"PATHC\",\"dbfile.permissions\":1,\"dbfile.size\":1234,\
     \"dbfile.timestamp\":\"TIMESTAMPC\",\"operation\":\"NEW\",\
     \"previous_dbfile.hash\":\"HASH\",\"previous_dbfile.id\":\"PREVIOUS\",\
     \"previous_dbfile.path\":\"PATH\",\"previous_dbfile.permissions\":0,\
     \"previous_dbfile.size\":123,\"previous_dbfile.timestamp\":\"TIMESTAMP\"}";
-    assert_eq!(create_test_event().format_json(), expected);
+    assert_eq!(create_test_event().envelope().to_json(), expected);
 }
 
 // ------------------------------------------------------------------------
@@ -148,7 +148,7 @@ pub fn scan(cfg: AppConfig) {
     loop{
 
         for element in config_paths.clone() {
-            let path = String::from(element["path"].as_str().unwrap());
+            let path = element.root();
             match Path::new(&path).exists() {
                 true => {
                     if db.is_empty() {
@@ -168,4 +168,4 @@ pub fn scan(cfg: AppConfig) {
         thread::sleep(Duration::from_secs(interval.try_into().unwrap()));
     }
 
-}
\ No newline at end of file
+}
\ No newline at end of file
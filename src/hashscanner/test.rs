@@ -28,7 +28,7 @@ fn remove_db(path: &str) {
 #[cfg(target_family = "unix")]
 /// Check dir scan result, DB should contains a DBFile definition with the given filepath
 fn test_scan_path_unix() {
-    let cfg = AppConfig::new(&utils::get_os(), None);
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
     let tdb = DB::new(&cfg.hashscanner_file);
     let scan_dir = String::from("./tmp/test_scan_path");
     let filepath = format!("{}/{}", scan_dir.clone(), "test_scan_path.txt");
@@ -57,7 +57,7 @@ fn test_scan_path_unix() {
 #[cfg(target_family = "unix")]
 /// Check that modify a file is reflected in DB, it should modify the size and hash of DBFile
 fn test_check_path() {
-    let cfg = AppConfig::new(&utils::get_os(), None);
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
     let tdb = DB::new(&cfg.hashscanner_file);
     let scan_dir = String::from("./tmp/test_check_path");
     let filepath = format!("{}/{}", scan_dir.clone(), "test_check_path.txt");
@@ -89,7 +89,7 @@ fn test_check_path() {
 #[should_panic(expected = "DBFileNotFoundError")]
 /// Check file deletion of filesystem and DB, it should panic with DBFileNotFoundError
 fn test_update_db() {
-    let cfg = AppConfig::new(&utils::get_os(), None);
+    let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
     let tdb = DB::new(&cfg.hashscanner_file);
     let scan_dir = String::from("./tmp/test_update_db");
     let filepath = format!("{}/{}", scan_dir.clone(), "test_update_db.txt");
@@ -1,13 +1,12 @@
 // Copyright (C) 2021, Achiefs.
 
 // To use files IO operations.
-use tokio::fs::File;
-use tokio_util::codec::{BytesCodec, FramedRead};
+use std::fs;
 // To manage HTTP requests
-use reqwest::{Client, Body};
-use reqwest::header;
+use reqwest::Client;
+use serde_json::{json, Value};
 // To log the program process
-use log::{info, debug, error};
+use log::{info, debug};
 // To manage paths
 use std::path::Path;
 // Handle time intervals
@@ -15,56 +14,128 @@ use std::time::Duration;
 use std::env;
 
 use crate::appconfig::*;
+use crate::configfile::IlmConfig;
+use crate::ecs;
+use crate::output;
 use crate::utils;
 
-fn get_template_path() -> String {
-    let relative_path = "./../../config/index_template.json";
-    let config_path = "/etc/fim/index_template.json";
-    let default_path = "config/index_template.json";
+// Name of the index template and lifecycle policy
+const NAME: &str = "fim";
+
+// Template of the documents sent, ECS documents use their own mappings
+fn get_template_name(cfg: &AppConfig) -> &str {
+    match cfg.format_network.as_str() {
+        "ecs" => "index_template_ecs.json",
+        _ => "index_template.json"
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_template_path(name: &str) -> String {
+    let relative_path = format!("./../../config/{}", name);
+    let config_path = format!("/etc/fim/{}", name);
+    let default_path = format!("config/{}", name);
     let executable_path = env::current_exe().unwrap();
     
-    if Path::new(default_path).exists() {
-        String::from(default_path)
-    }else if Path::new("./index_template.json").exists() {
-        String::from("./index_template.json")
-    }else if Path::new(relative_path).exists() {
-        String::from(relative_path)
-    }else if Path::new(config_path).exists() {
-        String::from(config_path)
+    if Path::new(&default_path).exists() {
+        default_path
+    }else if Path::new(&format!("./{}", name)).exists() {
+        format!("./{}", name)
+    }else if Path::new(&relative_path).exists() {
+        relative_path
+    }else if Path::new(&config_path).exists() {
+        config_path
     }else if utils::get_os() != "windows" {
-        format!("{}/{}", executable_path.clone().parent().unwrap().to_str().unwrap(), "index_template.json")
+        format!("{}/{}", executable_path.clone().parent().unwrap().to_str().unwrap(), name)
     }else{
-        format!("{}\\{}", executable_path.clone().parent().unwrap().to_str().unwrap(), "index_template.json")
+        format!("{}\\{}", executable_path.clone().parent().unwrap().to_str().unwrap(), name)
     }
 }
 
 // ----------------------------------------------------------------------------
 
-pub async fn push_template(cfg: AppConfig){
-    let template_path = get_template_path();
-    info!("Loaded index template from: {}", template_path);
-    let file = File::open(template_path).await.unwrap();
-    let stream = FramedRead::new(file, BytesCodec::new());
-    let body = Body::wrap_stream(stream);
-    let url = format!("{}/_template/fim", cfg.endpoint_address);
+/// Lifecycle policy of the FIM indices. Data streams roll over their
+/// backing index by size or age, old indices are removed when `delete_after`
+/// is set.
+pub fn get_policy(ilm: &IlmConfig, data_stream: bool) -> Value {
+    let mut phases = json!({ "hot": { "actions": {} } });
+    if data_stream {
+        phases["hot"]["actions"]["rollover"] = json!({
+            "max_primary_shard_size": ilm.rollover_size,
+            "max_age": ilm.rollover_age
+        });
+    }
+    if let Some(delete_after) = &ilm.delete_after {
+        phases["delete"] = json!({ "min_age": delete_after, "actions": { "delete": {} } });
+    }
+    json!({ "policy": { "_meta": { "managed_by": "fim" }, "phases": phases } })
+}
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(120))
-        .danger_accept_invalid_certs(cfg.insecure)
-        .build().unwrap();
+// ----------------------------------------------------------------------------
+
+/// Composable index template built from the mappings of the template file,
+/// matching the configured index and linked to the lifecycle policy.
+pub fn get_template(cfg: &AppConfig, file: &Value) -> Value {
+    let mut settings = file["settings"].clone();
+    if cfg.elastic_ilm.is_some() {
+        ecs::merge(&mut settings, json!({ "index": { "lifecycle": { "name": NAME } } }));
+    }
+    let mut mappings = file["mappings"].clone();
+    let mut template = json!({
+        "index_patterns": [output::get_index_pattern(&cfg.elastic_index)],
+        "priority": 100,
+        "_meta": { "managed_by": "fim", "version": VERSION }
+    });
+    if cfg.elastic_data_stream {
+        template["data_stream"] = json!({});
+        ecs::merge(&mut mappings, json!({ "properties": { "@timestamp": { "type": "date" } } }));
+    }
+    template["template"] = ecs::clean(json!({ "settings": settings, "mappings": mappings }));
+    template
+}
+
+// ----------------------------------------------------------------------------
+
+// Elasticsearch answers acknowledged true once the request is applied
+async fn put(cfg: &AppConfig, client: &Client, path: &str, body: &Value) -> Result<(), String> {
     let response = client
-        .put(url)
-        .header(header::CONTENT_TYPE, "application/json")
-        .basic_auth(cfg.endpoint_user, Some(cfg.endpoint_pass))
-        .body(body)
+        .put(format!("{}/{}", cfg.endpoint_address, path))
+        .timeout(Duration::from_secs(120))
+        .basic_auth(&cfg.endpoint_user, Some(&cfg.endpoint_pass))
+        .json(body)
         .send()
         .await;
+    let text = output::check_response(response).await?;
+    let body: Value = serde_json::from_str(&text).unwrap_or_default();
+    match body["acknowledged"].as_bool() {
+        Some(true) => Ok(()),
+        _ => Err(format!("request not acknowledged, {}", text))
+    }
+}
+
+// ----------------------------------------------------------------------------
 
-    match response {
-        Ok(response) => debug!("Push index template response: {:?}",
-                        response.text().await.unwrap()),
-        Err(e) => error!("Error on request: {:?}", e)
+/// Push the lifecycle policy, when configured, and the index template,
+/// returning an error if Elasticsearch does not acknowledge any of them.
+pub async fn push_template(cfg: AppConfig) -> Result<(), String> {
+    let template_path = get_template_path(get_template_name(&cfg));
+    info!("Loaded index template from: {}", template_path);
+    let text = fs::read_to_string(&template_path)
+        .map_err(|e| format!("unable to read index template '{}', {}", template_path, e))?;
+    let file: Value = serde_json::from_str(&text)
+        .map_err(|e| format!("invalid index template '{}', {}", template_path, e))?;
+
+    let client = output::get_client(&cfg)?;
+    if let Some(ilm) = &cfg.elastic_ilm {
+        put(&cfg, &client, &format!("_ilm/policy/{}", NAME), &get_policy(ilm, cfg.elastic_data_stream)).await
+            .map_err(|e| format!("lifecycle policy not pushed, {}", e))?;
+        debug!("Lifecycle policy '{}' pushed", NAME);
     }
+    put(&cfg, &client, &format!("_index_template/{}", NAME), &get_template(&cfg, &file)).await
+        .map_err(|e| format!("index template not pushed, {}", e))?;
+    info!("Index template '{}' pushed for '{}'", NAME, output::get_index_pattern(&cfg.elastic_index));
+    Ok(())
 }
 
 // ----------------------------------------------------------------------------
@@ -76,13 +147,57 @@ mod tests {
 
     #[test]
     fn test_push_template() {
-        tokio_test::block_on( push_template(
-            AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_push_template.yml"))));
+        let result = tokio_test::block_on( push_template(
+            AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap()));
+        assert!(result.unwrap_err().starts_with("index template not pushed, error on request"));
+    }
+
+    #[test]
+    fn test_get_template() {
+        let file = json!({ "order": 0, "index_patterns": ["fim-*"], "mappings": { "properties": { "file": { "type": "keyword" } } } });
+        let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert_eq!(get_template(&cfg, &file), json!({
+            "index_patterns": ["fim-*"],
+            "priority": 100,
+            "_meta": { "managed_by": "fim", "version": VERSION },
+            "template": { "mappings": { "properties": { "file": { "type": "keyword" } } } }
+        }));
+
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_elastic.yml")).unwrap();
+        let template = get_template(&cfg, &file);
+        assert_eq!(template["index_patterns"], json!(["fim-events"]));
+        assert_eq!(template["data_stream"], json!({}));
+        assert_eq!(template["template"]["settings"], json!({ "index": { "lifecycle": { "name": "fim" } } }));
+        assert_eq!(template["template"]["mappings"]["properties"]["@timestamp"], json!({ "type": "date" }));
+
+        let file: Value = serde_json::from_str(&fs::read_to_string(get_template_path("index_template.json")).unwrap()).unwrap();
+        assert!(get_template(&cfg, &file)["template"]["mappings"]["properties"]["fpid"].is_object());
+    }
+
+    #[test]
+    fn test_get_policy() {
+        let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_elastic.yml")).unwrap();
+        let ilm = cfg.elastic_ilm.unwrap();
+        assert_eq!(get_policy(&ilm, true)["policy"]["phases"], json!({
+            "hot": { "actions": { "rollover": { "max_primary_shard_size": "10gb", "max_age": "30d" } } },
+            "delete": { "min_age": "90d", "actions": { "delete": {} } }
+        }));
+        let ilm = IlmConfig { delete_after: None, ..ilm };
+        assert_eq!(get_policy(&ilm, false)["policy"]["phases"], json!({ "hot": { "actions": {} } }));
     }
 
     #[test]
     fn test_get_template_path() {
-        assert_eq!(get_template_path(), "config/index_template.json");
+        assert_eq!(get_template_path("index_template.json"), "config/index_template.json");
+        assert_eq!(get_template_path("index_template_ecs.json"), "config/index_template_ecs.json");
+    }
+
+    #[test]
+    fn test_get_template_name() {
+        let mut cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        assert_eq!(get_template_name(&cfg), "index_template.json");
+        cfg.format_network = String::from("ecs");
+        assert_eq!(get_template_name(&cfg), "index_template_ecs.json");
     }
 
 }
\ No newline at end of file
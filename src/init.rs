@@ -13,7 +13,13 @@ pub fn init() -> (AppConfig, Ruleset) {
 
     println!("[INFO] Achiefs File Integrity Monitoring software starting!");
     println!("[INFO] Reading config...");
-    let cfg = AppConfig::new(utils::get_os(), None);
+    let cfg = match AppConfig::new(utils::get_os(), None) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            println!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Create folder to store events based on config.yml
     fs::create_dir_all(
@@ -30,6 +36,11 @@ pub fn init() -> (AppConfig, Ruleset) {
         Path::new(&cfg.clone().hashscanner_file).parent().unwrap().to_str().unwrap()
     ).unwrap();
 
+    // Create folder to store undelivered network events
+    fs::create_dir_all(
+        Path::new(&cfg.clone().spool_file).parent().unwrap().to_str().unwrap()
+    ).unwrap();
+
   // Modify the logger configuration
   let log_config = ConfigBuilder::new()
     .set_time_format_custom(format_description!(
@@ -51,7 +62,14 @@ pub fn init() -> (AppConfig, Ruleset) {
     println!("[INFO] Log file: '{}'", cfg.clone().log_file);
     println!("[INFO] Log level: '{}'", cfg.clone().log_level);
 
-    let ruleset = Ruleset::new(utils::get_os(), None);
+    let ruleset = match Ruleset::new(utils::get_os(), None)
+        .and_then(|mut ruleset| ruleset.merge(&cfg.rules).map(|_| ruleset)) {
+        Ok(ruleset) => ruleset,
+        Err(e) => {
+            println!("[ERROR] {}", e);
+            std::process::exit(1);
+        }
+    };
 
     let db = db::DB::new(&cfg.hashscanner_file);
     db.create_table();
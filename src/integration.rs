@@ -6,6 +6,8 @@ use std::fmt;
 use log::{debug, warn};
 // To manage script execution
 use std::process::Command;
+// To load integrations from config
+use serde::Deserialize;
 
 // Single event data management
 use crate::event::Event;
@@ -14,12 +16,14 @@ use crate::utils;
 
 // ----------------------------------------------------------------------------
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Integration {
     pub name: String,
     pub condition: Vec<String>,
     pub binary: String,
     pub script: String,
+    #[serde(default)]
     pub parameters: String
 }
 
@@ -39,18 +43,6 @@ impl Integration {
 
     // ------------------------------------------------------------------------
 
-    pub fn new(name: String, condition: Vec<String>, binary: String, script: String, parameters: String) -> Self {
-        Integration {
-            name,
-            condition,
-            binary,
-            script,
-            parameters
-        }
-    }
-
-    // ------------------------------------------------------------------------
-
     pub fn launch(&self, event: String) {
         let formatted_event = match utils::get_os() {
             "windows" => format!("'{}'", event),
@@ -102,7 +94,7 @@ impl fmt::Debug for Integration {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use notify::event::*;
+    use notify::event::{EventKind, CreateKind};
     use crate::appconfig::*;
     use std::path::PathBuf;
     use crate::monitorevent::MonitorEvent;
@@ -131,11 +123,14 @@ mod tests {
             version: "x.x.x".to_string(),
             kind: EventKind::Create(CreateKind::Any),
             path: PathBuf::from(format!("C:\\{}\\test.txt", path)),
+            source: None,
             size: 0,
             labels: Vec::new(),
             operation: operation.to_string(),
             detailed_operation: "CREATE_FILE".to_string(),
+            detailed_operations: Vec::new(),
             checksum: "UNKNOWN".to_string(),
+            process: None,
             fpid: 0,
             system: "test".to_string()
         }
@@ -153,11 +148,14 @@ mod tests {
             version: "x.x.x".to_string(),
             kind: EventKind::Create(CreateKind::Any),
             path: PathBuf::from(format!("/{}/test.txt", path)),
+            source: None,
             size: 0,
             labels: Vec::new(),
             operation: operation.to_string(),
             detailed_operation: "CREATE_FILE".to_string(),
+            detailed_operations: Vec::new(),
             checksum: "UNKNOWN".to_string(),
+            process: None,
             fpid: 0,
             system: "test".to_string()
         }
@@ -179,21 +177,18 @@ mod tests {
     // ------------------------------------------------------------------------
 
     #[test]
-    fn test_new() {
-        let integration = Integration::new(
-            String::from("Name"),
-            [String::from("A"), String::from("B"), String::from("C")].to_vec(),
-            String::from("Binary"),
-            String::from("Script"),
-            String::from("Parameters")
-        );
+    fn test_deserialize() {
+        let integration: Integration = serde_yaml::from_str("name: Name\n\
+            condition: [\"A\", \"B\", \"C\"]\n\
+            binary: Binary\n\
+            script: Script\n").unwrap();
         assert_eq!(integration.name, "Name");
         assert_eq!(integration.condition[0], "A");
         assert_eq!(integration.condition[1], "B");
         assert_eq!(integration.condition[2], "C");
         assert_eq!(integration.binary, "Binary");
         assert_eq!(integration.script, "Script");
-        assert_eq!(integration.parameters, "Parameters");
+        assert_eq!(integration.parameters, "");
     }
 
     // ------------------------------------------------------------------------
@@ -201,7 +196,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_get_event_integration_windows() {
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/monitor_integration.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/monitor_integration.yml")).unwrap();
 
         let integrations = cfg.get_integrations(2, cfg.monitor.clone());
         let event = create_dummy_event_windows("tmp", "CREATE");
@@ -234,7 +229,7 @@ mod tests {
     #[test]
     fn test_get_event_integration_unix() {
         let os = utils::get_os();
-        let cfg = AppConfig::new(&os, Some(format!("test/unit/config/{}/monitor_integration.yml", os).as_str()));
+        let cfg = AppConfig::new(os, Some(format!("test/unit/config/{}/monitor_integration.yml", os).as_str())).unwrap();
 
         let event = create_dummy_event_unix("etc", "CREATE");
         let integrations = cfg.get_integrations(2, cfg.monitor.clone());
@@ -43,11 +43,14 @@ mod tests {
             version: "x.x.x".to_string(),
             kind: EventKind::Create(CreateKind::Any),
             path: PathBuf::new(),
+            source: None,
             size: 0,
             labels: Vec::new(),
             operation: "CREATE".to_string(),
             detailed_operation: "CREATE_FILE".to_string(),
+            detailed_operations: Vec::new(),
             checksum: "UNKNOWN".to_string(),
+            process: None,
             fpid: 0,
             system: "test".to_string()
         }
@@ -59,7 +62,7 @@ mod tests {
     #[test]
     fn test_check_integrations() {
         let event = create_test_event();
-        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/monitor_integration.yml"));
+        let cfg = AppConfig::new("windows", Some("test/unit/config/windows/monitor_integration.yml")).unwrap();
         check_integrations(event, cfg);
     }
 
@@ -69,7 +72,7 @@ mod tests {
     #[test]
     fn test_check_integrations_linux() {
         let event = create_test_event();
-        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_integration.yml"));
+        let cfg = AppConfig::new("linux", Some("test/unit/config/linux/monitor_integration.yml")).unwrap();
         check_integrations(event, cfg);
     }
 
@@ -79,7 +82,7 @@ mod tests {
     #[test]
     fn test_check_integrations_linux() {
         let event = create_test_event();
-        let cfg = AppConfig::new("macos", Some("test/unit/config/macos/monitor_integration.yml"));
+        let cfg = AppConfig::new("macos", Some("test/unit/config/macos/monitor_integration.yml")).unwrap();
         check_integrations(event, cfg);
     }
 
@@ -144,7 +144,7 @@ mod tests {
     #[test]
     fn test_read_log() {
         if utils::get_os() == "linux" {
-            let cfg = AppConfig::new("linux", Some("test/system/audit_config.yml"));
+            let cfg = AppConfig::new("linux", Some("test/system/audit_config.yml")).unwrap();
             let (event, position) = read_log(String::from("test/unit/audit.log"),
                 cfg, 0, 0);
 
@@ -226,14 +226,15 @@ mod tests {
 
     #[test]
     fn test_extract_fields() {
-        let mut data = Vec::<HashMap<String, String>>::new();
-        data.push(HashMap::from([ (String::from("type"), String::from("SYSCALL")) ]));
-        data.push(HashMap::from([ (String::from("type"), String::from("CWD")) ]));
-        data.push(HashMap::from([ (String::from("type"), String::from("PROCTITLE")) ]));
-        data.push(HashMap::from([ (String::from("type"), String::from("PATH")),
-            (String::from("nametype"), String::from("CREATE")) ]));
-        data.push(HashMap::from([ (String::from("type"), String::from("PATH")),
-            (String::from("nametype"), String::from("PARENT")) ]));
+        let data = vec![
+            HashMap::from([ (String::from("type"), String::from("SYSCALL")) ]),
+            HashMap::from([ (String::from("type"), String::from("CWD")) ]),
+            HashMap::from([ (String::from("type"), String::from("PROCTITLE")) ]),
+            HashMap::from([ (String::from("type"), String::from("PATH")),
+                (String::from("nametype"), String::from("CREATE")) ]),
+            HashMap::from([ (String::from("type"), String::from("PATH")),
+                (String::from("nametype"), String::from("PARENT")) ])
+        ];
         let (a, b, c, vd) = extract_fields(data);
         assert_eq!(a["type"], String::from("SYSCALL"));
         assert_eq!(b["type"], String::from("CWD"));
@@ -2,9 +2,14 @@
 
 // To allow big structs like json on audit events
 #![recursion_limit = "256"]
+// Tests keep the assertion style they were written with
+#![cfg_attr(test, allow(clippy::bool_assert_comparison, clippy::assertions_on_constants,
+    clippy::needless_borrow, clippy::write_literal, clippy::unnecessary_cast))]
 
 use std::sync::mpsc;
 use std::thread;
+// To manage terminal parameters
+use std::env;
 use log::{error, info};
 use crate::init::init;
 
@@ -14,6 +19,11 @@ mod utils;
 mod hash;
 // Configuration load functions
 mod appconfig;
+mod configfile;
+// Path, ignore, exclude and allowed pattern matching
+mod matcher;
+// Configuration and ruleset validation subcommands
+mod check;
 // Ruleset load functions
 mod ruleset;
 // Index management functions
@@ -23,6 +33,22 @@ mod event;
 mod monitorevent;
 mod appevent;
 mod ruleevent;
+// Event destinations, file, Elasticsearch, Splunk and syslog
+mod output;
+// Network events pending to be delivered
+mod spool;
+// Syslog destination
+mod syslog;
+// TLS settings of network destinations
+mod tls;
+// Generic HTTP destinations
+mod webhook;
+// To chain the events file records
+mod chain;
+// ECS, OCSF, CEF and LEEF event formats
+mod formatter;
+mod ecs;
+mod ocsf;
 // File reading continuously
 mod logreader;
 mod auditevent;
@@ -35,12 +61,20 @@ mod monitor;
 mod integration;
 mod launcher;
 mod multiwatcher;
+mod rename;
+mod debounce;
+mod rewatch;
+mod process;
+#[cfg(target_os = "linux")]
+mod fanotify;
+mod watchlimit;
 mod rotator;
 mod init;
 mod db;
 mod dbfile;
 mod hashscanner;
 mod hashevent;
+mod reload;
 
 // ----------------------------------------------------------------------------
 
@@ -48,6 +82,10 @@ mod hashevent;
 #[cfg(not(windows))]
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Some(code) = check::run(&args) {
+        std::process::exit(code);
+    }
     let (cfg, ruleset) = init();
 
     let (tx, rx) = mpsc::channel();
@@ -68,6 +106,13 @@ async fn main() {
     } else {
         info!("FIM HashScanner thread disabled, not running.")
     };
+    // Started in every mode, a reload may switch to network delivery
+    let spooler_cfg = cfg.clone();
+    match thread::Builder::new()
+        .name("FIM_Spooler".to_string()).spawn(|| spool::spooler(spooler_cfg)){
+        Ok(_v) => info!("FIM spooler thread started."),
+        Err(e) => error!("Could not start FIM spooler thread, error: {}", e)
+    };
     monitor::monitor(tx, rx, cfg, ruleset).await;
 }
 
@@ -76,9 +121,10 @@ async fn main() {
 #[cfg(windows)]
 #[tokio::main]
 async fn main() -> windows_service::Result<()> {
-    // To manage terminal parameters
-    use std::env;
-    let args: Vec<_> = env::args().collect();
+    let args: Vec<String> = env::args().collect();
+    if let Some(code) = check::run(&args) {
+        std::process::exit(code);
+    }
     if args.len() > 1 {
         match args[1].as_str() {
             "--foreground"|"-f" => {
@@ -102,6 +148,13 @@ async fn main() -> windows_service::Result<()> {
                 } else {
                     info!("FIM HashScanner thread disabled, not running.")
                 };
+                // Started in every mode, a reload may switch to network delivery
+                let spooler_cfg = cfg.clone();
+                match thread::Builder::new()
+                    .name("FIM_Spooler".to_string()).spawn(|| spool::spooler(spooler_cfg)){
+                    Ok(_v) => info!("FIM spooler thread started."),
+                    Err(e) => error!("Could not start FIM spooler thread, error: {}", e)
+                };
                 monitor::monitor(tx, rx, cfg, ruleset).await;
                 Ok(())
             },
@@ -0,0 +1,184 @@
+// Copyright (C) 2026, Achiefs.
+
+// To load the matching mode from config
+use serde::Deserialize;
+// To match glob patterns
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use std::path::{Path, MAIN_SEPARATOR};
+
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// ----------------------------------------------------------------------------
+
+/// How `path`, `ignore`, `exclude` and `allowed` values are compared with
+/// event paths. Glob patterns support `*`, `?`, `**` and character classes,
+/// regex patterns are searched anywhere in the value and substring keeps the
+/// plain `contains()` comparison.
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    #[default]
+    Glob,
+    Regex,
+    Substring
+}
+
+// ----------------------------------------------------------------------------
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false
+};
+
+// ----------------------------------------------------------------------------
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+// ----------------------------------------------------------------------------
+
+fn has_separator(pattern: &str) -> bool {
+    pattern.contains('/') || pattern.contains(MAIN_SEPARATOR)
+}
+
+// ----------------------------------------------------------------------------
+
+/// Returns an error description if the pattern is not valid for the mode.
+pub fn check_pattern(mode: MatchMode, pattern: &str) -> Result<(), String> {
+    match mode {
+        MatchMode::Glob => Pattern::new(pattern).map(|_| ())
+            .map_err(|e| format!("invalid glob pattern, {}", e.msg)),
+        MatchMode::Regex => Regex::new(pattern).map(|_| ()).map_err(|e| {
+            // Keep only the error description, the pattern is already in the file
+            let description = e.to_string();
+            format!("invalid regular expression, {}",
+                description.lines().last().unwrap_or_default().trim_start_matches("error: "))
+        }),
+        MatchMode::Substring => Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Glob match of the whole path or of any of its parent folders
+fn match_tree(pattern: &Pattern, path: &str) -> bool {
+    Path::new(path).ancestors().any(|p| pattern.matches_path_with(p, GLOB_OPTIONS))
+}
+
+// ----------------------------------------------------------------------------
+
+// Pattern as compiled for its mode, invalid ones match nothing
+#[derive(Clone, Debug)]
+enum Compiled {
+    Glob(Pattern),
+    Regex(Regex),
+    Plain,
+    Invalid
+}
+
+/// Pattern compiled once, when the configuration is loaded, and compared
+/// with each event path.
+#[derive(Clone, Debug)]
+pub struct Matcher {
+    pattern: String,
+    compiled: Compiled
+}
+
+impl Matcher {
+    /// Matcher of an `ignore`, `exclude` or `allowed` entry.
+    pub fn new(mode: MatchMode, pattern: &str) -> Self {
+        let compiled = match mode {
+            MatchMode::Glob => Pattern::new(pattern).map(Compiled::Glob).unwrap_or(Compiled::Invalid),
+            MatchMode::Regex => Regex::new(pattern).map(Compiled::Regex).unwrap_or(Compiled::Invalid),
+            MatchMode::Substring => Compiled::Plain
+        };
+        Matcher { pattern: String::from(pattern), compiled }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Matcher of a monitored `path` entry. Plain paths and the substring
+    /// mode keep the folder prefix comparison.
+    pub fn path(mode: MatchMode, pattern: &str) -> Self {
+        let compiled = match mode {
+            MatchMode::Glob if is_glob(pattern) => Pattern::new(&utils::clean_path(pattern))
+                .map(Compiled::Glob).unwrap_or(Compiled::Invalid),
+            MatchMode::Regex => Regex::new(pattern).map(Compiled::Regex).unwrap_or(Compiled::Invalid),
+            _ => Compiled::Plain
+        };
+        Matcher { pattern: String::from(pattern), compiled }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Check if a filename matches an `ignore` or `allowed` entry.
+    pub fn match_name(&self, filename: &str) -> bool {
+        match &self.compiled {
+            Compiled::Glob(glob) => glob.matches_with(filename, GLOB_OPTIONS),
+            Compiled::Regex(regex) => regex.is_match(filename),
+            Compiled::Plain => filename.contains(&self.pattern),
+            Compiled::Invalid => false
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Check if a folder matches an `exclude` entry. Glob patterns without a
+    /// separator are compared with each folder name, otherwise with the
+    /// folder and its parents.
+    pub fn match_folder(&self, path: &str) -> bool {
+        match &self.compiled {
+            Compiled::Glob(glob) => match has_separator(&self.pattern) {
+                true => match_tree(glob, path),
+                false => Path::new(path).iter()
+                    .any(|name| glob.matches_with(&name.to_string_lossy(), GLOB_OPTIONS))
+            },
+            Compiled::Regex(regex) => regex.is_match(path),
+            Compiled::Plain => path.contains(&self.pattern),
+            Compiled::Invalid => false
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Check if a path is inside the monitored `path` entry of a matcher
+    /// built with `Matcher::path`.
+    pub fn match_path(&self, path: &str) -> bool {
+        match &self.compiled {
+            Compiled::Glob(glob) => match_tree(glob, &utils::clean_path(path)),
+            Compiled::Regex(regex) => regex.is_match(path),
+            Compiled::Plain => utils::match_path(path, &self.pattern),
+            Compiled::Invalid => false
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Returns the folder to watch for a `path` entry, the longest leading part
+/// of the pattern without special characters.
+pub fn get_root(mode: MatchMode, pattern: &str) -> String {
+    let literal = match mode {
+        MatchMode::Glob if is_glob(pattern) => &pattern[..pattern.find(['*', '?', '[']).unwrap_or_default()],
+        MatchMode::Regex => {
+            let pattern = pattern.trim_start_matches('^');
+            match pattern.find(['.', '^', '$', '*', '+', '?', '(', ')', '[', ']', '{', '}', '|', '\\']) {
+                Some(pos) => &pattern[..pos],
+                None => return String::from(pattern)
+            }
+        },
+        _ => return String::from(pattern)
+    };
+    // Cut the partial name after the last separator
+    match literal.rfind(['/', MAIN_SEPARATOR]) {
+        Some(0) => String::from(&literal[..1]),
+        Some(pos) => String::from(&literal[..pos]),
+        None => String::new()
+    }
+}
@@ -0,0 +1,74 @@
+use super::*;
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check patterns are validated with the rules of each mode
+fn test_check_pattern() {
+    assert!(check_pattern(MatchMode::Glob, "/etc/**/*.conf").is_ok());
+    assert!(check_pattern(MatchMode::Glob, "/etc/[a").unwrap_err().starts_with("invalid glob pattern"));
+    assert!(check_pattern(MatchMode::Regex, "^/etc/.*\\.conf$").is_ok());
+    assert!(check_pattern(MatchMode::Regex, "(conf").unwrap_err().starts_with("invalid regular expression"));
+    assert!(check_pattern(MatchMode::Substring, "[").is_ok());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check filenames against ignore and allowed entries
+fn test_match_name() {
+    assert!(Matcher::new(MatchMode::Glob, "*.swp").match_name("file.swp"));
+    assert!(!Matcher::new(MatchMode::Glob, "*.swp").match_name("my.swp.conf"));
+    assert!(Matcher::new(MatchMode::Glob, "file?.[ch]").match_name("file1.c"));
+    assert!(!Matcher::new(MatchMode::Glob, "file?.[ch]").match_name("file10.c"));
+    assert!(Matcher::new(MatchMode::Regex, "\\.swp$").match_name("file.swp"));
+    assert!(!Matcher::new(MatchMode::Regex, "\\.swp$").match_name("my.swp.conf"));
+    assert!(Matcher::new(MatchMode::Substring, ".swp").match_name("my.swp.conf"));
+    assert!(!Matcher::new(MatchMode::Glob, "[").match_name("["));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check folders against exclude entries
+fn test_match_folder() {
+    assert!(Matcher::new(MatchMode::Glob, "node_modules").match_folder("/srv/app/node_modules/lib"));
+    assert!(!Matcher::new(MatchMode::Glob, "node_modules").match_folder("/srv/app/node_modules_old"));
+    assert!(Matcher::new(MatchMode::Glob, ".cache*").match_folder("/home/user/.cache2"));
+    assert!(Matcher::new(MatchMode::Glob, "/tmp/test").match_folder("/tmp/test/sub"));
+    assert!(!Matcher::new(MatchMode::Glob, "/tmp/test").match_folder("/tmp/tests"));
+    assert!(Matcher::new(MatchMode::Glob, "/home/*/tmp").match_folder("/home/user/tmp/a"));
+    assert!(Matcher::new(MatchMode::Regex, "/tmp/te.t$").match_folder("/tmp/test"));
+    assert!(Matcher::new(MatchMode::Substring, "/tmp/test").match_folder("/tmp/tests"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check event paths against monitored path entries
+fn test_match_path() {
+    assert!(Matcher::path(MatchMode::Glob, "/etc/*.conf").match_path("/etc/nginx.conf"));
+    assert!(!Matcher::path(MatchMode::Glob, "/etc/*.conf").match_path("/etc/nginx/nginx.conf"));
+    assert!(Matcher::path(MatchMode::Glob, "/etc/**/*.conf").match_path("/etc/nginx/sites/default.conf"));
+    assert!(Matcher::path(MatchMode::Glob, "/home/*/.ssh").match_path("/home/user/.ssh/authorized_keys"));
+    assert!(Matcher::path(MatchMode::Glob, "/home/*/.ssh/").match_path("/home/user/.ssh"));
+    assert!(!Matcher::path(MatchMode::Glob, "/home/*/.ssh").match_path("/home/user/.bashrc"));
+    assert!(Matcher::path(MatchMode::Regex, "^/var/log/.*\\.log$").match_path("/var/log/app/error.log"));
+    assert!(!Matcher::path(MatchMode::Regex, "^/var/log/.*\\.log$").match_path("/var/log/app/error.log.1"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the folder to watch is the fixed part of the pattern
+fn test_get_root() {
+    assert_eq!(get_root(MatchMode::Glob, "/etc"), "/etc");
+    assert_eq!(get_root(MatchMode::Glob, "/etc/*.conf"), "/etc");
+    assert_eq!(get_root(MatchMode::Glob, "/home/*/.ssh"), "/home");
+    assert_eq!(get_root(MatchMode::Glob, "/etc/ngin?/a"), "/etc");
+    assert_eq!(get_root(MatchMode::Glob, "/*"), "/");
+    assert_eq!(get_root(MatchMode::Glob, "*.conf"), "");
+    assert_eq!(get_root(MatchMode::Regex, "^/var/log/.*\\.log$"), "/var/log");
+    assert_eq!(get_root(MatchMode::Regex, "/var/log/app"), "/var/log/app");
+    assert_eq!(get_root(MatchMode::Substring, "/tmp/*"), "/tmp/*");
+}
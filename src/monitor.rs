@@ -4,16 +4,16 @@
 use std::fs;
 // To get file system changes
 use notify::RecursiveMode;
-use std::sync::mpsc;
+use std::sync::{Arc, Mutex, mpsc};
 // To log the program process
 use log::{info, error, debug, warn};
 // To manage paths
 use std::path::Path;
-use time::OffsetDateTime;
+use std::time::Duration;
 // To use intersperse()
 use itertools::Itertools;
 // Event handling
-use notify::event::{EventKind, AccessKind};
+use notify::event::{EventKind, AccessKind, CreateKind};
 
 
 // Utils functions
@@ -29,12 +29,28 @@ use crate::event;
 use event::Event;
 use crate::monitorevent::MonitorEvent;
 use crate::appevent::AppEvent;
+use crate::process::Process;
 use crate::ruleset::*;
 // File reading continuously
 use crate::logreader;
 // integrations checker
 use crate::launcher;
 use crate::multiwatcher::MultiWatcher;
+// Rename correlation
+use crate::rename::{self, Renames};
+// Event coalescing
+use crate::debounce::{Burst, Bursts};
+// Hash database of the scanner
+use crate::db;
+use crate::hashscanner;
+// Lost monitored roots
+use crate::rewatch::{Change, Roots};
+// Inotify watches exceeded
+use crate::watchlimit::Shortage;
+use walkdir::WalkDir;
+// Configuration hot reload
+use crate::reload;
+use crate::configfile::get_dropin_dir;
 
 // ----------------------------------------------------------------------------
 
@@ -42,8 +58,8 @@ fn setup_events(destination: &str, cfg: AppConfig){
     // Perform actions depending on destination
     info!("Events destination selected: {}", destination);
     match destination {
-        appconfig::NETWORK_MODE => {
-            debug!("Events folder not created in network mode");
+        appconfig::NETWORK_MODE|appconfig::SYSLOG_MODE => {
+            debug!("Events folder not created in {} mode", destination.to_lowercase());
         },
         _ => {
             info!("Events file: {}", cfg.events_file);
@@ -54,28 +70,20 @@ fn setup_events(destination: &str, cfg: AppConfig){
 
 // ----------------------------------------------------------------------------
 
-async fn push_template(destination: &str, cfg: AppConfig){
+async fn push_template(destination: &str, cfg: AppConfig) -> Result<(), String> {
     // Perform actions depending on destination
     match destination {
         appconfig::NETWORK_MODE|appconfig::BOTH_MODE => {
             // On start push template (Include check if events won't be ingested by http)
-            index::push_template(cfg).await;
+            if cfg.endpoint_type == "Elastic" {
+                return index::push_template(cfg).await;
+            }
         },
         _ => {
             debug!("Template not pushed in file mode");
         }
     }
-}
-
-// ----------------------------------------------------------------------------
-
-fn clean_audit_rules(cfg: &AppConfig){
-    for element in cfg.audit.clone() {
-        let path = element["path"].as_str().unwrap();
-        let rule = utils::get_audit_rule_permissions(element["rule"].as_str());
-        utils::run_auditctl(&["-W", path, "-k", "fim", "-p", &rule]);
-    }
-    std::process::exit(0);
+    Ok(())
 }
 
 // ----------------------------------------------------------------------------
@@ -84,85 +92,102 @@ fn clean_audit_rules(cfg: &AppConfig){
 pub async fn monitor(
     tx: mpsc::Sender<Result<notify::Event, notify::Error>>,
     rx: mpsc::Receiver<Result<notify::Event, notify::Error>>, 
-    cfg: AppConfig,
-    ruleset: Ruleset){
+    mut cfg: AppConfig,
+    mut ruleset: Ruleset){
 
-    let destination = cfg.clone().get_events_destination();
+    // Paths can override the destination, prepare every one in use
+    let destination = cfg.clone().get_all_destinations();
     setup_events(destination.as_str(), cfg.clone());
 
-    // Check if we have to push index template
-    push_template(destination.as_str(), cfg.clone()).await;
-
-    let mut watcher = MultiWatcher::new(cfg.clone().events_watcher.as_str(), tx);
+    // Check if we have to push index template, events can't be ingested without it
+    if let Err(e) = push_template(destination.as_str(), cfg.clone()).await {
+        error!("Elasticsearch setup failed, {}", e);
+        std::process::exit(1);
+    }
+    // Sinks are built once, a reload builds them again with its configuration
+    cfg.get_router();
+
+    // Audit rules registered by FIM, removed on exit
+    let audit_rules = Arc::new(Mutex::new(Vec::new()));
+    // Reload config and rules on changes or SIGHUP
+    let mut files = vec![cfg.path.clone(), ruleset.path.clone()];
+    files.extend(cfg.includes.clone());
+    reload::watch_files(files, vec![get_dropin_dir(&cfg.path)], cfg.clone(), tx.clone());
+    #[cfg(unix)]
+    reload::handle_signals(tx.clone(), audit_rules.clone(), cfg.clone());
+    let mut watcher = MultiWatcher::new(&cfg, tx);
     
     // Iterating over monitor paths and set watcher on each folder to watch.
     if ! cfg.clone().monitor.is_empty() {
         for element in cfg.clone().monitor {
-            let path = element["path"].as_str().unwrap();
+            let path = element.path.as_str();
             info!("Monitoring path: {}", path);
 
-            match element["ignore"].as_vec() {
+            match &element.ignore {
                 Some(ig) => {
-                    let ignore_vec  = ig.iter().map(|e| e.as_str().unwrap() );
+                    let ignore_vec  = ig.iter().map(|e| e.as_str() );
                     let ignore_list : String = Itertools::intersperse(ignore_vec, ", ").collect();
                     info!("Ignoring files with: '{}' inside '{}' path.", ignore_list, path);
                 },
                 None => debug!("Ignore for '{}' path not set.", path)
             };
 
-            match element["exclude"].as_vec() {
+            match &element.exclude {
                 Some(ex) => {
-                    let exclude_vec  = ex.iter().map(|e| e.as_str().unwrap() );
+                    let exclude_vec  = ex.iter().map(|e| e.as_str() );
                     let exclude_list : String = Itertools::intersperse(exclude_vec, ", ").collect();
                     info!("Excluding folders: '{}' inside '{}' path.", exclude_list, path);
                 },
                 None => debug!("Exclude folders for '{}' path not set.", path)
             };
 
-            match element["allowed"].as_vec(){
+            match &element.allowed {
                 Some(allowed) => {
-                    let allowed_vec = allowed.iter().map(|e| e.as_str().unwrap());
+                    let allowed_vec = allowed.iter().map(|e| e.as_str());
                     let allowed_list : String = Itertools::intersperse(allowed_vec, ", ").collect();
                     info!("Only files with '{}' will trigger event inside '{}' path.", allowed_list, path)
                 },
                 None => debug!("Monitoring files under '{}' path.", path)
             }
 
-            match watcher.watch(Path::new(path), RecursiveMode::Recursive) {
-                Ok(_d) => debug!("Monitoring '{}' path.", path),
-                Err(e) => warn!("[Monitor] Could not monitor given path '{}', description: {}", path, e)
+            let root = element.root();
+            match watcher.watch(Path::new(&root), RecursiveMode::Recursive) {
+                Ok(_d) => debug!("Monitoring '{}' path.", root),
+                Err(e) => warn!("[Monitor] Could not monitor given path '{}', description: {}", root, e)
             };
         }
     }
     let mut last_position = 0;
     if ! cfg.clone().audit.is_empty() && utils::get_os() == "linux" && utils::check_auditd() {
+        // Entries sharing a root share its watch, as reload and cleanup expect
+        for (root, rule) in reload::get_audit_watches(&cfg.audit) {
+            utils::run_auditctl(&["-w", &root, "-k", "fim", "-p", &rule]);
+        }
         for element in cfg.clone().audit {
-            let path = element["path"].as_str().unwrap();
-            let rule = utils::get_audit_rule_permissions(element["rule"].as_str());
-            utils::run_auditctl(&["-w", path, "-k", "fim", "-p", &rule]);
+            let path = element.path.as_str();
             info!("Checking audit path: {}", path);
 
-            match element["allowed"].as_vec() {
+            match &element.allowed {
                 Some(allowed) => {
-                    let allowed_vec  = allowed.iter().map(|e| e.as_str().unwrap() );
+                    let allowed_vec  = allowed.iter().map(|e| e.as_str() );
                     let allowed_list : String = Itertools::intersperse(allowed_vec, ", ").collect();
                     info!("Only files with '{}' will trigger event inside '{}' path.", allowed_list, path)
                 },
                 None => debug!("Monitoring files under '{}' path.", path)
             };
 
-            match element["exclude"].as_vec() {
+            match &element.exclude {
                 Some(ex) => {
-                    let exclude_vec  = ex.iter().map(|e| e.as_str().unwrap() );
+                    let exclude_vec  = ex.iter().map(|e| e.as_str() );
                     let exclude_list : String = Itertools::intersperse(exclude_vec, ", ").collect();
                     info!("Excluding folders: '{}' inside '{}' path.", exclude_list, path);
                 },
                 None => debug!("Exclude folders for '{}' path not set.", path)
             };
 
-            match element["ignore"].as_vec() {
+            match &element.ignore {
                 Some(ig) => {
-                    let ignore_list_vec  = ig.iter().map(|e| e.as_str().unwrap() );
+                    let ignore_list_vec  = ig.iter().map(|e| e.as_str() );
                     let ignore_list : String = Itertools::intersperse(ignore_list_vec, ", ").collect();
                     info!("Ignoring files with: '{}' inside '{}' path", ignore_list, path);
                 },
@@ -173,160 +198,321 @@ pub async fn monitor(
         watcher.watch(Path::new(logreader::AUDIT_PATH), RecursiveMode::NonRecursive).unwrap();
         last_position = utils::get_file_end(logreader::AUDIT_LOG_PATH, 0);
        
-        // Remove auditd rules introduced by FIM
-        // Setting ctrl + C handler
-        let cloned_cfg = cfg.clone();
-        match ctrlc::set_handler(move || clean_audit_rules(&cloned_cfg)) {
-            Ok(_v) => debug!("Handler Ctrl-C set and listening"),
-            Err(e) => error!("Error setting Ctrl-C handler, the process will continue without signal handling, Error: '{}'", e)
-        }
+        // Remove auditd rules introduced by FIM on exit
+        *audit_rules.lock().unwrap() = cfg.audit.clone();
     }
 
 
     // Main loop, receive any produced event and write it into the events log.
+    let mut renames = Renames::new();
+    let mut bursts = Bursts::new();
+    let mut roots = Roots::new(&get_roots(&cfg));
     'processor: loop {
-        for message in &rx {
-            match message {
-                Ok(event) => {
-                    // Get the event path and filename
-                    debug!("Event received: {:?}", event);
-
-                    let plain_path: &str = match event.paths.len() {
-                        0 => "UNKNOWN",
-                        _ => event.paths[0].to_str().unwrap()
-                    };
-                    if plain_path == "DISCONNECT" {
-                        info!("Received exit signal, exiting...");
-                        break 'processor;
+        for shortage in watcher.take_shortages() {
+            report_shortage(shortage, &cfg, &ruleset).await;
+        }
+        let timeout = bursts.timeout().map(|timeout| timeout.min(rename::WINDOW)).unwrap_or(rename::WINDOW);
+        let events = match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                // Get the event path and filename
+                debug!("Event received: {:?}", event);
+
+                let plain_path: &str = match event.paths.len() {
+                    0 => "UNKNOWN",
+                    _ => event.paths[0].to_str().unwrap()
+                };
+                if plain_path == "DISCONNECT" {
+                    info!("Received exit signal, exiting...");
+                    break 'processor;
+                }
+                if plain_path == reload::RELOAD_PATH {
+                    let audit_stopped = audit_rules.lock().unwrap().is_empty();
+                    if let Some((new_cfg, new_ruleset)) = reload::reload(&cfg, &ruleset, &mut watcher, &audit_rules).await {
+                        cfg = new_cfg;
+                        ruleset = new_ruleset;
                     }
-
-                    let event_path = Path::new(plain_path);
-                    let event_filename = event_path.file_name().unwrap();
-
-                    let current_date = OffsetDateTime::now_utc();
-                    let index_name = format!("fim-{}-{}-{}", current_date.year(), current_date.month() as u8, current_date.day() );
-                    let current_timestamp = utils::get_current_time_millis();
-                    let kind: notify::EventKind = event.kind;
-                    let path = event.paths[0].clone();
-
-                    // Reset reading position due to log rotation
-                    if plain_path == logreader::AUDIT_LOG_PATH && kind == EventKind::Access(AccessKind::Any) {
-                        last_position = 0;
+                    // Start reading audit log if the first audit paths were added
+                    if audit_stopped && !audit_rules.lock().unwrap().is_empty() {
+                        match watcher.watch(Path::new(logreader::AUDIT_PATH), RecursiveMode::NonRecursive) {
+                            Ok(_d) => debug!("Monitoring audit log folder."),
+                            Err(e) => error!("Could not monitor audit log folder, description: {}", e)
+                        };
+                        last_position = utils::get_file_end(logreader::AUDIT_LOG_PATH, 0);
                     }
+                    continue;
+                }
 
-                    // If the event comes from audit.log
-                    if plain_path == logreader::AUDIT_LOG_PATH {
-                        // Getting events from audit.log
-                        let mut events = Vec::new();
-                        let (log_event, position) = logreader::read_log(String::from(logreader::AUDIT_LOG_PATH), cfg.clone(), last_position, 0);
-                        if log_event.id != "0" { events.push(log_event); };
-                        let mut ctr = 0;
-                        last_position = position;
-                        while last_position < utils::get_file_end(logreader::AUDIT_LOG_PATH, 0) {
-                            debug!("Reading events, iteration: {}", ctr);
-                            let original_position = last_position;
-                            ctr += 1;
-                            let (evt, pos) = logreader::read_log(String::from(logreader::AUDIT_LOG_PATH), cfg.clone(), last_position, ctr);
-                            if evt.id != "0" {
-                                events.push(evt);
-                                ctr = 0;
-                            };
-                            last_position = pos;
-                            if original_position == pos {
-                                ctr = 0;
-                            }
+                // Reset reading position due to log rotation
+                if plain_path == logreader::AUDIT_LOG_PATH && event.kind == EventKind::Access(AccessKind::Any) {
+                    last_position = 0;
+                }
+
+                // If the event comes from audit.log
+                if plain_path == logreader::AUDIT_LOG_PATH {
+                    // Getting events from audit.log
+                    let mut events = Vec::new();
+                    let (log_event, position) = logreader::read_log(String::from(logreader::AUDIT_LOG_PATH), cfg.clone(), last_position, 0);
+                    if log_event.id != "0" { events.push(log_event); };
+                    let mut ctr = 0;
+                    last_position = position;
+                    while last_position < utils::get_file_end(logreader::AUDIT_LOG_PATH, 0) {
+                        debug!("Reading events, iteration: {}", ctr);
+                        let original_position = last_position;
+                        ctr += 1;
+                        let (evt, pos) = logreader::read_log(String::from(logreader::AUDIT_LOG_PATH), cfg.clone(), last_position, ctr);
+                        if evt.id != "0" {
+                            events.push(evt);
+                            ctr = 0;
+                        };
+                        last_position = pos;
+                        if original_position == pos {
+                            ctr = 0;
                         }
-                        debug!("Events read from audit log, position: {}", last_position);
-
-                        for audit_event in events {
-                            if ! audit_event.is_empty() {
-                                // Getting the position of event in config (match ignore and labels)
-                                let index = cfg.get_index(audit_event.clone().path.as_str(),
-                                    audit_event.clone().cwd.as_str(),
-                                    cfg.clone().audit.to_vec());
-
-                                if index != usize::MAX {
-                                    // If event contains ignored string ignore event
-                                    if ! cfg.match_ignore(index, audit_event.clone().file.as_str(), cfg.clone().audit)  &&
-                                        ! cfg.match_exclude(index, audit_event.clone().path.as_str(), cfg.clone().audit) &&
-                                        cfg.match_allowed(index, audit_event.clone().file.as_str(), cfg.clone().audit) {
-                                        audit_event.process(destination.clone().as_str(), index_name.clone(), cfg.clone(), ruleset.clone()).await;
-                                    }else{
-                                        debug!("Event ignored/excluded not stored in alerts");
-                                    }
+                    }
+                    debug!("Events read from audit log, position: {}", last_position);
+
+                    for audit_event in events {
+                        if ! audit_event.is_empty() {
+                            // Getting the position of event in config (match ignore and labels)
+                            let index = cfg.get_index(audit_event.clone().path.as_str(),
+                                audit_event.clone().cwd.as_str(),
+                                cfg.clone().audit.to_vec());
+
+                            if index != usize::MAX {
+                                // If event contains ignored string ignore event
+                                if ! cfg.match_ignore(index, audit_event.clone().file.as_str(), cfg.clone().audit)  &&
+                                    ! cfg.match_exclude(index, audit_event.clone().path.as_str(), cfg.clone().audit) &&
+                                    cfg.match_allowed(index, audit_event.clone().file.as_str(), cfg.clone().audit) {
+                                    let destination = cfg.get_destination(index, cfg.clone().audit);
+                                    audit_event.process(destination.as_str(), cfg.clone(), ruleset.clone()).await;
                                 }else{
-                                    debug!("Event not monitored by FIM");
-                                }
-                            }
-                            debug!("Event processed: {:?}", audit_event.clone());
-                        }
-                    }else {
-                        let index = cfg.get_index(event_path.to_str().unwrap(), "", cfg.clone().monitor.to_vec());
-                        let parent = match event_path.is_dir() {
-                            true => event_path.to_str().unwrap(),
-                            false => event_path.parent().unwrap().to_str().unwrap()
-                        };
-                        if index != usize::MAX {
-                            let labels = cfg.get_labels(index, cfg.clone().monitor);
-                            if ! cfg.match_ignore(index, event_filename.to_str().unwrap(), cfg.clone().monitor) &&
-                                ! cfg.match_exclude(index, parent, cfg.clone().monitor) &&
-                                cfg.match_allowed(index, event_filename.to_str().unwrap(), cfg.clone().monitor) { 
-                                let event = MonitorEvent {
-                                    id: utils::get_uuid(),
-                                    timestamp: current_timestamp.clone(),
-                                    hostname: utils::get_hostname(),
-                                    node: cfg.clone().node,
-                                    version: String::from(appconfig::VERSION),
-                                    kind,
-                                    path: path.clone(),
-                                    size: utils::get_file_size(path.clone().to_str().unwrap()),
-                                    labels,
-                                    operation: event::get_operation(kind),
-                                    detailed_operation: event::get_detailed_operation(kind),
-                                    checksum: hash::get_checksum( String::from(path.to_str().unwrap()), cfg.clone().events_max_file_checksum, cfg.clone().checksum_algorithm),
-                                    fpid: utils::get_pid(),
-                                    system: cfg.clone().system
-                                };
-
-                                debug!("Event processed: {:?}", event);
-                                event.process(cfg.clone(), ruleset.clone()).await;
-                                launcher::check_integrations(event.clone(), cfg.clone());
-                                // Check if main monitor path is renamed
-                                use yaml_rust::Yaml;
-                                let monitor_path = cfg.monitor[index]
-                                    .as_hash()
-                                    .unwrap()
-                                    .get(&Yaml::from_str("path"))
-                                    .and_then(|v| v.as_str())
-                                    .unwrap();
-                                if event.path.to_str().unwrap() == monitor_path && event.detailed_operation == "MODIFY_RENAME_ANY" {
-                                    let message = format!("Monitored path '{:?}' renamed to continue monitoring the path you need to update config and restart FIM", event.path);
-                                    warn!("{}", &message);
-                                    let appevent = AppEvent {
-                                        id: utils::get_uuid(),
-                                        timestamp: current_timestamp,
-                                        hostname: utils::get_hostname(),
-                                        node: cfg.clone().node,
-                                        version: String::from(appconfig::VERSION),
-                                        message,
-                                        fpid: utils::get_pid(),
-                                        system: cfg.clone().system
-                                    };
-                                    appevent.process(cfg.clone(), ruleset.clone()).await;
+                                    debug!("Event ignored/excluded not stored in alerts");
                                 }
                             }else{
-                                debug!("Event ignored/excluded not stored in alerts");
+                                debug!("Event not monitored by FIM");
                             }
-                        }else{
-                            debug!("Event not matched monitor");
                         }
+                        debug!("Event processed: {:?}", audit_event.clone());
                     }
-                },
-                Err(e) => {
-                    error!("Watch for event failed, error: {:?}", e);
+                    continue;
                 }
+                // Both sides of a rename are sent together
+                renames.correlate(event)
+            },
+            Ok(Err(e)) => {
+                error!("Watch for event failed, error: {:?}", e);
+                continue;
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => Vec::new(),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break 'processor
+        };
+        // Renamed files without destination go first, they were received before
+        for event in renames.expire().into_iter().chain(events) {
+            let window = get_window(&event, &cfg);
+            if window > 0 {
+                bursts.add(event, Duration::from_millis(window));
+                continue;
+            }
+            // Events of the moved path come before the move
+            if let Some(burst) = rename::get_paths(&event).and_then(|(from, _)| bursts.take(from)) {
+                process_burst(burst, &cfg, &ruleset).await;
+            }
+            process_event(event, &[], &cfg, &ruleset).await;
+        }
+        for burst in bursts.expire() {
+            process_burst(burst, &cfg, &ruleset).await;
+        }
+        for change in roots.check(&get_roots(&cfg)) {
+            rewatch(change, &mut watcher, &cfg, &ruleset).await;
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_roots(cfg: &AppConfig) -> Vec<String> {
+    cfg.monitor.iter().map(|element| element.root()).collect()
+}
+
+// ----------------------------------------------------------------------------
+
+// Stop watching a monitored root that is gone, so a renamed folder isn't
+// followed, and watch it again when it is back. Changes made meanwhile are
+// reported by a catch-up scan and the coverage gap by an AppEvent.
+async fn rewatch(change: Change, watcher: &mut MultiWatcher, cfg: &AppConfig, ruleset: &Ruleset) {
+    let message = match change {
+        Change::Lost(root) => {
+            // The watch of a removed folder may be gone already
+            let _ = watcher.unwatch(Path::new(&root));
+            format!("Monitored path '{}' is gone, it will be monitored again when it is back", root)
+        },
+        Change::Back(root, since) => {
+            let _ = watcher.unwatch(Path::new(&root));
+            if let Err(e) = watcher.watch(Path::new(&root), RecursiveMode::Recursive) {
+                warn!("[Monitor] Could not monitor given path '{}', description: {}", root, e);
+            }
+            scan(&root, cfg, ruleset).await;
+            format!("Monitored path '{}' is back and monitored again, changes made since {} were not monitored and are reported by a catch-up scan", root, since)
+        }
+    };
+    warn!("{}", message);
+    send_appevent(message, cfg, ruleset).await;
+}
+
+// ----------------------------------------------------------------------------
+
+// Monitored tree without an inotify watch for each folder, its uncovered
+// subtrees are reported, or the polling that replaces them.
+async fn report_shortage(shortage: Shortage, cfg: &AppConfig, ruleset: &Ruleset) {
+    let path = shortage.path.display();
+    let message = match shortage.polled {
+        true => format!("Monitored path '{}' needs {} inotify watches and {} are left (fs.inotify.max_user_watches), it is polled every {} seconds instead",
+            path, shortage.needed, shortage.available, cfg.poll_interval),
+        false => {
+            let uncovered: Vec<String> = shortage.uncovered.iter().map(|p| p.display().to_string()).collect();
+            format!("Monitored path '{}' needs {} inotify watches and {} are left (fs.inotify.max_user_watches), changes in these folders are not monitored: {}",
+                path, shortage.needed, shortage.available, uncovered.join(", "))
+        }
+    };
+    error!("{}", message);
+    send_appevent(message, cfg, ruleset).await;
+}
+
+// ----------------------------------------------------------------------------
+
+async fn send_appevent(message: String, cfg: &AppConfig, ruleset: &Ruleset) {
+    let appevent = AppEvent {
+        id: utils::get_uuid(),
+        timestamp: utils::get_current_time_millis(),
+        hostname: utils::get_hostname(),
+        node: cfg.clone().node,
+        version: String::from(appconfig::VERSION),
+        message,
+        fpid: utils::get_pid(),
+        system: cfg.clone().system
+    };
+    appevent.process(cfg.clone(), ruleset.clone()).await;
+}
+
+// ----------------------------------------------------------------------------
+
+// Catch-up scan of a root watched again. The hash database gives the files
+// created, modified and removed, without it every file found is reported
+// as created.
+async fn scan(root: &str, cfg: &AppConfig, ruleset: &Ruleset) {
+    if cfg.hashscanner_enabled && Path::new(&cfg.hashscanner_file).exists() {
+        hashscanner::check_path(cfg.clone(), String::from(root), true).await;
+        hashscanner::update_db(cfg.clone(), String::from(root), true).await;
+        return;
+    }
+    for entry in WalkDir::new(root).min_depth(1).into_iter().filter_map(Result::ok) {
+        let kind = match entry.file_type().is_dir() {
+            true => EventKind::Create(CreateKind::Folder),
+            false => EventKind::Create(CreateKind::File)
+        };
+        process_event(notify::Event::new(kind).add_path(entry.into_path()), &[], cfg, ruleset).await;
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Coalescing window of a monitor event in milliseconds, moves are never held
+fn get_window(event: &notify::Event, cfg: &AppConfig) -> u64 {
+    match (rename::get_paths(event), event.paths.first()) {
+        (None, Some(path)) => {
+            let index = cfg.get_index(path.to_str().unwrap_or_default(), "", cfg.monitor.clone());
+            cfg.get_debounce(index, &cfg.monitor)
+        },
+        _ => 0
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Produce a single monitor event for a burst, with the kind of the burst
+// and every detailed operation received
+async fn process_burst(burst: Burst, cfg: &AppConfig, ruleset: &Ruleset) {
+    let kinds: Vec<String> = burst.kinds.iter().map(|kind| event::get_detailed_operation(*kind)).collect();
+    let mut event = burst.event.clone();
+    event.kind = burst.kind();
+    process_event(event, &kinds, cfg, ruleset).await;
+}
+
+// ----------------------------------------------------------------------------
+
+// Produce the monitor event of a notify event. Moves use the destination
+// path, or the source one when the destination isn't monitored. The file
+// checksum is read here, once per event or burst.
+async fn process_event(event: notify::Event, detailed_operations: &[String], cfg: &AppConfig, ruleset: &Ruleset) {
+    let kind: notify::EventKind = event.kind;
+    let (source, path) = match rename::get_paths(&event) {
+        Some((from, to)) => (Some(from.clone()), to.clone()),
+        None => match event.paths.first() {
+            Some(path) => (None, path.clone()),
+            None => return
+        }
+    };
+    let event_path = path.as_path();
+    let event_filename = match event_path.file_name() {
+        Some(filename) => filename,
+        None => return
+    };
+    let current_timestamp = utils::get_current_time_millis();
+
+    let mut index = cfg.get_index(event_path.to_str().unwrap(), "", cfg.clone().monitor.to_vec());
+    if index == usize::MAX {
+        if let Some(source) = &source {
+            index = cfg.get_index(source.to_str().unwrap(), "", cfg.clone().monitor.to_vec());
+        }
+    }
+    let parent = match event_path.is_dir() {
+        true => event_path.to_str().unwrap(),
+        false => event_path.parent().unwrap().to_str().unwrap()
+    };
+    if index != usize::MAX {
+        let labels = cfg.get_labels(index, cfg.clone().monitor);
+        if ! cfg.match_ignore(index, event_filename.to_str().unwrap(), cfg.clone().monitor) &&
+            ! cfg.match_exclude(index, parent, cfg.clone().monitor) &&
+            cfg.match_allowed(index, event_filename.to_str().unwrap(), cfg.clone().monitor) { 
+            let event = MonitorEvent {
+                id: utils::get_uuid(),
+                timestamp: current_timestamp.clone(),
+                hostname: utils::get_hostname(),
+                node: cfg.clone().node,
+                version: String::from(appconfig::VERSION),
+                kind,
+                path: path.clone(),
+                source: source.clone(),
+                size: utils::get_file_size(path.clone().to_str().unwrap()),
+                labels,
+                operation: match source {
+                    Some(_) => String::from(event::MOVE),
+                    None => event::get_operation(kind)
+                },
+                detailed_operation: event::get_detailed_operation(kind),
+                detailed_operations: detailed_operations.to_vec(),
+                checksum: hash::get_checksum( String::from(path.to_str().unwrap()),
+                    cfg.get_max_file_checksum(index, cfg.clone().monitor),
+                    cfg.get_checksum_algorithm(index, cfg.clone().monitor)),
+                process: Process::from_event(&event),
+                fpid: utils::get_pid(),
+                system: cfg.clone().system
+            };
+
+            // Hashes stored by the scanner follow the moved files
+            if let Some(source) = event.source.as_ref().filter(|_| cfg.hashscanner_enabled && Path::new(&cfg.hashscanner_file).exists()) {
+                // Errors are logged by the database
+                let _ = db::DB::new(&cfg.hashscanner_file)
+                    .rename_path(source.to_str().unwrap(), event.path.to_str().unwrap());
             }
+            debug!("Event processed: {:?}", event);
+            event.process(cfg.clone(), ruleset.clone()).await;
+            launcher::check_integrations(event.clone(), cfg.clone());
+        }else{
+            debug!("Event ignored/excluded not stored in alerts");
         }
+    }else{
+        debug!("Event not matched monitor");
     }
 }
 
@@ -341,17 +527,20 @@ mod tests {
 
     #[test]
     fn test_push_template() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
         fs::create_dir_all(Path::new(&cfg.log_file).parent().unwrap().to_str().unwrap()).unwrap();
-        block_on(push_template("file", cfg.clone()));
-        block_on(push_template("network", cfg.clone()));
+        assert!(block_on(push_template("file", cfg.clone())).is_ok());
+        assert!(block_on(push_template("network", cfg.clone())).is_ok());
+        let mut cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+        cfg.endpoint_type = String::from("Elastic");
+        assert!(block_on(push_template(appconfig::NETWORK_MODE, cfg)).is_err());
     }
 
     // ------------------------------------------------------------------------
 
     #[test]
     fn test_setup_events() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
         fs::create_dir_all(Path::new(&cfg.log_file).parent().unwrap().to_str().unwrap()).unwrap();
         setup_events("file", cfg.clone());
         setup_events("network", cfg.clone());
@@ -1,19 +1,16 @@
 // Copyright (C) 2024, Achiefs.
 
 use crate::event;
-use crate::appconfig;
 use crate::appconfig::*;
 use crate::ruleset::*;
+use crate::output;
+use crate::output::Envelope;
+use crate::process::Process;
 
 use event::Event;
-use log::*;
 use serde_json::{json, to_string};
 use std::path::PathBuf;
-use reqwest::Client;
-use std::fs::OpenOptions;
-use std::time::Duration;
 use std::fmt;
-use std::io::Write;
 
 
 pub struct MonitorEvent {
@@ -23,22 +20,24 @@ pub struct MonitorEvent {
   pub node: String,
   pub version: String,
   pub path: PathBuf,
+  pub source: Option<PathBuf>,
   pub size: u64,
   pub kind: notify::EventKind,
   pub labels: Vec<String>,
   pub operation: String,
   pub detailed_operation: String,
+  pub detailed_operations: Vec<String>,
   pub checksum: String,
+  pub process: Option<Process>,
   pub fpid: u32,
   pub system: String
 }
 
 
 
-impl Event for MonitorEvent {
-  // Get formatted string with all required data
-  fn format_json(&self) -> String {
-      let obj = json!({
+impl MonitorEvent {
+  fn get_json(&self) -> serde_json::Value {
+      let mut json = json!({
           "id": self.id.clone(),
           "timestamp": self.timestamp.clone(),
           "hostname": self.hostname.clone(),
@@ -53,8 +52,31 @@ impl Event for MonitorEvent {
           "checksum": self.checksum.clone(),
           "system": self.system.clone()
       });
-      to_string(&obj).unwrap()
+      // Coalesced events list every operation observed
+      if !self.detailed_operations.is_empty() {
+          json["detailed_operations"] = json!(self.detailed_operations);
+      }
+      // Process of the change, named as in audit events
+      if let Some(process) = &self.process {
+          json["pid"] = json!(process.pid);
+          json["exe"] = json!(process.exe);
+          json["comm"] = json!(process.comm);
+          json["uid"] = json!(process.uid);
+      }
+      // Moves keep both locations, file is the destination
+      if let Some(source) = &self.source {
+          json["source_path"] = json!(source.to_str().unwrap());
+          json["destination_path"] = json!(self.path.to_str().unwrap());
+      }
+      json
   }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Event for MonitorEvent {
+  // Get formatted string with all required data
+  fn format_json(&self) -> String { to_string(&self.get_json()).unwrap() }
 
   // ------------------------------------------------------------------------
 
@@ -66,12 +88,15 @@ impl Event for MonitorEvent {
           node: self.node.clone(),
           version: self.version.clone(),
           path: self.path.clone(),
+          source: self.source.clone(),
           size: self.size,
           kind: self.kind,
           labels: self.labels.clone(),
           operation: self.operation.clone(),
           detailed_operation: self.detailed_operation.clone(),
+          detailed_operations: self.detailed_operations.clone(),
           checksum: self.checksum.clone(),
+          process: self.process.clone(),
           fpid: self.fpid,
           system: self.system.clone()
       }
@@ -79,106 +104,17 @@ impl Event for MonitorEvent {
 
   // ------------------------------------------------------------------------
 
-  // Function to write the received events to file
-  fn log(&self, cfg: AppConfig) {
-    let file = cfg.events_lock.lock().unwrap();
-    let mut events_file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(file.as_str())
-        .expect("(log) Unable to open events log file.");
-
-    match writeln!(events_file, "{}", self.format_json() ) {
-        Ok(_d) => debug!("Event log written"),
-        Err(e) => error!("Event could not be written, Err: [{}]", e)
-    }
+  fn envelope(&self) -> Envelope {
+    Envelope::new(&self.id, self.get_json()).with_kind("monitor")
+      .with_path(self.path.to_str().unwrap())
   }
 
   // ------------------------------------------------------------------------
 
-  // Function to send events through network
-  async fn send(&self, cfg: AppConfig) {
-        use time::OffsetDateTime;
-        let current_date = OffsetDateTime::now_utc();
-        let index = format!("fim-{}-{}-{}", current_date.year(), current_date.month() as u8, current_date.day() );
-        
-        // Splunk endpoint integration
-        if cfg.endpoint_type == "Splunk" {
-            let data = json!({
-                "source": self.node.clone(),
-                "sourcetype": "_json",
-                "event": json!({
-                    "timestamp": self.timestamp.clone(),
-                    "hostname": self.hostname.clone(),
-                    "node": self.node.clone(),
-                    "fpid": self.fpid.clone(),
-                    "version": self.version.clone(),
-                    "labels": self.labels.clone(),
-                    "operation": self.operation.clone(),
-                    "detailed_operation": self.detailed_operation.clone(),
-                    "file": String::from(self.path.clone().to_str().unwrap()),
-                    "file_size": self.size.clone(),
-                    "checksum": self.checksum.clone(),
-                    "system": self.system.clone()
-                }),
-                "index": "fim_events"
-            });
-            debug!("Sending received event to Splunk integration, event: {}", data);
-            let request_url = format!("{}/services/collector/event", cfg.endpoint_address);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .header("Authorization", format!("Splunk {}", cfg.endpoint_token))
-                .json(&data)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        // Elastic endpoint integration
-        } else {
-            let data = json!({
-                "timestamp": self.timestamp.clone(),
-                "hostname": self.hostname.clone(),
-                "node": self.node.clone(),
-                "fpid": self.fpid.clone(),
-                "version": self.version.clone(),
-                "labels": self.labels.clone(),
-                "operation": self.operation.clone(),
-                "detailed_operation": self.detailed_operation.clone(),
-                "file": String::from(self.path.clone().to_str().unwrap()),
-                "file_size": self.size.clone(),
-                "checksum": self.checksum.clone(),
-                "system": self.system.clone()
-            });
-            let request_url = format!("{}/{}/_doc/{}", cfg.endpoint_address, index, self.id);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .basic_auth(cfg.endpoint_user, Some(cfg.endpoint_pass))
-                .json(&data)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        }
-
-    }
-
-    // ------------------------------------------------------------------------
-
     // Function to manage event destination
     async fn process(&self, cfg: AppConfig, _ruleset: Ruleset) {
-        route(self, cfg.clone()).await;
+        let destination = cfg.get_path_destination(self.path.to_str().unwrap());
+        output::route(&self.envelope(), &destination, &cfg).await;
         _ruleset.match_rule(cfg, self.path.clone(), self.id.clone()).await;
     }
 
@@ -187,14 +123,24 @@ impl Event for MonitorEvent {
     fn get_string(&self, field: String) -> String {
         match field.as_str() {
             "path" => String::from(self.path.to_str().unwrap()),
+            "source_path" => self.source.as_ref().map(|source| String::from(source.to_str().unwrap())).unwrap_or_default(),
+            "destination_path" => match self.source {
+                Some(_) => String::from(self.path.to_str().unwrap()),
+                None => String::new()
+            },
             "file_size" => self.size.clone().to_string(),
             "hostname" => self.hostname.clone(),
             "node" => self.node.clone(),
             "version" => self.version.clone(),
             "operation" => self.operation.clone(),
             "detailed_operation" => self.detailed_operation.clone(),
+            "detailed_operations" => self.detailed_operations.join(","),
             "checksum" => self.checksum.clone(),
             "system" => self.system.clone(),
+            "pid" => self.process.as_ref().map(|process| process.pid.clone()).unwrap_or_default(),
+            "exe" => self.process.as_ref().map(|process| process.exe.clone()).unwrap_or_default(),
+            "comm" => self.process.as_ref().map(|process| process.comm.clone()).unwrap_or_default(),
+            "uid" => self.process.as_ref().map(|process| process.uid.clone()).unwrap_or_default(),
             _ => "".to_string()
         }
     }
@@ -216,21 +162,6 @@ impl fmt::Debug for MonitorEvent {
 
 // ----------------------------------------------------------------------------
 
-pub async fn route(event: &MonitorEvent, cfg: AppConfig) {
-  match cfg.get_events_destination().as_str() {
-      appconfig::BOTH_MODE => {
-          event.log(cfg.clone());
-          event.send(cfg).await;
-      },
-      appconfig::NETWORK_MODE => {
-          event.send(cfg).await;
-      },
-      _ => event.log(cfg.clone())
-  }
-}
-
-// ----------------------------------------------------------------------------
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -259,11 +190,14 @@ mod tests {
             version: "x.x.x".to_string(),
             kind: EventKind::Create(CreateKind::Any),
             path: PathBuf::new(),
+            source: None,
             size: 0,
             labels: Vec::new(),
             operation: "CREATE".to_string(),
             detailed_operation: "CREATE_FILE".to_string(),
+            detailed_operations: Vec::new(),
             checksum: "UNKNOWN".to_string(),
+            process: None,
             fpid: 0,
             system: "test".to_string()
         }
@@ -281,11 +215,13 @@ mod tests {
         assert_eq!(event.node, cloned.node);
         assert_eq!(event.version, cloned.version);
         assert_eq!(event.path, cloned.path);
+        assert_eq!(event.source, cloned.source);
         assert_eq!(event.size, cloned.size);
         assert_eq!(event.kind, cloned.kind);
         assert_eq!(event.labels, cloned.labels);
         assert_eq!(event.operation, cloned.operation);
         assert_eq!(event.detailed_operation, cloned.detailed_operation);
+        assert_eq!(event.detailed_operations, cloned.detailed_operations);
         assert_eq!(event.checksum, cloned.checksum);
         assert_eq!(event.fpid, cloned.fpid);
         assert_eq!(event.system, cloned.system);
@@ -315,8 +251,8 @@ mod tests {
     #[test]
     fn test_send() {
         let evt = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        block_on( evt.send(cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
@@ -324,8 +260,8 @@ mod tests {
     #[test]
     fn test_send_splunk() {
         let evt = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml"));
-        block_on( evt.send(cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+        block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
@@ -441,8 +377,8 @@ mod tests {
     #[test]
     fn test_process() {
         let event = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        let ruleset = Ruleset::new(&utils::get_os(), None);  
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap();  
 
         block_on(event.process(cfg, ruleset));
         //block_on(event.process(appconfig::NETWORK_MODE, String::from("test"), cfg.clone()));
@@ -472,13 +408,58 @@ mod tests {
 
     // ------------------------------------------------------------------------
 
+    #[test]
+    fn test_format_json_move() {
+        let mut event = create_test_event();
+        event.operation = String::from("MOVE");
+        event.path = PathBuf::from("/tmp/new");
+        event.source = Some(PathBuf::from("/tmp/old"));
+        let json = event.get_json();
+        assert_eq!(json["file"], "/tmp/new");
+        assert_eq!(json["source_path"], "/tmp/old");
+        assert_eq!(json["destination_path"], "/tmp/new");
+        assert_eq!(event.get_string(String::from("source_path")), "/tmp/old");
+        assert_eq!(event.get_string(String::from("destination_path")), "/tmp/new");
+        assert!(create_test_event().get_json().get("source_path").is_none());
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_format_json_detailed_operations() {
+        let mut event = create_test_event();
+        event.detailed_operations = vec![String::from("CREATE_FILE"), String::from("MODIFY_CONTENT")];
+        let json = event.get_json();
+        assert_eq!(json["detailed_operations"], json!(["CREATE_FILE", "MODIFY_CONTENT"]));
+        assert_eq!(event.get_string(String::from("detailed_operations")), "CREATE_FILE,MODIFY_CONTENT");
+        assert!(create_test_event().get_json().get("detailed_operations").is_none());
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_format_json_process() {
+        let mut event = create_test_event();
+        event.process = Some(Process { pid: String::from("10"), exe: String::from("/usr/bin/vim"),
+            comm: String::from("vim"), uid: String::from("1000") });
+        let json = event.get_json();
+        assert_eq!(json["pid"], "10");
+        assert_eq!(json["exe"], "/usr/bin/vim");
+        assert_eq!(json["comm"], "vim");
+        assert_eq!(json["uid"], "1000");
+        assert_eq!(event.get_string(String::from("comm")), "vim");
+        assert!(create_test_event().get_json().get("pid").is_none());
+    }
+
+    // ------------------------------------------------------------------------
+
     #[test]
     fn test_log() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log.yml")).unwrap();
         let filename = String::from("test_log.json");
         let evt = create_test_event();
 
-        evt.log(cfg.clone());
+        block_on( output::route(&evt.envelope(), FILE_MODE, &cfg) );
         let contents = fs::read_to_string(filename.clone());
         let expected = "{\"checksum\":\"UNKNOWN\",\"detailed_operation\":\"CREATE_FILE\",\
             \"file\":\"\",\"file_size\":0,\"fpid\":0,\
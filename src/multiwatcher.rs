@@ -1,46 +1,240 @@
 use notify::{RecommendedWatcher, Watcher, Config as NConfig};
 use notify::poll::PollWatcher;
+use std::collections::{HashMap, HashSet};
 use std::sync::mpsc;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use notify::RecursiveMode;
+use log::{info, error, warn};
+
+use crate::appconfig::AppConfig;
+#[cfg(target_os = "linux")]
+use crate::fanotify::Fanotify;
+use crate::watchlimit::{self, Shortage};
+
+// File systems whose changes made from other hosts are not notified
+const NETWORK_FS: [&str; 13] = ["nfs", "nfs4", "cifs", "smb3", "smbfs", "ncpfs", "afs", "9p", "ceph",
+    "glusterfs", "lustre", "davfs", "fuse"];
+
+// ----------------------------------------------------------------------------
+
+/// Check if a file system type is a network or FUSE one, which need polling.
+pub fn is_network_fs(fs_type: &str) -> bool {
+    NETWORK_FS.contains(&fs_type) || fs_type.starts_with("fuse.")
+}
+
+// ----------------------------------------------------------------------------
+
+// Mount point field of /proc/mounts, where spaces and tabs are escaped
+fn unescape(field: &str) -> String {
+    field.replace("\\040", " ").replace("\\011", "\t").replace("\\012", "\n").replace("\\134", "\\")
+}
+
+// ----------------------------------------------------------------------------
+
+/// Type of the file system holding `path` in a /proc/mounts list, the one of
+/// its deepest mount point, the last mounted if there are several.
+pub fn get_fs_type(path: &Path, mounts: &str) -> Option<String> {
+    mounts.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let point = PathBuf::from(unescape(fields.nth(1)?));
+        fields.next().map(|fs_type| (point, fs_type))
+    }).filter(|(point, _)| path.starts_with(point))
+        .max_by_key(|(point, _)| point.components().count())
+        .map(|(_, fs_type)| String::from(fs_type))
+}
+
+// ----------------------------------------------------------------------------
+
+// Check if a path is in a network file system, from its mount type
+#[cfg(target_os = "linux")]
+fn is_network(path: &Path) -> bool {
+    let path = path.canonicalize().unwrap_or(path.to_path_buf());
+    let mounts = std::fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    match get_fs_type(&path, &mounts) {
+        Some(fs_type) if is_network_fs(&fs_type) => {
+            info!("Polling '{}', it is in a {} file system.", path.display(), fs_type);
+            true
+        },
+        _ => false
+    }
+}
+
+// Network shares are reached with UNC paths
+#[cfg(windows)]
+fn is_network(path: &Path) -> bool {
+    path.to_str().is_some_and(|p| p.starts_with("\\\\"))
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn is_network(_path: &Path) -> bool {
+    false
+}
 
 // ----------------------------------------------------------------------------
 
 pub struct MultiWatcher {
     pub poll_watcher: PollWatcher,
     pub recommended_watcher: RecommendedWatcher,
-    pub kind: String
+    pub kind: String,
+    // Paths watched by polling, to unwatch them with the same watcher
+    polled: HashSet<PathBuf>,
+    // Trees short of watches, their root is watched alone and these subtrees
+    // recursively, to unwatch them together
+    partial: HashMap<PathBuf, Vec<PathBuf>>,
+    // Alert or Poll, for the trees exceeding the inotify watches left
+    watch_limit: String,
+    shortages: Vec<Shortage>,
+    #[cfg(target_os = "linux")]
+    fanotify: Option<Fanotify>
 }
 
 impl MultiWatcher {
-    pub fn new(kind: &str, tx: mpsc::Sender<Result<notify::Event, notify::Error>>) -> Self {
-        match kind {
-            "Pool" => {
-                let (_tx, _rx) = mpsc::channel();
-                MultiWatcher {
-                    poll_watcher: PollWatcher::new(tx, NConfig::default()).unwrap(),
-                    recommended_watcher: RecommendedWatcher::new(_tx, NConfig::default()).unwrap(),
-                    kind: String::from(kind)
+    /// Watcher of the `events.watcher` kind. Poll checks every path each
+    /// `events.poll.interval` seconds, Hybrid only the paths in network
+    /// file systems and uses the recommended watcher for the rest. Fanotify
+    /// watches the monitored trees, single folders use the recommended
+    /// watcher, and falls back to it if fanotify can't be used. Trees that
+    /// exceed the inotify watches left are polled or reported, following
+    /// `events.watch_limit`.
+    pub fn new(cfg: &AppConfig, tx: mpsc::Sender<Result<notify::Event, notify::Error>>) -> Self {
+        let poll_config = NConfig::default()
+            .with_poll_interval(Duration::from_secs(cfg.poll_interval))
+            .with_compare_contents(cfg.poll_compare_contents);
+        let mut kind = cfg.events_watcher.clone();
+        #[cfg(target_os = "linux")]
+        let fanotify = match kind.as_str() {
+            "Fanotify" => match Fanotify::new(tx.clone()) {
+                Ok(fanotify) => Some(fanotify),
+                Err(e) => {
+                    error!("Could not start fanotify, using the recommended watcher, description: {}", e);
+                    kind = String::from("Recommended");
+                    None
                 }
+            },
+            _ => None
+        };
+        #[cfg(not(target_os = "linux"))]
+        if kind == "Fanotify" {
+            error!("Fanotify is only available on Linux, using the recommended watcher.");
+            kind = String::from("Recommended");
+        }
+        MultiWatcher {
+            poll_watcher: PollWatcher::new(tx.clone(), poll_config).unwrap(),
+            recommended_watcher: RecommendedWatcher::new(tx, NConfig::default()).unwrap(),
+            kind,
+            polled: HashSet::new(),
+            partial: HashMap::new(),
+            watch_limit: cfg.events_watch_limit.clone(),
+            shortages: Vec::new(),
+            #[cfg(target_os = "linux")]
+            fanotify
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn use_poll(&self, path: &Path) -> bool {
+        match self.kind.as_str() {
+            "Poll" => true,
+            "Hybrid" => is_network(path),
+            _ => false
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn watch(&mut self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(fanotify) = self.fanotify.as_ref().filter(|_| mode == RecursiveMode::Recursive) {
+            return fanotify.watch(path).map_err(notify::Error::io);
+        }
+        if self.use_poll(path) {
+            self.poll_watcher.watch(path, mode)?;
+            self.polled.insert(path.to_path_buf());
+            Ok(())
+        }else{
+            if mode == RecursiveMode::Recursive {
+                return self.watch_tree(path);
             }
-            _ => {
-                let (_tx, _rx) = mpsc::channel();
-                MultiWatcher {
-                    poll_watcher: PollWatcher::new(_tx, NConfig::default()).unwrap(),
-                    recommended_watcher: RecommendedWatcher::new(tx, NConfig::default()).unwrap(),
-                    kind: String::from(kind)
+            self.recommended_watcher.watch(path, mode)
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Watch a tree with inotify, checking first it has a watch for each of
+    // its folders. The limit may still be reached if other processes take
+    // watches meanwhile. Other systems don't set inotify limits.
+    fn watch_tree(&mut self, path: &Path) -> notify::Result<()> {
+        let available = match watchlimit::get_available() {
+            Some(available) => available,
+            None => return self.recommended_watcher.watch(path, RecursiveMode::Recursive)
+        };
+        let mut shortage = match watchlimit::check(path, available) {
+            Some(shortage) => shortage,
+            None => match self.recommended_watcher.watch(path, RecursiveMode::Recursive) {
+                Err(notify::Error { kind: notify::ErrorKind::MaxFilesWatch, .. }) => Shortage {
+                    path: path.to_path_buf(), needed: watchlimit::count_folders(path), available,
+                    covered: Vec::new(), uncovered: vec![path.to_path_buf()], polled: false },
+                result => return result
+            }
+        };
+        warn!("Inotify watches exceeded watching '{}', it needs {} and {} are left.", path.display(),
+            shortage.needed, shortage.available);
+        // Watches added before the limit was reached are not needed
+        let _ = self.recommended_watcher.unwatch(path);
+        if self.watch_limit == "Poll" {
+            self.poll_watcher.watch(path, RecursiveMode::Recursive)?;
+            self.polled.insert(path.to_path_buf());
+            shortage.polled = true;
+        }else if !shortage.uncovered.iter().any(|uncovered| uncovered == path) {
+            // Only the subtrees that fit are watched, the uncovered ones don't
+            // take the watches left to other trees
+            self.recommended_watcher.watch(path, RecursiveMode::NonRecursive)?;
+            let mut subtrees = Vec::new();
+            for subtree in shortage.covered.iter() {
+                match self.recommended_watcher.watch(subtree, RecursiveMode::Recursive) {
+                    Ok(()) => subtrees.push(subtree.clone()),
+                    Err(notify::Error { kind: notify::ErrorKind::MaxFilesWatch, .. }) => {
+                        let _ = self.recommended_watcher.unwatch(subtree);
+                        shortage.uncovered.push(subtree.clone());
+                    },
+                    Err(e) => {
+                        error!("Could not watch '{}', description: {}", subtree.display(), e);
+                        shortage.uncovered.push(subtree.clone());
+                    }
                 }
             }
+            self.partial.insert(path.to_path_buf(), subtrees);
         }
+        self.shortages.push(shortage);
+        Ok(())
     }
 
     // ------------------------------------------------------------------------
 
-    pub fn watch(&mut self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
-        if self.kind != "Poll" {
-            self.recommended_watcher.watch(path, mode)
+    /// Trees found exceeding the inotify watches left since the last call.
+    pub fn take_shortages(&mut self) -> Vec<Shortage> {
+        std::mem::take(&mut self.shortages)
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn unwatch(&mut self, path: &Path) -> notify::Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(fanotify) = self.fanotify.as_ref().filter(|fanotify| fanotify.is_watched(path)) {
+            return fanotify.unwatch(path).map_err(notify::Error::io);
+        }
+        if self.polled.remove(path) {
+            self.poll_watcher.unwatch(path)
+        }else if let Some(subtrees) = self.partial.remove(path) {
+            for subtree in subtrees {
+                let _ = self.recommended_watcher.unwatch(&subtree);
+            }
+            self.recommended_watcher.unwatch(path)
         }else{
-            self.poll_watcher.watch(path, mode)
+            self.recommended_watcher.unwatch(path)
         }
     }
 }
@@ -50,17 +244,25 @@ impl MultiWatcher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils;
+
+    // ------------------------------------------------------------------------
+
+    fn create_test_watcher(kind: &str) -> MultiWatcher {
+        let mut cfg = AppConfig::new(utils::get_os(), None).unwrap();
+        cfg.events_watcher = String::from(kind);
+        let (_tx, _rx) = mpsc::channel();
+        MultiWatcher::new(&cfg, _tx)
+    }
 
     // ------------------------------------------------------------------------
 
     #[test]
     fn test_new() {
-        let (_tx, _rx) = mpsc::channel();
-        let watcher = MultiWatcher::new("Pool", _tx);
-        assert_eq!(watcher.kind, "Pool");
+        let watcher = create_test_watcher("Poll");
+        assert_eq!(watcher.kind, "Poll");
 
-        let (_tx2, _rx2) = mpsc::channel();
-        let watcher2 = MultiWatcher::new("Recommended", _tx2);
+        let watcher2 = create_test_watcher("Recommended");
         assert_eq!(watcher2.kind, "Recommended");
     }
 
@@ -69,8 +271,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_watch() {
-        let (_tx, _rx) = mpsc::channel();
-        let mut watcher = MultiWatcher::new("Recommended", _tx);
+        let mut watcher = create_test_watcher("Recommended");
         match watcher.watch(Path::new("C:"), RecursiveMode::NonRecursive) {
             Ok(()) => (),
             _ => assert_eq!(1, 2)
@@ -82,11 +283,69 @@ mod tests {
     #[cfg(target_os = "linux")]
     #[test]
     fn test_watch() {
-        let (_tx, _rx) = mpsc::channel();
-        let mut watcher = MultiWatcher::new("Recommended", _tx);
+        let mut watcher = create_test_watcher("Recommended");
         match watcher.watch(Path::new("/etc"), RecursiveMode::NonRecursive) {
             Ok(()) => (),
             _ => assert_eq!(1, 2)
         }
     }
-}
\ No newline at end of file
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_unwatch() {
+        let mut watcher = create_test_watcher("Recommended");
+        watcher.watch(Path::new("/etc"), RecursiveMode::NonRecursive).unwrap();
+        assert!(watcher.unwatch(Path::new("/etc")).is_ok());
+        assert!(watcher.unwatch(Path::new("/etc")).is_err());
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_watch_poll() {
+        let mut watcher = create_test_watcher("Poll");
+        watcher.watch(Path::new("/etc"), RecursiveMode::NonRecursive).unwrap();
+        assert!(watcher.polled.contains(Path::new("/etc")));
+        assert!(watcher.unwatch(Path::new("/etc")).is_ok());
+        assert!(watcher.polled.is_empty());
+
+        // Local file systems use the recommended watcher
+        let mut watcher = create_test_watcher("Hybrid");
+        watcher.watch(Path::new("/proc/self"), RecursiveMode::NonRecursive).unwrap();
+        assert!(watcher.polled.is_empty());
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_get_fs_type() {
+        let mounts = "/dev/sda1 / ext4 rw,relatime 0 0\n\
+            proc /proc proc rw,nosuid 0 0\n\
+            server:/export /mnt/nfs nfs4 rw,relatime 0 0\n\
+            //server/share /mnt/my\\040share cifs rw 0 0\n\
+            tmpfs /mnt/nfs/tmp tmpfs rw 0 0\n\
+            user@host:/ /mnt/sshfs fuse.sshfs rw 0 0\n";
+        assert_eq!(get_fs_type(Path::new("/etc/fim"), mounts), Some(String::from("ext4")));
+        assert_eq!(get_fs_type(Path::new("/mnt/nfs/data"), mounts), Some(String::from("nfs4")));
+        assert_eq!(get_fs_type(Path::new("/mnt/nfs/tmp/a"), mounts), Some(String::from("tmpfs")));
+        assert_eq!(get_fs_type(Path::new("/mnt/my share/a"), mounts), Some(String::from("cifs")));
+        assert_eq!(get_fs_type(Path::new("/mnt/nfsdata"), mounts), Some(String::from("ext4")));
+        assert_eq!(get_fs_type(Path::new("/mnt/sshfs"), mounts), Some(String::from("fuse.sshfs")));
+        assert_eq!(get_fs_type(Path::new("/etc"), ""), None);
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_is_network_fs() {
+        assert!(is_network_fs("nfs4"));
+        assert!(is_network_fs("cifs"));
+        assert!(is_network_fs("fuse.sshfs"));
+        assert!(!is_network_fs("ext4"));
+        assert!(!is_network_fs("tmpfs"));
+        assert!(!is_network_fs("fusectl"));
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright (C) 2026, Achiefs.
+
+// To build the OCSF events
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::appconfig::AppConfig;
+use crate::ecs;
+use crate::hash::ShaType;
+use crate::output::Envelope;
+use crate::ruleset;
+
+#[cfg(test)]
+mod test;
+
+// OCSF schema version of the events
+pub const OCSF_VERSION: &str = "1.1.0";
+pub const FILE_ACTIVITY: u64 = 1001;
+pub const DETECTION_FINDING: u64 = 2004;
+// File System Activity names, their position is the activity id
+const ACTIVITIES: [&str; 15] = ["Unknown", "Create", "Read", "Update", "Delete", "Rename", "Set Attributes",
+    "Set Security", "Get Attributes", "Get Security", "Encrypt", "Decrypt", "Mount", "Unmount", "Open"];
+// Severity names, their position is the severity id
+const SEVERITIES: [&str; 7] = ["Unknown", "Informational", "Low", "Medium", "High", "Critical", "Fatal"];
+
+// ----------------------------------------------------------------------------
+
+/// File System Activity id of an event, from its detailed operation when
+/// available and from its operation otherwise.
+pub fn get_activity_id(operation: &str, detailed_operation: &str) -> u64 {
+    match detailed_operation {
+        "" => match operation {
+            "ANY" => 0,
+            "CREATE" => 1,
+            "ACCESS" => 2,
+            "WRITE" | "NORMAL" => 3,
+            "REMOVE" | "DELETE" => 4,
+            _ => 99
+        },
+        "ANY" => 0,
+        "MODIFY_METADATA_PERMISSIONS" | "MODIFY_METADATA_OWNERSHIP" => 7,
+        "ACCESS_ANY" | "ACCESS_READ" | "ACCESS_OPEN_READ" => 2,
+        detailed if detailed.starts_with("CREATE") => 1,
+        detailed if detailed.starts_with("MODIFY_RENAME") => 5,
+        detailed if detailed.starts_with("MODIFY_METADATA") => 6,
+        detailed if detailed.starts_with("MODIFY") => 3,
+        detailed if detailed.starts_with("REMOVE") => 4,
+        detailed if detailed.starts_with("ACCESS_OPEN") => 14,
+        _ => 99
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_activity_name(activity_id: u64) -> &'static str {
+    ACTIVITIES.get(activity_id as usize).copied().unwrap_or("Other")
+}
+
+// ----------------------------------------------------------------------------
+
+/// Severity id of a rule severity, from 0 to 10.
+pub fn get_severity_id(severity: u64) -> u64 {
+    match severity {
+        0..=1 => 1,
+        2..=3 => 2,
+        4..=6 => 3,
+        7..=8 => 4,
+        _ => 5
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_time(timestamp: &Value) -> Value {
+    timestamp.as_str().and_then(|timestamp| timestamp.parse::<u64>().ok()).map(Value::from).unwrap_or_default()
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_os_type_id(system: &Value) -> u64 {
+    match system.as_str() {
+        Some("windows") => 100,
+        Some("linux") => 200,
+        Some("macos") => 300,
+        _ => 0
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// File type from the detailed operation or the audit mode
+fn get_file_type_id(detailed_operation: &str, mode: &Value) -> u64 {
+    let mode = mode.as_str().and_then(|mode| u32::from_str_radix(mode, 8).ok());
+    match (detailed_operation, mode.map(|mode| mode & 0o170000)) {
+        (_, Some(0o100000)) => 1,
+        (_, Some(0o040000)) => 2,
+        (_, Some(0o120000)) => 7,
+        (detailed, _) if detailed.ends_with("_FILE") => 1,
+        (detailed, _) if detailed.ends_with("_FOLDER") => 2,
+        _ => 0
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_hashes(checksum: &Value, algorithm: &ShaType) -> Value {
+    let (algorithm_id, name) = match algorithm {
+        ShaType::Sha256 => (3, "SHA-256"),
+        ShaType::Sha512 => (4, "SHA-512"),
+        other => (99, other.name())
+    };
+    match checksum.as_str() {
+        Some(checksum) if !checksum.is_empty() => json!([{
+            "algorithm_id": algorithm_id,
+            "algorithm": name,
+            "value": checksum
+        }]),
+        _ => Value::Null
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_file(path: &Value, type_id: u64) -> Value {
+    let file = match path.as_str() {
+        Some(path) => Path::new(path),
+        None => return Value::Null
+    };
+    json!({
+        "path": path,
+        "name": file.file_name().map(|name| name.to_string_lossy()),
+        "parent_folder": file.parent().map(|parent| parent.to_string_lossy()),
+        "type_id": type_id
+    })
+}
+
+// ----------------------------------------------------------------------------
+
+/// OCSF event of an envelope. File events are File System Activity events,
+/// rule matches are Detection Findings with the severity of the rule and
+/// application events use the base event class.
+pub fn to_ocsf(envelope: &Envelope, cfg: &AppConfig) -> Value {
+    let data = &envelope.data;
+    let kind = envelope.kind.as_deref().unwrap_or("event");
+    let path = Value::from(envelope.path.clone());
+    let mut event = json!({
+        "time": get_time(&data["timestamp"]),
+        "metadata": {
+            "version": OCSF_VERSION,
+            "uid": envelope.id,
+            "log_name": kind,
+            "product": { "name": "FIM", "vendor_name": "Achiefs", "version": data["version"] },
+            "labels": data["labels"]
+        },
+        "device": {
+            "hostname": data["hostname"],
+            "name": data["node"],
+            "type_id": 0,
+            "os": { "name": data["system"], "type_id": get_os_type_id(&data["system"]) }
+        },
+        "severity_id": 1,
+        "severity": SEVERITIES[1]
+    });
+
+    let fields = match kind {
+        "rule" => {
+            let severity_id = get_severity_id(data["severity"].as_u64().unwrap_or(ruleset::DEFAULT_SEVERITY as u64));
+            // Rule ids are not unique, the finding is identified by the matched event
+            let uid = format!("{}:{}", data["parent_id"].as_str().unwrap_or_default(), data["id"]);
+            json!({
+                "class_uid": DETECTION_FINDING,
+                "class_name": "Detection Finding",
+                "category_uid": 2,
+                "category_name": "Findings",
+                "activity_id": 1,
+                "activity_name": "Create",
+                "type_uid": DETECTION_FINDING * 100 + 1,
+                "type_name": "Detection Finding: Create",
+                "severity_id": severity_id,
+                "severity": SEVERITIES[severity_id as usize],
+                "message": data["message"],
+                "metadata": { "uid": uid },
+                "finding_info": {
+                    "uid": uid,
+                    "title": data["message"],
+                    "analytic": { "uid": data["id"].to_string(), "name": data["message"], "type_id": 1, "type": "Rule" },
+                    "related_events": [{ "uid": data["parent_id"] }]
+                },
+                "evidences": [{ "file": get_file(&path, 0) }],
+                "unmapped": { "rule": data["rule"] }
+            })
+        },
+        "app" => json!({
+            "class_uid": 0,
+            "class_name": "Base Event",
+            "category_uid": 0,
+            "category_name": "Uncategorized",
+            "activity_id": 0,
+            "type_uid": 0,
+            "message": data["message"]
+        }),
+        _ => {
+            // Only monitor events have a detailed operation
+            let detailed_operation = data["detailed_operation"].as_str().unwrap_or_default();
+            let activity_id = get_activity_id(data["operation"].as_str().unwrap_or_default(), detailed_operation);
+            let mut fields = json!({
+                "class_uid": FILE_ACTIVITY,
+                "class_name": "File System Activity",
+                "category_uid": 1,
+                "category_name": "System Activity",
+                "activity_id": activity_id,
+                "activity_name": get_activity_name(activity_id),
+                "type_uid": FILE_ACTIVITY * 100 + activity_id,
+                "type_name": format!("File System Activity: {}", get_activity_name(activity_id))
+            });
+            let type_id = get_file_type_id(detailed_operation, &data["mode"]);
+            let mut file = get_file(&path, type_id);
+            match kind {
+                "audit" => {
+                    let algorithm = cfg.get_path_checksum_algorithm(path.as_str().unwrap_or_default());
+                    ecs::merge(&mut file, json!({
+                        "size": data["file_size"],
+                        "hashes": get_hashes(&data["checksum"], &algorithm),
+                        "uid": data["inode"],
+                        "owner": { "uid": data["ouid"] }
+                    }));
+                    ecs::merge(&mut fields, json!({
+                        "status_id": match data["success"].as_str() {
+                            Some("yes") => 1,
+                            Some("no") => 2,
+                            _ => 0
+                        },
+                        "actor": {
+                            "process": {
+                                "pid": ecs::get_number(&data["pid"]),
+                                "name": data["comm"],
+                                "cmd_line": data["command"],
+                                "file": get_file(&data["exe"], 1),
+                                "parent_process": { "pid": ecs::get_number(&data["ppid"]) },
+                                "user": { "uid": data["uid"] }
+                            },
+                            "user": { "uid": data["auid"] }
+                        },
+                        "unmapped": {
+                            "cwd": data["cwd"], "syscall": data["syscall"], "arch": data["arch"],
+                            "a0": data["a0"], "a1": data["a1"], "a2": data["a2"], "a3": data["a3"],
+                            "euid": data["euid"], "gid": data["gid"], "exit": data["exit"], "key": data["key"],
+                            "ses": data["ses"], "tty": data["tty"], "mode": data["mode"]
+                        }
+                    }));
+                },
+                "hash" => {
+                    let algorithm = &cfg.hashscanner_algorithm;
+                    file = get_file(&data["dbfile.path"], 1);
+                    ecs::merge(&mut file, json!({
+                        "size": data["dbfile.size"],
+                        "hashes": get_hashes(&data["dbfile.hash"], algorithm)
+                    }));
+                    ecs::merge(&mut fields, json!({
+                        "time": get_time(&data["dbfile.timestamp"]),
+                        "metadata": { "uid": data["dbfile.id"] },
+                        "unmapped": {
+                            "permissions": data["dbfile.permissions"],
+                            "previous_hash": data["previous_dbfile.hash"],
+                            "previous_size": data["previous_dbfile.size"],
+                            "previous_permissions": data["previous_dbfile.permissions"]
+                        }
+                    }));
+                },
+                _ => {
+                    let algorithm = cfg.get_path_checksum_algorithm(path.as_str().unwrap_or_default());
+                    ecs::merge(&mut file, json!({
+                        "size": data["file_size"],
+                        "hashes": get_hashes(&data["checksum"], &algorithm)
+                    }));
+                    ecs::merge(&mut fields, json!({
+                        "actor": { "process": {
+                            "pid": ecs::get_number(&data["pid"]),
+                            "name": data["comm"],
+                            "file": get_file(&data["exe"], 1),
+                            "user": { "uid": data["uid"] }
+                        }},
+                        "unmapped": { "detailed_operation": data["detailed_operation"] }
+                    }));
+                    // Moves describe the source file and the destination as result
+                    if data["source_path"].is_string() {
+                        ecs::merge(&mut fields, json!({ "file_result": file }));
+                        file = get_file(&data["source_path"], type_id);
+                    }
+                }
+            }
+            ecs::merge(&mut fields, json!({ "file": file }));
+            fields
+        }
+    };
+    ecs::merge(&mut event, fields);
+    ecs::clean(event)
+}
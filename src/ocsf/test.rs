@@ -0,0 +1,228 @@
+use super::*;
+
+use crate::utils;
+
+// ----------------------------------------------------------------------------
+
+fn create_test_config() -> AppConfig {
+    AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_format.yml")).unwrap()
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check detailed operations take precedence over operations
+fn test_get_activity_id() {
+    assert_eq!(get_activity_id("CREATE", "CREATE_FILE"), 1);
+    assert_eq!(get_activity_id("ACCESS", "ACCESS_READ"), 2);
+    assert_eq!(get_activity_id("ACCESS", "ACCESS_OPEN_WRITE"), 14);
+    assert_eq!(get_activity_id("ACCESS", "ACCESS_CLOSE_WRITE"), 99);
+    assert_eq!(get_activity_id("WRITE", "MODIFY_DATA_CONTENT"), 3);
+    assert_eq!(get_activity_id("WRITE", "MODIFY_RENAME_BOTH"), 5);
+    assert_eq!(get_activity_id("WRITE", "MODIFY_METADATA_WRITETIME"), 6);
+    assert_eq!(get_activity_id("WRITE", "MODIFY_METADATA_PERMISSIONS"), 7);
+    assert_eq!(get_activity_id("REMOVE", "REMOVE_FOLDER"), 4);
+    assert_eq!(get_activity_id("ANY", "ANY"), 0);
+    assert_eq!(get_activity_id("OTHER", "OTHER"), 99);
+
+    assert_eq!(get_activity_id("CREATE", ""), 1);
+    assert_eq!(get_activity_id("NORMAL", ""), 3);
+    assert_eq!(get_activity_id("DELETE", ""), 4);
+    assert_eq!(get_activity_id("PARENT", ""), 99);
+    assert_eq!(get_activity_name(99), "Other");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check rule severities are grouped in OCSF severity ids
+fn test_get_severity_id() {
+    let ids: Vec<u64> = (0..=10).map(get_severity_id).collect();
+    assert_eq!(ids, vec![1, 1, 2, 2, 3, 3, 3, 4, 4, 5, 5]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check monitor events are File System Activity events
+fn test_to_ocsf_monitor() {
+    let envelope = Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "timestamp": "1699063400250",
+        "hostname": "Hostname",
+        "node": "Node",
+        "fpid": 10,
+        "version": "x.x.x",
+        "labels": ["etc"],
+        "operation": "WRITE",
+        "detailed_operation": "MODIFY_DATA_CONTENT",
+        "file": "/etc/hosts",
+        "file_size": 10,
+        "checksum": "abc",
+        "system": "linux"
+    })).with_kind("monitor").with_path("/etc/hosts");
+    assert_eq!(to_ocsf(&envelope, &create_test_config()), json!({
+        "class_uid": 1001,
+        "class_name": "File System Activity",
+        "category_uid": 1,
+        "category_name": "System Activity",
+        "activity_id": 3,
+        "activity_name": "Update",
+        "type_uid": 100103,
+        "type_name": "File System Activity: Update",
+        "severity_id": 1,
+        "severity": "Informational",
+        "time": 1699063400250u64,
+        "metadata": {
+            "version": OCSF_VERSION,
+            "uid": "Test_id",
+            "log_name": "monitor",
+            "product": { "name": "FIM", "vendor_name": "Achiefs", "version": "x.x.x" },
+            "labels": ["etc"]
+        },
+        "device": { "hostname": "Hostname", "name": "Node", "type_id": 0, "os": { "name": "linux", "type_id": 200 } },
+        "file": {
+            "path": "/etc/hosts",
+            "name": "hosts",
+            "parent_folder": "/etc",
+            "type_id": 0,
+            "size": 10,
+            "hashes": [{ "algorithm_id": 3, "algorithm": "SHA-256", "value": "abc" }]
+        },
+        "unmapped": { "detailed_operation": "MODIFY_DATA_CONTENT" }
+    }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check moves describe the source file with the destination as result
+fn test_to_ocsf_move() {
+    let envelope = Envelope::new("Test_id", json!({
+        "operation": "MOVE",
+        "detailed_operation": "MODIFY_RENAME_BOTH",
+        "file": "/etc/hosts.new",
+        "file_size": 10,
+        "source_path": "/tmp/hosts",
+        "destination_path": "/etc/hosts.new"
+    })).with_kind("monitor").with_path("/etc/hosts.new");
+    let event = to_ocsf(&envelope, &create_test_config());
+    assert_eq!(event["activity_name"], "Rename");
+    assert_eq!(event["file"], json!({ "path": "/tmp/hosts", "name": "hosts", "parent_folder": "/tmp", "type_id": 0 }));
+    assert_eq!(event["file_result"]["path"], "/etc/hosts.new");
+    assert_eq!(event["file_result"]["size"], 10);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check monitor events of the fanotify watcher carry their process as actor
+fn test_to_ocsf_monitor_process() {
+    let envelope = Envelope::new("Test_id", json!({
+        "operation": "WRITE",
+        "detailed_operation": "MODIFY_DATA_ANY",
+        "file": "/etc/hosts",
+        "pid": "10",
+        "exe": "/usr/bin/vim",
+        "comm": "vim",
+        "uid": "1000"
+    })).with_kind("monitor").with_path("/etc/hosts");
+    let event = to_ocsf(&envelope, &create_test_config());
+    assert_eq!(event["actor"], json!({ "process": {
+        "pid": 10,
+        "name": "vim",
+        "file": { "path": "/usr/bin/vim", "name": "vim", "parent_folder": "/usr/bin", "type_id": 1 },
+        "user": { "uid": "1000" }
+    }}));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check audit events carry the process as actor and the call status
+fn test_to_ocsf_audit() {
+    let envelope = Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "operation": "CREATE",
+        "mode": "0100644",
+        "inode": "1234",
+        "pid": "4321",
+        "ppid": "1",
+        "comm": "touch",
+        "exe": "/usr/bin/touch",
+        "uid": "1000",
+        "auid": "1000",
+        "success": "no",
+        "syscall": "257"
+    })).with_kind("audit").with_path("/tmp/test");
+    let event = to_ocsf(&envelope, &create_test_config());
+    assert_eq!(event["class_uid"], FILE_ACTIVITY);
+    assert_eq!(event["activity_id"], 1);
+    assert_eq!(event["status_id"], 2);
+    assert_eq!(event["file"]["type_id"], 1);
+    assert_eq!(event["file"]["uid"], "1234");
+    assert_eq!(event["actor"], json!({
+        "process": {
+            "pid": 4321,
+            "name": "touch",
+            "file": { "path": "/usr/bin/touch", "name": "touch", "parent_folder": "/usr/bin", "type_id": 1 },
+            "parent_process": { "pid": 1 },
+            "user": { "uid": "1000" }
+        },
+        "user": { "uid": "1000" }
+    }));
+    assert_eq!(event["unmapped"], json!({ "syscall": "257", "mode": "0100644" }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check hash events use the database file values
+fn test_to_ocsf_hash() {
+    let envelope = Envelope::new("Db_id", json!({
+        "previous_dbfile.hash": "old",
+        "dbfile.id": "Db_id",
+        "dbfile.timestamp": "1699063400250",
+        "dbfile.hash": "new",
+        "dbfile.path": "/etc/passwd",
+        "dbfile.size": 10,
+        "operation": "WRITE"
+    })).with_kind("hash").with_path("/etc/passwd");
+    let event = to_ocsf(&envelope, &create_test_config());
+    assert_eq!(event["activity_id"], 3);
+    assert_eq!(event["time"], 1699063400250u64);
+    assert_eq!(event["metadata"]["uid"], "Db_id");
+    assert_eq!(event["file"]["hashes"][0]["value"], "new");
+    assert_eq!(event["unmapped"], json!({ "previous_hash": "old" }));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check rule matches are Detection Findings with the rule severity
+fn test_to_ocsf_rule() {
+    let envelope = Envelope::new("1", json!({
+        "id": 1,
+        "rule": "\\.sh$",
+        "timestamp": "1699063400250",
+        "message": "Shell script",
+        "severity": 9,
+        "parent_id": "0000"
+    })).with_kind("rule").with_path("/etc/test.sh");
+    let event = to_ocsf(&envelope, &create_test_config());
+    assert_eq!(event["class_uid"], DETECTION_FINDING);
+    assert_eq!(event["type_uid"], 200401);
+    assert_eq!(event["severity_id"], 5);
+    assert_eq!(event["severity"], "Critical");
+    assert_eq!(event["metadata"]["uid"], "0000:1");
+    assert_eq!(event["finding_info"], json!({
+        "uid": "0000:1",
+        "title": "Shell script",
+        "analytic": { "uid": "1", "name": "Shell script", "type_id": 1, "type": "Rule" },
+        "related_events": [{ "uid": "0000" }]
+    }));
+    assert_eq!(event["evidences"][0]["file"]["path"], "/etc/test.sh");
+
+    let envelope = Envelope::new("1", json!({ "id": 1, "parent_id": "0000" })).with_kind("rule");
+    assert_eq!(to_ocsf(&envelope, &create_test_config())["severity_id"], 4);
+}
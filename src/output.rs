@@ -0,0 +1,785 @@
+// Copyright (C) 2026, Achiefs.
+
+// To box sink futures and keep the trait object safe
+use futures::future::BoxFuture;
+// To manage HTTP requests
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use log::*;
+use std::cmp::Ordering;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+
+use crate::appconfig;
+use crate::appconfig::AppConfig;
+use crate::chain;
+use crate::ecs;
+use crate::formatter;
+use crate::spool::Spool;
+use crate::syslog::SyslogSink;
+use crate::tls;
+use crate::webhook::WebhookSink;
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// Seconds between checks of Splunk indexer acknowledgement
+const ACK_INTERVAL: Duration = Duration::from_secs(1);
+
+// ----------------------------------------------------------------------------
+
+/// Common shape of every event written by FIM. Event types only build the
+/// envelope, sinks decide how it is stored or sent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Unique id of the event, used as document id by Elasticsearch.
+    pub id: String,
+    /// Splunk `source` of the event, the node name when not set.
+    pub source: Option<String>,
+    /// File the event refers to, selects the Splunk metadata of its path.
+    pub path: Option<String>,
+    /// Event type, monitor, audit, hash, rule or app, selects the CEF and
+    /// LEEF fields of the event.
+    pub kind: Option<String>,
+    /// Event fields.
+    pub data: Value
+}
+
+impl Envelope {
+    pub fn new(id: &str, data: Value) -> Self {
+        Envelope { id: String::from(id), source: None, path: None, kind: None, data }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(String::from(source));
+        self
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(String::from(path));
+        self
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn with_kind(mut self, kind: &str) -> Self {
+        self.kind = Some(String::from(kind));
+        self
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn to_json(&self) -> String { self.data.to_string() }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Destination of events. Sinks are selected by the router depending on
+/// the mode they belong to, FILE_MODE, NETWORK_MODE or SYSLOG_MODE, and
+/// WEBHOOK_MODE sinks take every event. Network events, and the events of
+/// retried sinks, that fail to be written are kept in the spool.
+pub trait OutputSink: Send + Sync {
+    fn name(&self) -> &str;
+    fn mode(&self) -> &str;
+    fn write<'a>(&'a self, envelope: &'a Envelope) -> BoxFuture<'a, Result<(), String>>;
+
+    /// Events are only sent by the spooler thread, which keeps them in the
+    /// spool until their delivery is confirmed.
+    fn spooled(&self) -> bool { false }
+
+    /// Events that fail to be written are kept in the spool and sent again
+    /// by the spooler thread, instead of being dropped.
+    fn retried(&self) -> bool { false }
+
+    /// Bytes the event takes in a request to the sink, limits the batch size.
+    fn size(&self, envelope: &Envelope) -> usize { envelope.to_json().len() }
+
+    /// Events written to the sink, webhooks only take the ones matching
+    /// their filter.
+    fn accepts(&self, _envelope: &Envelope) -> bool { true }
+
+    /// Write several events, returning one result per event. Sinks without
+    /// a batch API write them one by one.
+    fn write_batch<'a>(&'a self, envelopes: &'a [Envelope]) -> BoxFuture<'a, Vec<Result<(), String>>> {
+        Box::pin(async move {
+            let mut results = Vec::new();
+            for envelope in envelopes {
+                results.push(self.write(envelope).await);
+            }
+            results
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Network events waiting to be sent together, shared by every router.
+#[derive(Default)]
+pub struct Batch {
+    envelopes: Vec<Envelope>,
+    bytes: usize,
+    started: Option<Instant>
+}
+
+impl Batch {
+    pub fn push(&mut self, envelope: &Envelope, bytes: usize) {
+        if self.envelopes.is_empty() { self.started = Some(Instant::now()); }
+        self.bytes += bytes;
+        self.envelopes.push(envelope.clone());
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn len(&self) -> usize { self.envelopes.len() }
+
+    // ------------------------------------------------------------------------
+
+    pub fn is_empty(&self) -> bool { self.envelopes.is_empty() }
+
+    // ------------------------------------------------------------------------
+
+    /// Seconds since the oldest event was added.
+    pub fn age(&self) -> Option<Duration> { self.started.map(|t| t.elapsed()) }
+
+    // ------------------------------------------------------------------------
+
+    pub fn take(&mut self) -> Vec<Envelope> {
+        self.bytes = 0;
+        self.started = None;
+        std::mem::take(&mut self.envelopes)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Appends events as lines of the configured format to the events file.
+pub struct FileSink {
+    file: Arc<Mutex<String>>,
+    cfg: AppConfig
+}
+
+impl FileSink {
+    pub fn new(cfg: &AppConfig) -> Self {
+        FileSink { file: cfg.events_lock.clone(), cfg: cfg.clone() }
+    }
+}
+
+impl OutputSink for FileSink {
+    fn name(&self) -> &str { "file" }
+
+    fn mode(&self) -> &str { appconfig::FILE_MODE }
+
+    fn write<'a>(&'a self, envelope: &'a Envelope) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let file = self.file.lock().unwrap();
+            let mut events_file = OpenOptions::new().create(true).append(true).open(file.as_str())
+                .map_err(|e| format!("unable to open events file '{}', {}", file, e))?;
+            let record = formatter::format(&self.cfg.format_file, envelope, &self.cfg);
+            // The rotator truncates the file holding the lock, so the chain goes on in the new file
+            let link = self.cfg.chain_enabled.then(|| chain::get_last(&file).next(&record, chain::get_key(&self.cfg)));
+            let line = match &link {
+                Some(link) => chain::seal(&record, link),
+                None => record
+            };
+            writeln!(events_file, "{}", line)
+                .map_err(|e| format!("event could not be written, {}", e))?;
+            if let Some(link) = link {
+                chain::save(&file, &link)?;
+            }
+            debug!("Event log written");
+            Ok(())
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Only 2xx responses mean the event was stored, returns the response body
+pub async fn check_response(response: Result<Response, reqwest::Error>) -> Result<String, String> {
+    let response = response.map_err(|e| format!("error on request, {}", e))?;
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    debug!("Response received: {:?}", text);
+    match status.is_success() {
+        true => Ok(text),
+        false => Err(format!("unexpected response status {}, {}", status, text))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// HTTP client shared by every sink and the index setup, built on first
+/// use with the TLS settings of the endpoint. Nothing is sent when those
+/// settings can't be applied.
+pub fn get_client(cfg: &AppConfig) -> Result<Client, String> {
+    if let Some(client) = cfg.client.get() {
+        return Ok(client.clone());
+    }
+    let config = tls::get_endpoint_config(cfg)
+        .map_err(|e| format!("endpoint TLS settings not applied, {}", e))?;
+    let client = Client::builder().timeout(Duration::from_secs(30)).use_preconfigured_tls(config).build()
+        .map_err(|e| format!("unable to build HTTP client, {}", e))?;
+    Ok(cfg.client.get_or_init(|| client).clone())
+}
+
+// ----------------------------------------------------------------------------
+
+// Date tokens accepted in index names
+const INDEX_TOKENS: [char; 6] = ['Y', 'y', 'm', 'd', 'H', '%'];
+/// Daily index used when no index is configured.
+pub const DEFAULT_INDEX: &str = "fim-%Y-%m-%d";
+/// Data stream used when no index is configured.
+pub const DEFAULT_DATA_STREAM: &str = "fim-events";
+
+/// Index where events are stored. The `%Y`, `%y`, `%m`, `%d` and `%H`
+/// tokens of the pattern are replaced by the zero padded UTC date and `%%`
+/// by a literal `%`.
+pub fn get_index_name(pattern: &str, date: OffsetDateTime) -> String {
+    let mut index = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            index.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => index.push_str(&format!("{:04}", date.year())),
+            Some('y') => index.push_str(&format!("{:02}", date.year() % 100)),
+            Some('m') => index.push_str(&format!("{:02}", date.month() as u8)),
+            Some('d') => index.push_str(&format!("{:02}", date.day())),
+            Some('H') => index.push_str(&format!("{:02}", date.hour())),
+            _ => index.push('%')
+        }
+    }
+    index
+}
+
+// ----------------------------------------------------------------------------
+
+/// Pattern matching every index of an index name, the part before the
+/// first date token followed by `*`.
+pub fn get_index_pattern(pattern: &str) -> String {
+    match pattern.find('%') {
+        Some(position) => format!("{}*", &pattern[..position]),
+        None => String::from(pattern)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Time the event happened, from its timestamp in milliseconds or the one
+/// of a database event.
+pub fn get_event_time(envelope: &Envelope) -> Option<OffsetDateTime> {
+    let timestamp = match envelope.data["timestamp"].is_null() {
+        true => &envelope.data["dbfile.timestamp"],
+        false => &envelope.data["timestamp"]
+    };
+    timestamp.as_str().and_then(|timestamp| timestamp.parse::<i128>().ok())
+        .and_then(|timestamp| OffsetDateTime::from_unix_timestamp_nanos(timestamp * 1000000).ok())
+}
+
+// ----------------------------------------------------------------------------
+
+/// True when the pattern has a date token, a literal `%%` is not one.
+pub fn has_date_tokens(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' && chars.next() != Some('%') {
+            return true;
+        }
+    }
+    false
+}
+
+// ----------------------------------------------------------------------------
+
+/// Check an index name accepted by Elasticsearch can be built from the
+/// pattern, lowercase and with known date tokens.
+pub fn check_index_pattern(pattern: &str) -> Result<(), String> {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' && !chars.next().map(|token| INDEX_TOKENS.contains(&token)).unwrap_or(false) {
+            return Err(String::from("unknown date token, expected one of %Y, %y, %m, %d, %H or %%"));
+        }
+    }
+    let index = get_index_name(pattern, OffsetDateTime::now_utc());
+    if index.is_empty() || index.starts_with(['-', '_', '+', '.'])
+        || index.chars().any(|c| c.is_uppercase() || " \\/*?\"<>|,#:".contains(c)) {
+        return Err(String::from("invalid index name, expected lowercase characters without spaces or \\/*?\"<>|,#: \
+            and not starting with -, _, + or ."));
+    }
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+
+/// Indexes each event as a document of the configured index or data stream.
+pub struct ElasticSink {
+    client: Result<Client, String>,
+    address: String,
+    user: String,
+    pass: String,
+    cfg: AppConfig
+}
+
+impl ElasticSink {
+    pub fn new(cfg: &AppConfig) -> Self {
+        ElasticSink {
+            client: get_client(cfg),
+            address: cfg.endpoint_address.clone(),
+            user: cfg.endpoint_user.clone(),
+            pass: cfg.endpoint_pass.clone(),
+            cfg: cfg.clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Indexed document, CEF and LEEF lines are kept in its message field.
+    /// Data streams require the `@timestamp` field, set to the event time.
+    pub fn get_document(&self, envelope: &Envelope) -> Value {
+        let mut document = match formatter::format_value(&self.cfg.format_network, envelope, &self.cfg) {
+            Value::String(line) => json!({ "message": line }),
+            document => document
+        };
+        if self.cfg.elastic_data_stream && document.get("@timestamp").is_none() {
+            let timestamp = match envelope.data["timestamp"].is_null() {
+                true => &envelope.data["dbfile.timestamp"],
+                false => &envelope.data["timestamp"]
+            };
+            document["@timestamp"] = match ecs::get_time(timestamp) {
+                Value::Null => ecs::get_time(&Value::from(utils::get_current_time_millis())),
+                time => time
+            };
+        }
+        document
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Index or data stream of an event, dated with the event time so
+    /// events sent later from the spool go to the index of their day.
+    pub fn get_index(&self, envelope: &Envelope) -> String {
+        get_index_name(&self.cfg.elastic_index, get_event_time(envelope).unwrap_or(OffsetDateTime::now_utc()))
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Data streams only accept the create operation.
+    fn get_action(&self) -> &str {
+        match self.cfg.elastic_data_stream {
+            true => "create",
+            false => "index"
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn get_url(&self, envelope: &Envelope) -> String {
+        let endpoint = match self.cfg.elastic_data_stream {
+            true => "_create",
+            false => "_doc"
+        };
+        format!("{}/{}/{}/{}", self.address, self.get_index(envelope), endpoint, envelope.id)
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Bulk API lines of an event, the index action followed by the document.
+    pub fn get_bulk_lines(&self, envelope: &Envelope) -> String {
+        format!("{}\n{}\n", json!({ self.get_action(): { "_index": self.get_index(envelope), "_id": envelope.id } }),
+            self.get_document(envelope))
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Bulk API body, the action and document lines of every event.
+    pub fn get_bulk_body(&self, envelopes: &[Envelope]) -> String {
+        envelopes.iter().map(|envelope| self.get_bulk_lines(envelope)).collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Result of each event from a bulk API response. Items rejected with a
+/// 429 or 5xx status are retried, other rejections are logged and dropped
+/// as sending them again would fail the same way.
+pub fn get_bulk_results(response: &str, envelopes: &[Envelope]) -> Vec<Result<(), String>> {
+    let body: Value = match serde_json::from_str(response) {
+        Ok(body) => body,
+        Err(e) => return envelopes.iter().map(|_| Err(format!("invalid bulk response, {}", e))).collect()
+    };
+    if body["errors"] != Value::Bool(true) {
+        return envelopes.iter().map(|_| Ok(())).collect();
+    }
+    let items = body["items"].as_array().cloned().unwrap_or_default();
+    envelopes.iter().enumerate().map(|(i, envelope)| {
+        let item = match items.get(i).and_then(|item| item.as_object()).and_then(|item| item.values().next()) {
+            Some(item) => item,
+            None => return Err(String::from("missing bulk response item"))
+        };
+        if item.get("error").is_none() { return Ok(()); }
+        let status = item["status"].as_u64().unwrap_or_default();
+        let reason = item["error"]["reason"].as_str().unwrap_or_default();
+        match status == 429 || status >= 500 {
+            true => Err(format!("bulk item status {}, {}", status, reason)),
+            false => {
+                error!("Event '{}' rejected by Elasticsearch, status {}, {}", envelope.id, status, reason);
+                Ok(())
+            }
+        }
+    }).collect()
+}
+
+impl OutputSink for ElasticSink {
+    fn name(&self) -> &str { "elastic" }
+
+    fn mode(&self) -> &str { appconfig::NETWORK_MODE }
+
+    fn size(&self, envelope: &Envelope) -> usize { self.get_bulk_lines(envelope).len() }
+
+    fn write<'a>(&'a self, envelope: &'a Envelope) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            check_response(self.client.as_ref()?
+                .post(self.get_url(envelope))
+                .basic_auth(&self.user, Some(&self.pass))
+                .json(&self.get_document(envelope))
+                .send()
+                .await).await.map(|_| ())
+        })
+    }
+
+    fn write_batch<'a>(&'a self, envelopes: &'a [Envelope]) -> BoxFuture<'a, Vec<Result<(), String>>> {
+        Box::pin(async move {
+            debug!("Sending {} events to Elasticsearch bulk API", envelopes.len());
+            let client = match &self.client {
+                Ok(client) => client,
+                Err(e) => return envelopes.iter().map(|_| Err(e.clone())).collect()
+            };
+            let response = check_response(client
+                .post(format!("{}/_bulk", self.address))
+                .basic_auth(&self.user, Some(&self.pass))
+                .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+                .body(self.get_bulk_body(envelopes))
+                .send()
+                .await).await;
+            match response {
+                Ok(text) => get_bulk_results(&text, envelopes),
+                Err(e) => envelopes.iter().map(|_| Err(e.clone())).collect()
+            }
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Sends events to the Splunk HTTP event collector. With indexer
+/// acknowledgement enabled, events always go through the spool and a batch
+/// is only delivered once Splunk confirms it was indexed.
+pub struct SplunkSink {
+    client: Result<Client, String>,
+    address: String,
+    token: String,
+    ack: bool,
+    channel: String,
+    ack_timeout: Duration,
+    cfg: AppConfig
+}
+
+impl SplunkSink {
+    pub fn new(cfg: &AppConfig) -> Self {
+        SplunkSink {
+            client: get_client(cfg),
+            address: cfg.endpoint_address.clone(),
+            token: cfg.endpoint_token.clone(),
+            ack: cfg.splunk_ack,
+            channel: cfg.splunk_channel.clone(),
+            ack_timeout: Duration::from_secs(cfg.splunk_ack_timeout),
+            cfg: cfg.clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Collector event, metadata set in the event path overrides the global one.
+    pub fn get_body(&self, envelope: &Envelope) -> Value {
+        let path = envelope.path.as_deref().map(|path| self.cfg.get_path_splunk(path)).unwrap_or_default();
+        json!({
+            "source": envelope.source.clone().or(path.source).unwrap_or(self.cfg.splunk_source.clone()),
+            "sourcetype": path.sourcetype.unwrap_or(self.cfg.splunk_sourcetype.clone()),
+            "event": formatter::format_value(&self.cfg.format_network, envelope, &self.cfg),
+            "index": path.index.unwrap_or(self.cfg.splunk_index.clone())
+        })
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn post(&self, endpoint: &str) -> Result<RequestBuilder, String> {
+        Ok(self.client.as_ref()?
+            .post(format!("{}/services/collector/{}", self.address, endpoint))
+            .header("Authorization", format!("Splunk {}", self.token))
+            .header("X-Splunk-Request-Channel", &self.channel))
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Poll the acknowledgement endpoint until the request is indexed
+    async fn wait_ack(&self, ack_id: u64) -> Result<(), String> {
+        let start = Instant::now();
+        while start.elapsed() < self.ack_timeout {
+            tokio::time::sleep(ACK_INTERVAL).await;
+            let text = check_response(self.post("ack")?
+                .json(&json!({ "acks": [ack_id] }))
+                .send()
+                .await).await?;
+            let body: Value = serde_json::from_str(&text).unwrap_or_default();
+            if body["acks"][ack_id.to_string()] == Value::Bool(true) {
+                debug!("Splunk request {} acknowledged", ack_id);
+                return Ok(());
+            }
+        }
+        Err(format!("request {} not acknowledged after {} seconds", ack_id, self.ack_timeout.as_secs()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Result of each event from a collector response and the acknowledgement id
+/// of the request. When an event of the batch is invalid the collector stops
+/// there, previous events were indexed, the invalid one is logged and dropped
+/// and the next ones are retried.
+pub fn get_hec_results(status: u16, response: &str, envelopes: &[Envelope]) -> (Vec<Result<(), String>>, Option<u64>) {
+    let body: Value = serde_json::from_str(response).unwrap_or_default();
+    if (200..300).contains(&status) {
+        return (envelopes.iter().map(|_| Ok(())).collect(), body["ackId"].as_u64());
+    }
+    let reason = body["text"].as_str().unwrap_or(response);
+    let results = match body["invalid-event-number"].as_u64() {
+        Some(invalid) => envelopes.iter().enumerate().map(|(i, envelope)| {
+            match (i as u64).cmp(&invalid) {
+                Ordering::Less => Ok(()),
+                Ordering::Equal => {
+                    error!("Event '{}' rejected by Splunk, {}", envelope.id, reason);
+                    Ok(())
+                },
+                Ordering::Greater => Err(format!("previous event rejected, {}", reason))
+            }
+        }).collect(),
+        None => envelopes.iter().map(|_| Err(format!("collector status {}, {}", status, reason))).collect()
+    };
+    (results, None)
+}
+
+impl OutputSink for SplunkSink {
+    fn name(&self) -> &str { "splunk" }
+
+    fn mode(&self) -> &str { appconfig::NETWORK_MODE }
+
+    fn spooled(&self) -> bool { self.ack }
+
+    // Events are sent one per line
+    fn size(&self, envelope: &Envelope) -> usize { self.get_body(envelope).to_string().len() + 1 }
+
+    fn write<'a>(&'a self, envelope: &'a Envelope) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            self.write_batch(std::slice::from_ref(envelope)).await.remove(0)
+        })
+    }
+
+    fn write_batch<'a>(&'a self, envelopes: &'a [Envelope]) -> BoxFuture<'a, Vec<Result<(), String>>> {
+        Box::pin(async move {
+            let body: Vec<String> = envelopes.iter().map(|envelope| self.get_body(envelope).to_string()).collect();
+            debug!("Sending {} events to Splunk HTTP event collector", envelopes.len());
+            let request = match self.post("event") {
+                Ok(request) => request,
+                Err(e) => return envelopes.iter().map(|_| Err(e.clone())).collect()
+            };
+            let response = match request.body(body.join("\n")).send().await {
+                Ok(response) => response,
+                Err(e) => return envelopes.iter().map(|_| Err(format!("error on request, {}", e))).collect()
+            };
+            let status = response.status().as_u16();
+            let text = response.text().await.unwrap_or_default();
+            debug!("Response received: {:?}", text);
+            let (mut results, ack_id) = get_hec_results(status, &text, envelopes);
+            if self.ack && (200..300).contains(&status) {
+                let acked = match ack_id {
+                    Some(ack_id) => self.wait_ack(ack_id).await,
+                    None => Err(String::from("ackId missing in response, enable indexer acknowledgement for the token"))
+                };
+                if let Err(e) = acked {
+                    results.iter_mut().for_each(|result| *result = Err(e.clone()));
+                }
+            }
+            results
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Set of configured sinks, events are written to the ones of the requested
+/// destination mode. Network events are batched and sent when the batch is
+/// full or, by the spooler thread, once the batch interval is over.
+pub struct Router {
+    sinks: Vec<Box<dyn OutputSink>>,
+    spool: Spool,
+    batch: Arc<Mutex<Batch>>,
+    max_events: usize,
+    max_bytes: usize,
+    interval: Duration
+}
+
+impl Router {
+    pub fn new(cfg: &AppConfig) -> Self {
+        // Sinks keep the configuration without its router, which would keep itself alive
+        let cfg = &AppConfig { router: Arc::new(Mutex::new(None)), ..cfg.clone() };
+        let network: Box<dyn OutputSink> = match cfg.endpoint_type.as_str() {
+            "Splunk" => Box::new(SplunkSink::new(cfg)),
+            _ => Box::new(ElasticSink::new(cfg))
+        };
+        let mut sinks: Vec<Box<dyn OutputSink>> = vec![Box::new(FileSink::new(cfg)), network];
+        if cfg.syslog_address != "Not_defined" {
+            sinks.push(Box::new(SyslogSink::new(cfg)));
+        }
+        for webhook in cfg.webhooks.iter() {
+            sinks.push(Box::new(WebhookSink::new(cfg, webhook)));
+        }
+        Router {
+            sinks,
+            spool: Spool::new(cfg),
+            batch: cfg.batch_lock.clone(),
+            max_events: cfg.batch_max_events,
+            max_bytes: cfg.batch_max_bytes,
+            interval: Duration::from_secs(cfg.batch_interval)
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Sinks used for a destination mode, both means file and network
+    fn select<'a>(&'a self, destination: &'a str) -> impl Iterator<Item = &'a dyn OutputSink> {
+        self.sinks.iter().map(|sink| sink.as_ref()).filter(move |sink| match destination {
+            appconfig::BOTH_MODE => sink.mode() == appconfig::FILE_MODE || sink.mode() == appconfig::NETWORK_MODE,
+            _ => sink.mode() == destination
+        })
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Network sink with the given name or the current one
+    fn network(&self, name: &str) -> Option<&dyn OutputSink> {
+        let network = self.sinks.iter().map(|sink| sink.as_ref())
+            .filter(|sink| sink.mode() == appconfig::NETWORK_MODE);
+        network.clone().find(|sink| sink.name() == name).or(network.clone().next())
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn store(&self, sink: &str, envelope: &Envelope) {
+        if let Err(e) = self.spool.push(sink, envelope) {
+            error!("Event '{}' lost, {}", envelope.id, e);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub async fn route(&self, envelope: &Envelope, destination: &str) {
+        for sink in self.select(destination).chain(self.select(appconfig::WEBHOOK_MODE)) {
+            if !sink.accepts(envelope) { continue; }
+            debug!("Writing event '{}' to {} sink", envelope.id, sink.name());
+            if sink.mode() != appconfig::NETWORK_MODE {
+                match sink.write(envelope).await {
+                    Err(e) if sink.retried() => {
+                        debug!("Event '{}' not delivered to {} sink, {}", envelope.id, sink.name(), e);
+                        self.store(sink.name(), envelope);
+                    },
+                    Err(e) => error!("Could not write event '{}' to {} sink, error: {}", envelope.id, sink.name(), e),
+                    Ok(_) => ()
+                }
+                continue;
+            }
+            // Keep delivery order, new events wait behind the spooled ones
+            if sink.spooled() || !self.spool.is_empty() {
+                debug!("Event '{}' queued in spool", envelope.id);
+                self.store(sink.name(), envelope);
+                continue;
+            }
+            let full = {
+                let mut batch = self.batch.lock().unwrap();
+                batch.push(envelope, sink.size(envelope));
+                batch.len() >= self.max_events || batch.bytes >= self.max_bytes
+            };
+            if full { self.flush().await; }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Send the batched events, the ones not delivered go to the spool.
+    pub async fn flush(&self) {
+        let envelopes = self.batch.lock().unwrap().take();
+        if envelopes.is_empty() { return; }
+        let sink = match self.network("") {
+            Some(sink) => sink,
+            None => return
+        };
+        let results = sink.write_batch(&envelopes).await;
+        for (envelope, result) in envelopes.iter().zip(results) {
+            if let Err(e) = result {
+                debug!("Event '{}' not delivered to {} sink, {}", envelope.id, sink.name(), e);
+                self.store(sink.name(), envelope);
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Send the batched events once the batch interval is over.
+    pub async fn flush_due(&self) {
+        let due = self.batch.lock().unwrap().age().map(|age| age >= self.interval).unwrap_or(false);
+        if due { self.flush().await; }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Move the batched events to the spool without sending them, used on exit.
+    pub fn save(&self) {
+        let mut batch = self.batch.lock().unwrap();
+        if batch.is_empty() { return; }
+        let envelopes = batch.take();
+        if let Some(sink) = self.network("") {
+            for envelope in envelopes.iter() { self.store(sink.name(), envelope); }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Write events to a network or retried sink, used to deliver spooled
+    /// events. Events of a sink no longer configured go to the current
+    /// network one.
+    pub async fn send(&self, name: &str, envelopes: &[Envelope]) -> Vec<Result<(), String>> {
+        let retried = self.sinks.iter().map(|sink| sink.as_ref()).find(|sink| sink.retried() && sink.name() == name);
+        match retried.or(self.network(name)) {
+            Some(sink) => sink.write_batch(envelopes).await,
+            None => envelopes.iter().map(|_| Err(String::from("no network sink configured"))).collect()
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Writes an event to the sinks of the given destination mode, with the
+/// router of the configuration.
+pub async fn route(envelope: &Envelope, destination: &str, cfg: &AppConfig) {
+    cfg.get_router().route(envelope, destination).await;
+}
@@ -0,0 +1,368 @@
+use super::*;
+use std::fs;
+use tokio_test::block_on;
+
+use crate::utils;
+
+// ----------------------------------------------------------------------------
+
+fn create_test_envelope() -> Envelope {
+    Envelope::new("Test_id", json!({ "id": "Test_id", "node": "FIM" }))
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the envelope keeps the id, the source and the event fields
+fn test_envelope() {
+    let envelope = create_test_envelope();
+    assert_eq!(envelope.id, "Test_id");
+    assert_eq!(envelope.source, None);
+    assert_eq!(envelope.to_json(), "{\"id\":\"Test_id\",\"node\":\"FIM\"}");
+    assert_eq!(envelope.with_source("FIM_RULESET").source, Some(String::from("FIM_RULESET")));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the file sink appends one JSON line per event
+fn test_file_sink() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_output.yml")).unwrap();
+    let sink = FileSink::new(&cfg);
+    block_on(sink.write(&create_test_envelope())).unwrap();
+    block_on(sink.write(&create_test_envelope())).unwrap();
+    let contents = fs::read_to_string("test_output.json").unwrap();
+    assert_eq!(contents, "{\"id\":\"Test_id\",\"node\":\"FIM\"}\n".repeat(2));
+    fs::remove_file("test_output.json").unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check each sink writes events in the format of its destination
+fn test_sink_format() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_format.yml")).unwrap();
+    let envelope = create_test_envelope().with_kind("monitor");
+    block_on(FileSink::new(&cfg).write(&envelope)).unwrap();
+    let contents = fs::read_to_string("test_format.log").unwrap();
+    assert_eq!(contents, format!("{}\n", formatter::to_cef(&envelope)));
+    fs::remove_file("test_format.log").unwrap();
+
+    let sink = ElasticSink::new(&cfg);
+    assert_eq!(sink.get_document(&envelope), json!({ "message": formatter::to_leef(&envelope) }));
+    let sink = SplunkSink::new(&cfg);
+    assert_eq!(sink.get_body(&envelope)["event"], formatter::to_leef(&envelope));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the document url uses the daily index and the event id
+fn test_elastic_sink() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    let sink = ElasticSink::new(&cfg);
+    assert_eq!(sink.get_url(&create_test_envelope()),
+        format!("{}/{}/_doc/Test_id", cfg.endpoint_address, sink.get_index(&create_test_envelope())));
+    assert_eq!(sink.get_index(&create_test_envelope()), get_index_name(DEFAULT_INDEX, OffsetDateTime::now_utc()));
+    assert_eq!(sink.get_document(&create_test_envelope()), create_test_envelope().data);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check nothing is sent when the endpoint TLS settings can't be applied
+fn test_get_client_invalid_tls() {
+    let mut cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    cfg.insecure = false;
+    cfg.endpoint_ca_file = Some(String::from("test/unit/tls/missing.pem"));
+    assert!(get_client(&cfg).unwrap_err().starts_with("endpoint TLS settings not applied"));
+    assert!(cfg.client.get().is_none());
+
+    let sink = ElasticSink::new(&cfg);
+    let results = block_on(sink.write_batch(&[create_test_envelope()]));
+    assert!(results[0].as_ref().unwrap_err().starts_with("endpoint TLS settings not applied"));
+    assert!(block_on(sink.write(&create_test_envelope())).is_err());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check data streams are written with the create operation and a timestamp
+fn test_elastic_sink_data_stream() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_elastic.yml")).unwrap();
+    let sink = ElasticSink::new(&cfg);
+    assert_eq!(sink.get_url(&create_test_envelope()),
+        format!("{}/{}/_create/Test_id", cfg.endpoint_address, sink.get_index(&create_test_envelope())));
+    assert!(sink.get_bulk_body(&[create_test_envelope()]).starts_with("{\"create\":"));
+
+    let envelope = Envelope::new("Test_id", json!({ "timestamp": "1699063400250" }));
+    assert_eq!(sink.get_document(&envelope)["@timestamp"], "2023-11-04T02:03:20.250Z");
+    let envelope = Envelope::new("Db_id", json!({ "dbfile.timestamp": "1699063400250" }));
+    assert_eq!(sink.get_document(&envelope)["@timestamp"], "2023-11-04T02:03:20.250Z");
+    assert!(sink.get_document(&create_test_envelope())["@timestamp"].is_string());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check date tokens are replaced by zero padded values
+fn test_get_index_name() {
+    let date = OffsetDateTime::from_unix_timestamp(1678150800).unwrap();
+    assert_eq!(get_index_name(DEFAULT_INDEX, date), "fim-2023-03-07");
+    assert_eq!(get_index_name("fim-%y.%m.%d-%H", date), "fim-23.03.07-01");
+    assert_eq!(get_index_name("fim-100%%", date), "fim-100%");
+    assert_eq!(get_index_name("fim-events", date), "fim-events");
+    assert_eq!(get_index_pattern(DEFAULT_INDEX), "fim-*");
+    assert_eq!(get_index_pattern("fim-events"), "fim-events");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check events are indexed by the day they happened, not the day they are sent
+fn test_get_index_event_time() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    let sink = ElasticSink::new(&cfg);
+    let envelope = Envelope::new("Test_id", json!({ "timestamp": "1699063400250" }));
+    assert_eq!(get_event_time(&envelope), Some(OffsetDateTime::from_unix_timestamp_nanos(1699063400250000000).unwrap()));
+    assert_eq!(sink.get_index(&envelope), "fim-2023-11-04");
+    assert!(sink.get_bulk_lines(&envelope).starts_with("{\"index\":{\"_id\":\"Test_id\",\"_index\":\"fim-2023-11-04\"}}"));
+    let envelope = Envelope::new("Db_id", json!({ "dbfile.timestamp": "1699063400250" }));
+    assert_eq!(sink.get_index(&envelope), "fim-2023-11-04");
+    assert_eq!(get_event_time(&create_test_envelope()), None);
+    assert_eq!(sink.get_index(&create_test_envelope()), get_index_name(DEFAULT_INDEX, OffsetDateTime::now_utc()));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check index patterns produce valid index names
+fn test_check_index_pattern() {
+    assert!(check_index_pattern(DEFAULT_INDEX).is_ok());
+    assert!(check_index_pattern("logs-fim-default").is_ok());
+    assert!(check_index_pattern("fim-%Y-%j").is_err());
+    assert!(check_index_pattern("fim-%").is_err());
+    assert!(check_index_pattern("Fim").is_err());
+    assert!(check_index_pattern("fim events").is_err());
+    assert!(check_index_pattern("_fim").is_err());
+    assert!(check_index_pattern("").is_err());
+    assert!(has_date_tokens(DEFAULT_INDEX));
+    assert!(!has_date_tokens(DEFAULT_DATA_STREAM));
+    assert!(!has_date_tokens("fim-100%%"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the collector body wraps the event and uses the node as default source
+fn test_splunk_sink() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+    let sink = SplunkSink::new(&cfg);
+    let body = sink.get_body(&create_test_envelope());
+    assert_eq!(body["source"], "FIM");
+    assert_eq!(body["sourcetype"], "_json");
+    assert_eq!(body["index"], "fim_events");
+    assert_eq!(body["event"]["id"], "Test_id");
+    let body = sink.get_body(&create_test_envelope().with_source("FIM_RULESET"));
+    assert_eq!(body["source"], "FIM_RULESET");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the router is built once per loaded configuration
+fn test_get_router() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    let router = cfg.get_router();
+    assert!(Arc::ptr_eq(&router, &cfg.clone().get_router()));
+    let reloaded = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    assert!(!Arc::ptr_eq(&router, &reloaded.get_router()));
+    // The sinks don't hold the router of the configuration
+    assert_eq!(Arc::strong_count(&cfg.router), 1);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check sinks are selected by destination mode and endpoint type
+fn test_router_select() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    let router = Router::new(&cfg);
+    let names = |destination| router.select(destination).map(|s| s.name()).collect::<Vec<&str>>();
+    assert_eq!(names(appconfig::FILE_MODE), vec!["file"]);
+    assert_eq!(names(appconfig::NETWORK_MODE), vec!["elastic"]);
+    assert_eq!(names(appconfig::BOTH_MODE), vec!["file", "elastic"]);
+
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+    let router = Router::new(&cfg);
+    assert_eq!(router.select(appconfig::NETWORK_MODE).map(|s| s.name()).collect::<Vec<&str>>(), vec!["splunk"]);
+    assert_eq!(router.select(appconfig::SYSLOG_MODE).count(), 0);
+
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog.yml")).unwrap();
+    let router = Router::new(&cfg);
+    let names = |destination| router.select(destination).map(|s| s.name()).collect::<Vec<&str>>();
+    assert_eq!(names(appconfig::SYSLOG_MODE), vec!["syslog"]);
+    assert_eq!(names(appconfig::BOTH_MODE), vec!["file", "elastic"]);
+    assert_eq!(router.select(appconfig::WEBHOOK_MODE).count(), 0);
+
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_webhook.yml")).unwrap();
+    let router = Router::new(&cfg);
+    let names = |destination| router.select(destination).map(|s| s.name()).collect::<Vec<&str>>();
+    assert_eq!(names(appconfig::WEBHOOK_MODE), vec!["chatops", "webhook1"]);
+    assert_eq!(names(appconfig::FILE_MODE), vec!["file"]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the batch counts events and bytes until it is taken
+fn test_batch() {
+    let mut batch = Batch::default();
+    assert!(batch.is_empty());
+    assert_eq!(batch.age(), None);
+    batch.push(&create_test_envelope(), 10);
+    batch.push(&create_test_envelope(), 20);
+    assert_eq!(batch.len(), 2);
+    assert_eq!(batch.bytes, 30);
+    assert!(batch.age().is_some());
+    assert_eq!(batch.take(), vec![create_test_envelope(), create_test_envelope()]);
+    assert!(batch.is_empty());
+    assert_eq!(batch.bytes, 0);
+    assert_eq!(batch.age(), None);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the bulk body has an index action and the document for each event
+fn test_get_bulk_body() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    let sink = ElasticSink::new(&cfg);
+    let action = format!("{{\"index\":{{\"_id\":\"Test_id\",\"_index\":\"{}\"}}}}", sink.get_index(&create_test_envelope()));
+    let line = format!("{}\n{{\"id\":\"Test_id\",\"node\":\"FIM\"}}\n", action);
+    assert_eq!(sink.get_bulk_lines(&create_test_envelope()), line);
+    assert_eq!(sink.size(&create_test_envelope()), line.len());
+    assert_eq!(sink.get_bulk_body(&[create_test_envelope(), create_test_envelope()]), line.repeat(2));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check per item results, only throttled or failed items are retried
+fn test_get_bulk_results() {
+    let envelopes = vec![create_test_envelope(), create_test_envelope(), create_test_envelope()];
+    let results = get_bulk_results("{\"errors\":false,\"items\":[]}", &envelopes);
+    assert!(results.iter().all(|r| r.is_ok()));
+
+    let response = json!({ "errors": true, "items": [
+        { "index": { "_id": "1", "status": 201 } },
+        { "index": { "_id": "2", "status": 429, "error": { "reason": "rejected execution" } } },
+        { "index": { "_id": "3", "status": 400, "error": { "reason": "mapper_parsing_exception" } } }
+    ]}).to_string();
+    let results = get_bulk_results(&response, &envelopes);
+    assert_eq!(results[0], Ok(()));
+    assert_eq!(results[1], Err(String::from("bulk item status 429, rejected execution")));
+    assert_eq!(results[2], Ok(()));
+
+    let results = get_bulk_results("{\"errors\":true,\"items\":[]}", &envelopes[..1]);
+    assert_eq!(results[0], Err(String::from("missing bulk response item")));
+    assert!(get_bulk_results("<html>", &envelopes[..1])[0].is_err());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check network events wait in the batch and are spooled on exit
+fn test_router_batch() {
+    let mut cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_push_template.yml")).unwrap();
+    cfg.spool_file = String::from("test_output_batch.db");
+    let _ = fs::remove_file(&cfg.spool_file);
+    let router = Router::new(&cfg);
+    block_on(router.route(&create_test_envelope(), appconfig::NETWORK_MODE));
+    block_on(router.route(&create_test_envelope(), appconfig::NETWORK_MODE));
+    assert_eq!(cfg.batch_lock.lock().unwrap().len(), 2);
+    assert_eq!(cfg.batch_lock.lock().unwrap().bytes, 2 * ElasticSink::new(&cfg).size(&create_test_envelope()));
+    assert!(router.spool.is_empty());
+
+    block_on(router.flush_due());
+    assert_eq!(cfg.batch_lock.lock().unwrap().len(), 2);
+    router.save();
+    assert!(cfg.batch_lock.lock().unwrap().is_empty());
+    assert_eq!(router.spool.len(), 2);
+    fs::remove_file("test_output_batch.db").unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check events a retried sink fails to write are spooled under its name
+fn test_router_retried() {
+    let mut cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog.yml")).unwrap();
+    cfg.syslog_address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    cfg.spool_file = String::from("test_output_retried.db");
+    let _ = fs::remove_file(&cfg.spool_file);
+    let router = Router::new(&cfg);
+    block_on(router.route(&create_test_envelope(), appconfig::SYSLOG_MODE));
+    let entries = router.spool.peek(10);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].sink, "syslog");
+    assert!(block_on(router.send("syslog", &[create_test_envelope()]))[0].as_ref().unwrap_err()
+        .starts_with(&format!("cannot connect to '{}'", cfg.syslog_address)));
+    fs::remove_file("test_output_retried.db").unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the collector metadata set in the event path overrides the global one
+fn test_splunk_sink_path() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_splunk.yml")).unwrap();
+    let sink = SplunkSink::new(&cfg);
+    let body = sink.get_body(&create_test_envelope().with_path("/etc/passwd"));
+    assert_eq!(body["index"], "fim_etc");
+    assert_eq!(body["source"], "etc");
+    assert_eq!(body["sourcetype"], "fim:event");
+    let body = sink.get_body(&create_test_envelope().with_path("/test/file"));
+    assert_eq!(body["index"], "fim");
+    assert_eq!(body["source"], "FIM");
+    let body = sink.get_body(&create_test_envelope().with_path("/etc/passwd").with_source("FIM_RULESET"));
+    assert_eq!(body["source"], "FIM_RULESET");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check per event results of collector responses
+fn test_get_hec_results() {
+    let envelopes = vec![create_test_envelope(), create_test_envelope(), create_test_envelope()];
+    let (results, ack_id) = get_hec_results(200, "{\"text\":\"Success\",\"code\":0,\"ackId\":7}", &envelopes);
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(ack_id, Some(7));
+
+    let response = "{\"text\":\"Invalid data format\",\"code\":6,\"invalid-event-number\":1}";
+    let (results, ack_id) = get_hec_results(400, response, &envelopes);
+    assert_eq!(results[0], Ok(()));
+    assert_eq!(results[1], Ok(()));
+    assert_eq!(results[2], Err(String::from("previous event rejected, Invalid data format")));
+    assert_eq!(ack_id, None);
+
+    let (results, _) = get_hec_results(503, "{\"text\":\"Server is busy\",\"code\":9}", &envelopes);
+    assert!(results.iter().all(|r| r == &Err(String::from("collector status 503, Server is busy"))));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check events wait in the spool until acknowledged when indexer acknowledgement is enabled
+fn test_router_ack() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_splunk.yml")).unwrap();
+    let _ = fs::remove_file(&cfg.spool_file);
+    let router = Router::new(&cfg);
+    block_on(router.route(&create_test_envelope(), appconfig::NETWORK_MODE));
+    assert!(cfg.batch_lock.lock().unwrap().is_empty());
+    assert_eq!(router.spool.len(), 1);
+
+    let results = block_on(router.send("splunk", &[create_test_envelope()]));
+    assert!(results[0].is_err());
+    fs::remove_file("test_splunk.db").unwrap();
+}
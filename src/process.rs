@@ -0,0 +1,50 @@
+// Copyright (C) 2026, Achiefs.
+
+// To describe the process that made a change
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+#[cfg(test)]
+mod test;
+
+// ----------------------------------------------------------------------------
+
+/// Process that made a change, fields are strings as in audit events.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Process {
+    pub pid: String,
+    pub exe: String,
+    pub comm: String,
+    pub uid: String
+}
+
+impl Process {
+    /// Reads the process from /proc, the fields of a process already gone
+    /// are empty except its pid.
+    pub fn new(pid: u32) -> Self {
+        let proc = format!("/proc/{}", pid);
+        let exe = fs::read_link(format!("{}/exe", proc))
+            .map(|exe| String::from(exe.to_str().unwrap_or_default())).unwrap_or_default();
+        let comm = fs::read_to_string(format!("{}/comm", proc)).unwrap_or_default();
+        // Real user id, the first of the Uid line
+        let uid = fs::read_to_string(format!("{}/status", proc)).unwrap_or_default().lines()
+            .find_map(|line| line.strip_prefix("Uid:"))
+            .and_then(|ids| ids.split_whitespace().next().map(String::from))
+            .unwrap_or_default();
+        Process { pid: pid.to_string(), exe, comm: String::from(comm.trim_end()), uid }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Text kept in the info attribute of a notify event.
+    pub fn to_info(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Process carried by a notify event, None for watchers without it.
+    pub fn from_event(event: &notify::Event) -> Option<Self> {
+        event.info().and_then(|info| serde_json::from_str(info).ok())
+    }
+}
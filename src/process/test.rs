@@ -0,0 +1,38 @@
+use super::*;
+use notify::event::EventKind;
+
+// ----------------------------------------------------------------------------
+
+#[cfg(target_os = "linux")]
+#[test]
+/// Check the current process is read from /proc
+fn test_new() {
+    let process = Process::new(std::process::id());
+    assert_eq!(process.pid, std::process::id().to_string());
+    assert_eq!(process.exe, std::env::current_exe().unwrap().to_str().unwrap());
+    assert!(!process.comm.is_empty() && !process.comm.ends_with('\n'));
+    let status = fs::read_to_string("/proc/self/status").unwrap();
+    assert!(status.lines().any(|line| line.starts_with("Uid:") && line.contains(&process.uid)));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a process already gone keeps its pid only
+fn test_new_gone() {
+    let process = Process::new(u32::MAX);
+    assert_eq!(process, Process { pid: u32::MAX.to_string(), ..Default::default() });
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the process travels in the notify event info
+fn test_from_event() {
+    let process = Process { pid: String::from("10"), exe: String::from("/usr/bin/vim"),
+        comm: String::from("vim"), uid: String::from("1000") };
+    let event = notify::Event::new(EventKind::Any).set_info(&process.to_info());
+    assert_eq!(Process::from_event(&event), Some(process));
+    assert_eq!(Process::from_event(&notify::Event::new(EventKind::Any)), None);
+    assert_eq!(Process::from_event(&notify::Event::new(EventKind::Any).set_info("other")), None);
+}
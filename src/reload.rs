@@ -0,0 +1,402 @@
+// Copyright (C) 2026, Achiefs.
+
+// Fake event path used to request a reload to the monitor loop
+pub const RELOAD_PATH: &str = "RELOAD";
+
+// To log the program process
+use log::{info, error, debug, warn};
+// To share the active audit rules with the signal handler
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::fs;
+use std::path::{Path, PathBuf};
+use notify::RecursiveMode;
+use itertools::Itertools;
+use notify::event::{Event, EventKind, EventAttributes};
+
+use crate::appconfig;
+use crate::appconfig::AppConfig;
+use crate::configfile::PathConfig;
+use crate::ruleset::Ruleset;
+use crate::appevent::AppEvent;
+use crate::multiwatcher::MultiWatcher;
+use crate::output::Router;
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// ----------------------------------------------------------------------------
+
+/// Entries added, removed or modified between two versions of a path list,
+/// paths are compared by their `path` attribute.
+#[derive(Default, Debug, PartialEq)]
+pub struct PathChanges {
+    pub added: Vec<PathConfig>,
+    pub removed: Vec<PathConfig>,
+    pub modified: Vec<(PathConfig, PathConfig)>
+}
+
+/// Rule ids added, removed or modified between two rulesets.
+#[derive(Default, Debug, PartialEq)]
+pub struct RuleChanges {
+    pub added: Vec<usize>,
+    pub removed: Vec<usize>,
+    pub modified: Vec<usize>
+}
+
+// ----------------------------------------------------------------------------
+
+impl PathChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn describe(&self, section: &str) -> Vec<String> {
+        let paths = |list: Vec<&PathConfig>| list.iter()
+            .map(|p| format!("'{}'", p.path)).collect::<Vec<String>>().join(", ");
+        let mut description = Vec::new();
+        if !self.added.is_empty() {
+            description.push(format!("{} added: {}", section, paths(self.added.iter().collect())));
+        }
+        if !self.removed.is_empty() {
+            description.push(format!("{} removed: {}", section, paths(self.removed.iter().collect())));
+        }
+        if !self.modified.is_empty() {
+            description.push(format!("{} modified: {}", section, paths(self.modified.iter().map(|m| &m.1).collect())));
+        }
+        description
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl RuleChanges {
+    fn describe(&self) -> Vec<String> {
+        let ids = |list: &Vec<usize>| list.iter()
+            .map(|id| id.to_string()).collect::<Vec<String>>().join(", ");
+        let mut description = Vec::new();
+        if !self.added.is_empty() { description.push(format!("rules added: {}", ids(&self.added))); }
+        if !self.removed.is_empty() { description.push(format!("rules removed: {}", ids(&self.removed))); }
+        if !self.modified.is_empty() { description.push(format!("rules modified: {}", ids(&self.modified))); }
+        description
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+pub fn diff_paths(old: &[PathConfig], new: &[PathConfig]) -> PathChanges {
+    let mut changes = PathChanges::default();
+    for element in new {
+        match old.iter().find(|o| o.path == element.path) {
+            Some(previous) => if previous != element {
+                changes.modified.push((previous.clone(), element.clone()));
+            },
+            None => changes.added.push(element.clone())
+        }
+    }
+    changes.removed = old.iter()
+        .filter(|o| !new.iter().any(|n| n.path == o.path))
+        .cloned().collect();
+    changes
+}
+
+// ----------------------------------------------------------------------------
+
+/// Roots to stop and to start watching. Pattern entries may share a root,
+/// which stays watched while any entry resolves to it.
+pub fn diff_roots(old: &[PathConfig], new: &[PathConfig]) -> (Vec<String>, Vec<String>) {
+    let old_roots: Vec<String> = old.iter().map(|element| element.root()).unique().collect();
+    let new_roots: Vec<String> = new.iter().map(|element| element.root()).unique().collect();
+    let removed = old_roots.iter().filter(|root| !new_roots.contains(root)).cloned().collect();
+    let added = new_roots.iter().filter(|root| !old_roots.contains(root)).cloned().collect();
+    (removed, added)
+}
+
+// ----------------------------------------------------------------------------
+
+/// Root and permissions of an auditctl watch.
+pub type AuditWatch = (String, String);
+
+/// Auditctl watches of the audit entries, one for each root with the
+/// permissions of every entry that resolves to it.
+pub fn get_audit_watches(audit: &[PathConfig]) -> Vec<AuditWatch> {
+    let roots: Vec<String> = audit.iter().map(|element| element.root()).unique().collect();
+    roots.into_iter().map(|root| {
+        let permissions: String = audit.iter().filter(|element| element.root() == root)
+            .map(|element| utils::get_audit_rule_permissions(element.rule.as_deref())).collect();
+        let rule = "rwax".chars().filter(|c| permissions.contains(*c)).collect();
+        (root, rule)
+    }).collect()
+}
+
+// ----------------------------------------------------------------------------
+
+/// Auditctl watches to remove and to add. A root still used keeps its watch
+/// unless the combined permissions of its entries changed, then it is added
+/// again with the new ones.
+pub fn diff_audit(old: &[PathConfig], new: &[PathConfig]) -> (Vec<AuditWatch>, Vec<AuditWatch>) {
+    let old_watches = get_audit_watches(old);
+    let new_watches = get_audit_watches(new);
+    let removed = old_watches.iter().filter(|watch| !new_watches.contains(watch)).cloned().collect();
+    let added = new_watches.iter().filter(|watch| !old_watches.contains(watch)).cloned().collect();
+    (removed, added)
+}
+
+// ----------------------------------------------------------------------------
+
+pub fn diff_rules(old: &Ruleset, new: &Ruleset) -> RuleChanges {
+    let mut changes = RuleChanges::default();
+    for (id, rule) in &new.rules {
+        match old.rules.get(id) {
+            Some(previous) => if previous != rule { changes.modified.push(*id) },
+            None => changes.added.push(*id)
+        }
+    }
+    changes.removed = old.rules.keys().filter(|id| !new.rules.contains_key(id)).copied().collect();
+    changes.added.sort();
+    changes.removed.sort();
+    changes.modified.sort();
+    changes
+}
+
+// ----------------------------------------------------------------------------
+
+/// Settings read once at start by the logger or other threads cannot be
+/// reloaded, their current value is kept and the changed keys are returned.
+pub fn keep_settings(old: &AppConfig, new: &mut AppConfig) -> Vec<&'static str> {
+    let mut kept = Vec::new();
+    if old.events_watcher != new.events_watcher { kept.push("events.watcher"); }
+    if old.poll_interval != new.poll_interval || old.poll_compare_contents != new.poll_compare_contents { kept.push("events.poll"); }
+    if old.events_watch_limit != new.events_watch_limit { kept.push("events.watch_limit"); }
+    if old.events_file != new.events_file { kept.push("events.file"); }
+    if old.log_file != new.log_file { kept.push("log.file"); }
+    if old.log_level != new.log_level { kept.push("log.level"); }
+    if old.log_max_file_size != new.log_max_file_size { kept.push("log.max_file_size"); }
+    if old.hashscanner_file != new.hashscanner_file { kept.push("hashscanner.file"); }
+    if old.hashscanner_enabled != new.hashscanner_enabled { kept.push("hashscanner.enabled"); }
+    if old.hashscanner_interval != new.hashscanner_interval { kept.push("hashscanner.interval"); }
+    if old.hashscanner_algorithm != new.hashscanner_algorithm { kept.push("hashscanner.algorithm"); }
+    if old.spool_file != new.spool_file || old.spool_max_backoff != new.spool_max_backoff { kept.push("events.spool"); }
+    if old.batch_interval != new.batch_interval { kept.push("events.batch.interval"); }
+    if old.chain_enabled != new.chain_enabled || old.chain_key != new.chain_key { kept.push("events.chain"); }
+    if old.engine != new.engine { kept.push("engine"); }
+
+    new.events_watcher = old.events_watcher.clone();
+    new.poll_interval = old.poll_interval;
+    new.poll_compare_contents = old.poll_compare_contents;
+    new.events_watch_limit = old.events_watch_limit.clone();
+    new.events_file = old.events_file.clone();
+    new.log_file = old.log_file.clone();
+    new.log_level = old.log_level.clone();
+    new.log_max_file_size = old.log_max_file_size;
+    new.hashscanner_file = old.hashscanner_file.clone();
+    new.hashscanner_enabled = old.hashscanner_enabled;
+    new.hashscanner_interval = old.hashscanner_interval;
+    new.hashscanner_algorithm = old.hashscanner_algorithm.clone();
+    // The spooler thread keeps the spool it was started with
+    new.spool_file = old.spool_file.clone();
+    new.spool_lock = old.spool_lock.clone();
+    new.spool_max_backoff = old.spool_max_backoff;
+    new.batch_interval = old.batch_interval;
+    // A new key would break the chain of the current events file
+    new.chain_enabled = old.chain_enabled;
+    new.chain_key = old.chain_key.clone();
+    // The monitor loop reads the events of the engine it was started with
+    new.engine = old.engine.clone();
+    // Locks are shared with the rotator thread
+    new.events_lock = old.events_lock.clone();
+    new.log_lock = old.log_lock.clone();
+    // Batched events are sent by the spooler thread
+    new.batch_lock = old.batch_lock.clone();
+    // Reopened by the syslog sink when the collector settings change
+    new.syslog_lock = old.syslog_lock.clone();
+    // Shared with the threads started with the current configuration
+    new.router = old.router.clone();
+    kept
+}
+
+// ----------------------------------------------------------------------------
+
+pub fn reload_event() -> Event {
+    Event {
+        paths: vec![PathBuf::from(RELOAD_PATH)],
+        kind: EventKind::Any,
+        attrs: EventAttributes::new()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Watch the given configuration files and request a reload when any of them
+/// is written, or when a YAML file changes inside the given folders. Parent
+/// folders are watched so editors replacing the file are detected too.
+pub fn watch_files(files: Vec<String>, dirs: Vec<PathBuf>, cfg: AppConfig,
+    tx: mpsc::Sender<Result<notify::Event, notify::Error>>) {
+    match thread::Builder::new().name("FIM_Reloader".to_string()).spawn(move || {
+        let (file_tx, file_rx) = mpsc::channel();
+        let mut watcher = MultiWatcher::new(&cfg, file_tx);
+        // Absolute paths to match the ones reported by the watcher
+        let files: Vec<PathBuf> = files.iter()
+            .map(|f| fs::canonicalize(f).unwrap_or(PathBuf::from(f))).collect();
+        let dirs: Vec<PathBuf> = dirs.iter().filter_map(|d| fs::canonicalize(d).ok()).collect();
+        let parents = files.iter().map(|f| f.parent().unwrap_or(Path::new(".")));
+        for folder in parents.chain(dirs.iter().map(|d| d.as_path())) {
+            match watcher.watch(folder, RecursiveMode::NonRecursive) {
+                Ok(_d) => debug!("Watching '{:?}' to reload on changes.", folder),
+                Err(e) => warn!("Could not watch '{:?}' for changes, description: {}", folder, e)
+            }
+        }
+        let in_dirs = |path: &PathBuf| path.extension().is_some_and(|e| e == "yml") &&
+            path.parent().is_some_and(|p| dirs.iter().any(|d| d == p));
+        for message in &file_rx {
+            match message {
+                Ok(event) => {
+                    let written = matches!(event.kind, EventKind::Create(_)|EventKind::Modify(_)|EventKind::Remove(_));
+                    if written && event.paths.iter().any(|p| files.contains(p) || in_dirs(p)) {
+                        debug!("Configuration file changed: {:?}", event.paths);
+                        if tx.send(Ok(reload_event())).is_err() { break; }
+                    }
+                },
+                Err(e) => error!("Watch for configuration changes failed, error: {:?}", e)
+            }
+        }
+    }){
+        Ok(_v) => info!("FIM reloader thread started."),
+        Err(e) => error!("Could not start FIM reloader thread, error: {}", e)
+    };
+}
+
+// ----------------------------------------------------------------------------
+
+/// Request a reload on SIGHUP. On SIGINT or SIGTERM the active audit rules
+/// are removed and batched network events are spooled before exit.
+#[cfg(unix)]
+pub fn handle_signals(tx: mpsc::Sender<Result<notify::Event, notify::Error>>,
+    audit_rules: Arc<Mutex<Vec<PathConfig>>>, cfg: AppConfig) {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = match Signals::new([SIGHUP, SIGINT, SIGTERM]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            error!("Error setting signals handler, the process will continue without signal handling, Error: '{}'", e);
+            return;
+        }
+    };
+    match thread::Builder::new().name("FIM_Signals".to_string()).spawn(move || {
+        for signal in signals.forever() {
+            if signal == SIGHUP {
+                info!("SIGHUP received, reloading configuration.");
+                if tx.send(Ok(reload_event())).is_err() { break; }
+            }else{
+                clean_audit_rules(&audit_rules.lock().unwrap());
+                // The router slot is shared across reloads, it holds the live sinks
+                let router = cfg.router.lock().unwrap().clone();
+                if let Some(router) = router {
+                    router.save();
+                }
+                std::process::exit(0);
+            }
+        }
+    }){
+        Ok(_v) => debug!("Signals handler set and listening"),
+        Err(e) => error!("Could not start signals handler thread, error: {}", e)
+    };
+}
+
+// ----------------------------------------------------------------------------
+
+pub fn clean_audit_rules(audit: &[PathConfig]) {
+    for (root, rule) in get_audit_watches(audit) {
+        utils::run_auditctl(&["-W", &root, "-k", "fim", "-p", &rule]);
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Load config and rules again and apply the differences: monitored paths are
+/// watched or unwatched, only changed auditctl rules are replaced and an
+/// AppEvent describing the changes is produced. If any file has errors
+/// nothing is applied and None is returned.
+pub async fn reload(cfg: &AppConfig, ruleset: &Ruleset, watcher: &mut MultiWatcher,
+    audit_rules: &Arc<Mutex<Vec<PathConfig>>>) -> Option<(AppConfig, Ruleset)> {
+    let mut new_cfg = match AppConfig::new(&cfg.system, Some(&cfg.path)) {
+        Ok(new_cfg) => new_cfg,
+        Err(e) => {
+            error!("Configuration not reloaded, keeping current one. {}", e);
+            return None;
+        }
+    };
+    let new_ruleset = match Ruleset::new(&cfg.system, Some(&ruleset.path))
+        .and_then(|mut new_ruleset| new_ruleset.merge(&new_cfg.rules).map(|_| new_ruleset)) {
+        Ok(new_ruleset) => new_ruleset,
+        Err(e) => {
+            error!("Ruleset not reloaded, keeping current one. {}", e);
+            return None;
+        }
+    };
+
+    let kept = keep_settings(cfg, &mut new_cfg);
+    if !kept.is_empty() {
+        warn!("Changes in '{}' require a restart to be applied.", kept.join(", "));
+    }
+    // Sinks are built again with the new configuration
+    *new_cfg.router.lock().unwrap() = Some(Arc::new(Router::new(&new_cfg)));
+
+    let monitor = diff_paths(&cfg.monitor, &new_cfg.monitor);
+    let (removed, added) = diff_roots(&cfg.monitor, &new_cfg.monitor);
+    for root in &removed {
+        match watcher.unwatch(Path::new(root)) {
+            Ok(_d) => info!("Stopped monitoring path: {}", root),
+            Err(e) => warn!("Could not stop monitoring path '{}', description: {}", root, e)
+        }
+    }
+    for root in &added {
+        match watcher.watch(Path::new(root), RecursiveMode::Recursive) {
+            Ok(_d) => info!("Monitoring path: {}", root),
+            Err(e) => warn!("[Monitor] Could not monitor given path '{}', description: {}", root, e)
+        }
+    }
+
+    let audit = diff_paths(&cfg.audit, &new_cfg.audit);
+    if !audit.is_empty() && utils::get_os() == "linux" && utils::check_auditd() {
+        // Only replace the watches of roots whose entries or permissions changed
+        let (removed, added) = diff_audit(&cfg.audit, &new_cfg.audit);
+        for (root, rule) in removed {
+            utils::run_auditctl(&["-W", &root, "-k", "fim", "-p", &rule]);
+            info!("Stopped checking audit path: {}", root);
+        }
+        for (root, rule) in added {
+            utils::run_auditctl(&["-w", &root, "-k", "fim", "-p", &rule]);
+            info!("Checking audit path: {}", root);
+        }
+        *audit_rules.lock().unwrap() = new_cfg.audit.clone();
+    }
+
+    let rules = diff_rules(ruleset, &new_ruleset);
+    let mut description = monitor.describe("monitor");
+    description.extend(audit.describe("audit"));
+    description.extend(rules.describe());
+
+    if description.is_empty() {
+        info!("Configuration reloaded, no changes found.");
+    }else{
+        let message = format!("Configuration reloaded, {}", description.join("; "));
+        info!("{}", message);
+        let appevent = AppEvent {
+            id: utils::get_uuid(),
+            timestamp: utils::get_current_time_millis(),
+            hostname: utils::get_hostname(),
+            node: new_cfg.node.clone(),
+            version: String::from(appconfig::VERSION),
+            message,
+            fpid: utils::get_pid(),
+            system: new_cfg.system.clone()
+        };
+        appevent.process(new_cfg.clone(), new_ruleset.clone()).await;
+    }
+    Some((new_cfg, new_ruleset))
+}
@@ -0,0 +1,223 @@
+use super::*;
+use std::collections::HashMap;
+use tokio_test::block_on;
+
+// ----------------------------------------------------------------------------
+
+fn path_config(path: &str, labels: Vec<&str>) -> PathConfig {
+    PathConfig {
+        path: String::from(path),
+        labels: labels.iter().map(|l| String::from(*l)).collect(),
+        ignore: None,
+        exclude: None,
+        allowed: None,
+        match_mode: Default::default(),
+        checksum_algorithm: None,
+        max_file_checksum: None,
+        debounce: None,
+        destination: None,
+        splunk: None,
+        rule: None,
+        integrations: Vec::new(),
+        matchers: Default::default()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn ruleset(rules: Vec<(usize, &str)>) -> Ruleset {
+    let mut map = HashMap::new();
+    for (id, rule) in rules {
+        map.insert(id, HashMap::from([(String::from("rule"), String::from(rule))]));
+    }
+    Ruleset { rules: map, path: String::from("rules.yml") }
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check added, removed and modified paths are detected by path
+fn test_diff_paths() {
+    let old = vec![path_config("/etc", vec![]), path_config("/bin", vec![]), path_config("/usr", vec![])];
+    let new = vec![path_config("/etc", vec![]), path_config("/usr", vec!["usr"]), path_config("/tmp", vec![])];
+    let changes = diff_paths(&old, &new);
+    assert_eq!(changes.added, vec![path_config("/tmp", vec![])]);
+    assert_eq!(changes.removed, vec![path_config("/bin", vec![])]);
+    assert_eq!(changes.modified, vec![(path_config("/usr", vec![]), path_config("/usr", vec!["usr"]))]);
+    assert!(!changes.is_empty());
+    assert!(diff_paths(&old, &old).is_empty());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a root shared by pattern entries is kept while any entry uses it
+fn test_diff_roots() {
+    let old = vec![path_config("/etc/*.conf", vec![]), path_config("/etc/*.d", vec![]), path_config("/bin", vec![])];
+    let new = vec![path_config("/etc/*.conf", vec![]), path_config("/usr/*.so", vec![]), path_config("/usr/lib", vec![])];
+    assert_eq!(diff_roots(&old, &new), (vec![String::from("/bin")], vec![String::from("/usr"), String::from("/usr/lib")]));
+    assert_eq!(diff_roots(&old, &old), (Vec::new(), Vec::new()));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check entries sharing a root share one watch with their permissions combined
+fn test_diff_audit() {
+    let mut conf = path_config("/etc/*.conf", vec![]);
+    conf.rule = Some(String::from("wa"));
+    let mut all = path_config("/etc/**", vec![]);
+    all.rule = Some(String::from("r"));
+    let bin = path_config("/bin", vec![]);
+    assert_eq!(get_audit_watches(&[conf.clone(), all.clone(), bin.clone()]),
+        vec![(String::from("/etc"), String::from("rwa")), (String::from("/bin"), String::from("wax"))]);
+
+    // Removing one of the entries re-adds the root with the permissions left
+    let old = vec![conf.clone(), all.clone(), bin.clone()];
+    let new = vec![all.clone(), bin.clone()];
+    assert_eq!(diff_audit(&old, &new), (vec![(String::from("/etc"), String::from("rwa"))],
+        vec![(String::from("/etc"), String::from("r"))]));
+
+    // The root stays untouched while the permissions don't change
+    let mut read = conf.clone();
+    read.rule = Some(String::from("r"));
+    assert_eq!(diff_audit(&[all.clone(), bin.clone()], &[all.clone(), read, bin.clone()]), (Vec::new(), Vec::new()));
+
+    // It is removed when no entry uses it anymore
+    assert_eq!(diff_audit(&old, &[bin]), (vec![(String::from("/etc"), String::from("rwa"))], Vec::new()));
+    assert_eq!(diff_audit(&old, &old), (Vec::new(), Vec::new()));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check added, removed and modified rules are detected by id
+fn test_diff_rules() {
+    let old = ruleset(vec![(1, "\\.sh$"), (2, "\\.php$"), (3, "\\.py$")]);
+    let new = ruleset(vec![(1, "\\.sh$"), (3, "\\.pyc$"), (5, "\\.exe$")]);
+    let changes = diff_rules(&old, &new);
+    assert_eq!(changes.added, vec![5]);
+    assert_eq!(changes.removed, vec![2]);
+    assert_eq!(changes.modified, vec![3]);
+    assert_eq!(diff_rules(&old, &old), RuleChanges::default());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check changes are described in a single line per kind
+fn test_describe() {
+    let old = vec![path_config("/etc", vec![]), path_config("/bin", vec![])];
+    let new = vec![path_config("/bin", vec!["bin"]), path_config("/tmp", vec![])];
+    assert_eq!(diff_paths(&old, &new).describe("monitor"), vec![
+        String::from("monitor added: '/tmp'"),
+        String::from("monitor removed: '/etc'"),
+        String::from("monitor modified: '/bin'")
+    ]);
+    let changes = diff_rules(&ruleset(vec![(1, "a")]), &ruleset(vec![(2, "b"), (3, "c")]));
+    assert_eq!(changes.describe(), vec![
+        String::from("rules added: 2, 3"),
+        String::from("rules removed: 1")
+    ]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check settings that need a restart keep their current value
+fn test_keep_settings() {
+    let old = AppConfig::new(utils::get_os(), None).unwrap();
+    let mut new = old.clone();
+    new.log_file = String::from("/tmp/other.log");
+    new.hashscanner_interval = 1;
+    new.node = String::from("other");
+    new.engine = String::from("audit");
+    new.events_lock = Arc::new(Mutex::new(String::new()));
+    new.spool_lock = Arc::new(Mutex::new(None));
+    new.router = Arc::new(Mutex::new(None));
+
+    assert_eq!(keep_settings(&old, &mut new), vec!["log.file", "hashscanner.interval", "engine"]);
+    assert_eq!(new.log_file, old.log_file);
+    assert_eq!(new.engine, "monitor");
+    assert_eq!(new.hashscanner_interval, old.hashscanner_interval);
+    assert_eq!(new.node, "other");
+    assert!(Arc::ptr_eq(&new.events_lock, &old.events_lock));
+    assert!(Arc::ptr_eq(&new.spool_lock, &old.spool_lock));
+    assert!(Arc::ptr_eq(&new.router, &old.router));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the reload request event
+fn test_reload_event() {
+    let event = reload_event();
+    assert_eq!(event.paths, vec![PathBuf::from(RELOAD_PATH)]);
+    assert_eq!(event.kind, EventKind::Any);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a reload without changes keeps the same configuration and ruleset
+fn test_reload() {
+    let cfg = AppConfig::new(utils::get_os(), None).unwrap();
+    let ruleset = Ruleset::new(utils::get_os(), None).unwrap();
+    let (tx, _rx) = mpsc::channel();
+    let mut watcher = MultiWatcher::new(&cfg, tx);
+    let audit_rules = Arc::new(Mutex::new(Vec::new()));
+
+    let (new_cfg, new_ruleset) = block_on(reload(&cfg, &ruleset, &mut watcher, &audit_rules)).unwrap();
+    assert_eq!(new_cfg.monitor, cfg.monitor);
+    assert_eq!(new_ruleset.rules, ruleset.rules);
+    assert!(audit_rules.lock().unwrap().is_empty());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check nothing is applied when the files contain errors
+fn test_reload_errors() {
+    let mut cfg = AppConfig::new(utils::get_os(), None).unwrap();
+    let ruleset = Ruleset::new(utils::get_os(), None).unwrap();
+    let (tx, _rx) = mpsc::channel();
+    let mut watcher = MultiWatcher::new(&cfg, tx);
+    let audit_rules = Arc::new(Mutex::new(Vec::new()));
+
+    cfg.path = String::from("NotFound");
+    assert!(block_on(reload(&cfg, &ruleset, &mut watcher, &audit_rules)).is_none());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check removing one of two entries sharing a root keeps it watched
+fn test_reload_shared_root() {
+    let root = Path::new("./test_reload_shared");
+    let config = "test_reload_shared.yml";
+    let write_config = |entries: &[&str]| {
+        let monitor: String = entries.iter().map(|entry| format!("  - path: ./test_reload_shared/{}\n", entry)).collect();
+        fs::write(config, format!("node: \"FIM\"\nevents:\n  destination: file\n  file: ./test_reload_shared.json\n\
+            monitor:\n{}hashscanner:\n  file: ./test_reload_shared.db\nlog:\n  file: ./test_reload_shared.log\n", monitor)).unwrap();
+    };
+    let _ = fs::remove_dir_all(root);
+    fs::create_dir(root).unwrap();
+    write_config(&["*.log", "*.txt"]);
+    let cfg = AppConfig::new(utils::get_os(), Some(config)).unwrap();
+    let ruleset = Ruleset::new(utils::get_os(), None).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = MultiWatcher::new(&cfg, tx);
+    watcher.watch(Path::new(&cfg.monitor[0].root()), RecursiveMode::Recursive).unwrap();
+    let audit_rules = Arc::new(Mutex::new(Vec::new()));
+
+    write_config(&["*.log"]);
+    let (new_cfg, _) = block_on(reload(&cfg, &ruleset, &mut watcher, &audit_rules)).unwrap();
+    assert_eq!(new_cfg.monitor.len(), 1);
+    fs::write(root.join("file.log"), "test").unwrap();
+    assert!(rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap().is_ok());
+
+    fs::remove_dir_all(root).unwrap();
+    for file in [config, "test_reload_shared.json"] {
+        let _ = fs::remove_file(file);
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (C) 2026, Achiefs.
+
+// To pair the two sides of a rename into a single move
+use notify::Event;
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod test;
+
+// Time a rename source waits for its destination
+pub const WINDOW: Duration = Duration::from_millis(500);
+
+// ----------------------------------------------------------------------------
+
+fn get_mode(event: &Event) -> Option<RenameMode> {
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(mode)) => Some(mode),
+        _ => None
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Event of a rename with both paths, source first.
+pub fn get_move(from: &Event, to: &Event) -> Event {
+    let mut event = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+        .add_some_path(from.paths.first().cloned())
+        .add_some_path(to.paths.first().cloned());
+    if let Some(tracker) = to.tracker().or(from.tracker()) {
+        event = event.set_tracker(tracker);
+    }
+    // Process of the rename, from watchers that report it
+    if let Some(process_id) = to.attrs.process_id() {
+        event = event.set_process_id(process_id);
+    }
+    if let Some(info) = to.info() {
+        event = event.set_info(info);
+    }
+    event
+}
+
+// ----------------------------------------------------------------------------
+
+/// Source and destination of a move event, None for other events.
+pub fn get_paths(event: &Event) -> Option<(&PathBuf, &PathBuf)> {
+    match (get_mode(event), event.paths.as_slice()) {
+        (Some(RenameMode::Both), [from, to]) => Some((from, to)),
+        _ => None
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Pairs the `From` and `To` sides of renames. Inotify links them with a
+/// cookie, kept by notify as the event tracker, and sends a `Both` event
+/// after them, Windows sends both sides one after the other without it.
+/// Sources are held until their destination arrives or `WINDOW` expires,
+/// then they are released as they came, a file moved out of the monitored
+/// paths has no destination. Trackers of matched renames wait the same
+/// window for their `Both` event.
+#[derive(Default)]
+pub struct Renames {
+    pending: Vec<(Event, Instant)>,
+    matched: HashMap<usize, Instant>
+}
+
+impl Renames {
+    pub fn new() -> Self {
+        Renames::default()
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Events to process after receiving `event`, a matched rename gives a
+    /// single `Both` event with the source and destination paths.
+    pub fn correlate(&mut self, event: Event) -> Vec<Event> {
+        match get_mode(&event) {
+            Some(RenameMode::From) => {
+                self.pending.push((event, Instant::now()));
+                Vec::new()
+            },
+            Some(RenameMode::To) => {
+                // Without tracker only the source received just before can be paired
+                let position = match event.tracker() {
+                    Some(tracker) => self.pending.iter().position(|(from, _)| from.tracker() == Some(tracker)),
+                    None => self.pending.last().filter(|(from, _)| from.tracker().is_none())
+                        .map(|_| self.pending.len() - 1)
+                };
+                match position {
+                    Some(position) => {
+                        let (from, _) = self.pending.remove(position);
+                        if let Some(tracker) = event.tracker() {
+                            self.matched.insert(tracker, Instant::now());
+                        }
+                        vec![get_move(&from, &event)]
+                    },
+                    None => vec![event]
+                }
+            },
+            Some(RenameMode::Both) => match event.tracker() {
+                // Already sent when its destination arrived
+                Some(tracker) if self.matched.remove(&tracker).is_some() => Vec::new(),
+                tracker => {
+                    self.pending.retain(|(from, _)| tracker.is_none() || from.tracker() != tracker);
+                    vec![event]
+                }
+            },
+            _ => vec![event]
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Sources whose destination didn't arrive in time. Trackers whose
+    /// `Both` event didn't arrive are forgotten.
+    pub fn expire(&mut self) -> Vec<Event> {
+        let (expired, pending) = self.pending.drain(..)
+            .partition(|(_, received)| received.elapsed() >= WINDOW);
+        self.pending = pending;
+        self.matched.retain(|_, matched| matched.elapsed() < WINDOW);
+        expired.into_iter().map(|(event, _)| event).collect()
+    }
+}
@@ -0,0 +1,80 @@
+use super::*;
+use std::thread;
+
+// ----------------------------------------------------------------------------
+
+fn create_rename_event(mode: RenameMode, path: &str, tracker: Option<usize>) -> Event {
+    let event = Event::new(EventKind::Modify(ModifyKind::Name(mode))).add_path(PathBuf::from(path));
+    match tracker {
+        Some(tracker) => event.set_tracker(tracker),
+        None => event
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check inotify renames give one move, the following Both event is dropped
+fn test_correlate_tracker() {
+    let mut renames = Renames::new();
+    assert!(renames.correlate(create_rename_event(RenameMode::From, "/tmp/a", Some(1))).is_empty());
+    let events = renames.correlate(create_rename_event(RenameMode::To, "/tmp/b", Some(1)));
+    assert_eq!(events.len(), 1);
+    assert_eq!(get_paths(&events[0]), Some((&PathBuf::from("/tmp/a"), &PathBuf::from("/tmp/b"))));
+    let both = get_move(&create_rename_event(RenameMode::From, "/tmp/a", Some(1)),
+        &create_rename_event(RenameMode::To, "/tmp/b", Some(1)));
+    assert!(renames.correlate(both.clone()).is_empty());
+    // Not sent before, as when the source was received by another watcher
+    assert_eq!(renames.correlate(both.clone()), vec![both]);
+    assert!(renames.expire().is_empty());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check sides without tracker are paired when received one after the other
+fn test_correlate_no_tracker() {
+    let mut renames = Renames::new();
+    assert!(renames.correlate(create_rename_event(RenameMode::From, "C:\\a", None)).is_empty());
+    let events = renames.correlate(create_rename_event(RenameMode::To, "C:\\b", None));
+    assert_eq!(get_paths(&events[0]), Some((&PathBuf::from("C:\\a"), &PathBuf::from("C:\\b"))));
+
+    // A tracked source can't be paired without tracker
+    renames.correlate(create_rename_event(RenameMode::From, "/tmp/a", Some(2)));
+    let to = create_rename_event(RenameMode::To, "/tmp/c", None);
+    assert_eq!(renames.correlate(to.clone()), vec![to]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check sources without destination and other events are released as they came
+fn test_expire() {
+    let mut renames = Renames::new();
+    let from = create_rename_event(RenameMode::From, "/tmp/a", Some(3));
+    renames.correlate(from.clone());
+    let create = Event::new(EventKind::Create(notify::event::CreateKind::File)).add_path(PathBuf::from("/tmp/d"));
+    assert_eq!(renames.correlate(create.clone()), vec![create]);
+    assert!(renames.expire().is_empty());
+    thread::sleep(WINDOW);
+    assert_eq!(renames.expire(), vec![from]);
+    assert!(renames.expire().is_empty());
+    let to = create_rename_event(RenameMode::To, "/tmp/b", Some(3));
+    assert_eq!(renames.correlate(to.clone()), vec![to]);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check trackers of matched renames are forgotten once the window is over
+fn test_expire_matched() {
+    let mut renames = Renames::new();
+    renames.correlate(create_rename_event(RenameMode::From, "/tmp/a", Some(4)));
+    renames.correlate(create_rename_event(RenameMode::To, "/tmp/b", Some(4)));
+    assert_eq!(renames.matched.len(), 1);
+    renames.expire();
+    assert_eq!(renames.matched.len(), 1);
+    thread::sleep(WINDOW);
+    assert!(renames.expire().is_empty());
+    assert!(renames.matched.is_empty());
+}
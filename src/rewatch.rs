@@ -0,0 +1,115 @@
+// Copyright (C) 2026, Achiefs.
+
+// To notice the monitored roots that are gone and come back
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// Time between checks of the monitored roots
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+// ----------------------------------------------------------------------------
+
+// Identity of a folder, it changes when the folder is replaced by another one
+#[cfg(unix)]
+fn get_id(root: &str) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(root).ok().map(|metadata| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn get_id(root: &str) -> Option<(u64, u64)> {
+    fs::metadata(root).ok().map(|_| (0, 0))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Change of a monitored root, `Back` carries the time since it wasn't
+/// monitored, in milliseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Lost(String),
+    Back(String, String)
+}
+
+// ----------------------------------------------------------------------------
+
+// Last folder seen at a root and, while missing, since when
+#[derive(Debug)]
+struct Root {
+    id: Option<(u64, u64)>,
+    since: Option<String>
+}
+
+// ----------------------------------------------------------------------------
+
+/// Checks the monitored roots still are the folders watched. A root removed
+/// or renamed is lost until a folder is found at its path again, a root
+/// replaced between two checks, as a deploy does, is lost and back at once.
+pub struct Roots {
+    roots: HashMap<String, Root>,
+    checked: Instant,
+    timestamp: String
+}
+
+impl Roots {
+    /// Roots to check, the missing ones are reported when they appear.
+    pub fn new(roots: &[String]) -> Self {
+        let mut state = Roots { roots: HashMap::new(), checked: Instant::now(), timestamp: utils::get_current_time_millis() };
+        state.update(roots);
+        state
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Changes since the last check, nothing until `INTERVAL` passes. The
+    /// roots given replace the previous ones, as after a reload.
+    pub fn check(&mut self, roots: &[String]) -> Vec<Change> {
+        match self.checked.elapsed() >= INTERVAL {
+            true => {
+                self.checked = Instant::now();
+                self.update(roots)
+            },
+            false => Vec::new()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Compare the folder at each root with the last one seen
+    fn update(&mut self, roots: &[String]) -> Vec<Change> {
+        let now = utils::get_current_time_millis();
+        self.roots.retain(|root, _| roots.contains(root));
+        let mut changes = Vec::new();
+        for root in roots {
+            let id = get_id(root);
+            let state = match self.roots.get_mut(root) {
+                Some(state) => state,
+                None => {
+                    self.roots.insert(root.clone(), Root { id, since: id.map_or(Some(now.clone()), |_| None) });
+                    continue;
+                }
+            };
+            match (state.id, id) {
+                (Some(_), None) => {
+                    state.since = Some(now.clone());
+                    changes.push(Change::Lost(root.clone()));
+                },
+                (None, Some(_)) => changes.push(Change::Back(root.clone(), state.since.take().unwrap_or(now.clone()))),
+                (Some(old), Some(new)) if old != new => {
+                    changes.push(Change::Lost(root.clone()));
+                    changes.push(Change::Back(root.clone(), self.timestamp.clone()));
+                },
+                _ => ()
+            }
+            state.id = id;
+        }
+        self.timestamp = now;
+        changes
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+
+// ----------------------------------------------------------------------------
+
+fn get_since(change: &Change) -> String {
+    match change {
+        Change::Back(_, since) => since.clone(),
+        Change::Lost(_) => String::new()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a removed root is lost until it is created again
+fn test_update_removed() {
+    let root = String::from("./test_rewatch_removed");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir(&root).unwrap();
+    let roots = vec![root.clone()];
+    let mut state = Roots::new(&roots);
+    assert!(state.update(&roots).is_empty());
+
+    fs::remove_dir(&root).unwrap();
+    assert_eq!(state.update(&roots), vec![Change::Lost(root.clone())]);
+    assert!(state.update(&roots).is_empty());
+    fs::create_dir(&root).unwrap();
+    let changes = state.update(&roots);
+    assert!(matches!(&changes[..], [Change::Back(path, _)] if *path == root));
+    assert!(state.update(&roots).is_empty());
+    fs::remove_dir(&root).unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+/// Check a root replaced between two checks is lost and back at once
+fn test_update_replaced() {
+    let root = String::from("./test_rewatch_replaced");
+    let _ = fs::remove_dir_all(&root);
+    let _ = fs::remove_dir_all("./test_rewatch_replaced_new");
+    fs::create_dir(&root).unwrap();
+    fs::create_dir("./test_rewatch_replaced_new").unwrap();
+    let roots = vec![root.clone()];
+    let mut state = Roots::new(&roots);
+    let checked = state.timestamp.clone();
+
+    fs::remove_dir(&root).unwrap();
+    fs::rename("./test_rewatch_replaced_new", &root).unwrap();
+    let changes = state.update(&roots);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0], Change::Lost(root.clone()));
+    assert_eq!(get_since(&changes[1]), checked);
+    assert!(state.update(&roots).is_empty());
+    fs::remove_dir(&root).unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check missing roots are reported when they appear and removed roots forgotten
+fn test_update_config() {
+    let root = String::from("./test_rewatch_config");
+    let _ = fs::remove_dir_all(&root);
+    let mut state = Roots::new(&[]);
+    let roots = vec![root.clone()];
+    assert!(state.update(&roots).is_empty());
+    fs::create_dir(&root).unwrap();
+    assert_eq!(state.update(&roots).len(), 1);
+    assert!(state.update(&[]).is_empty());
+    fs::remove_dir(&root).unwrap();
+    assert!(state.update(&roots).is_empty());
+    // Checks wait for the interval
+    assert!(state.check(&roots).is_empty());
+}
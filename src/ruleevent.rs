@@ -1,20 +1,18 @@
 // Copyright (C) 2024, Achiefs.
 
 use crate::event;
-use crate::appconfig;
 use crate::appconfig::*;
 use crate::ruleset::*;
+use crate::output;
+use crate::output::Envelope;
 
 use event::Event;
-use log::*;
 use serde_json::{json, to_string};
 use std::path::PathBuf;
-use reqwest::Client;
-use std::fs::OpenOptions;
-use std::time::Duration;
-use std::io::Write;
 
 pub struct RuleEvent {
+    // Unique id of this match, the rule id is only part of the document
+    pub event_id: String,
     pub id: usize,
     pub rule: String,
     pub timestamp: String,
@@ -24,15 +22,15 @@ pub struct RuleEvent {
     pub fpid: u32,
     pub system: String,
     pub message: String,
+    pub severity: u8,
     pub parent_id: String
 }
 
 // ----------------------------------------------------------------------------
 
-impl Event for RuleEvent {
-    // Get formatted string with all required data
-    fn format_json(&self) -> String {
-        let obj = json!({
+impl RuleEvent {
+    fn get_json(&self) -> serde_json::Value {
+        json!({
             "id": self.id.clone(),
             "rule": self.rule.clone(),
             "timestamp": self.timestamp.clone(),
@@ -41,15 +39,23 @@ impl Event for RuleEvent {
             "version": self.version.clone(),
             "system": self.system.clone(),
             "message": self.message.clone(),
+            "severity": self.severity,
             "parent_id": self.parent_id.clone()
-        });
-        to_string(&obj).unwrap()
+        })
     }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Event for RuleEvent {
+    // Get formatted string with all required data
+    fn format_json(&self) -> String { to_string(&self.get_json()).unwrap() }
 
     // ------------------------------------------------------------------------
 
     fn clone(&self) -> Self {
         RuleEvent {
+            event_id: self.event_id.clone(),
             id: self.id,
             rule: self.rule.clone(),
             timestamp: self.timestamp.clone(),
@@ -59,113 +65,24 @@ impl Event for RuleEvent {
             fpid: self.fpid,
             system: self.system.clone(),
             message: self.message.clone(),
+            severity: self.severity,
             parent_id: self.parent_id.clone()
         }
     }
 
     // ------------------------------------------------------------------------
 
-    // Function to write the received events to file
-    fn log(&self, cfg: AppConfig) {
-        let file = cfg.events_lock.lock().unwrap();
-        let mut events_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(file.as_str())
-            .expect("(log) Unable to open events log file.");
-
-        match writeln!(events_file, "{}", self.format_json() ) {
-            Ok(_d) => debug!("Event log written"),
-            Err(e) => error!("Event could not be written, Err: [{}]", e)
-        }
-    }
-
-    // ------------------------------------------------------------------------
-
-    // Function to send events through network
-    async fn send(&self, cfg: AppConfig) {
-        use time::OffsetDateTime;
-        let current_date = OffsetDateTime::now_utc();
-        let index = format!("fim-{}-{}-{}", current_date.year(), current_date.month() as u8, current_date.day() );
-        
-        // Splunk endpoint integration
-        if cfg.endpoint_type == "Splunk" {
-            let data = json!({
-                "source": "FIM_RULESET",
-                "sourcetype": "_json",
-                "event": json!({
-                    "rule": self.rule.clone(),
-                    "timestamp": self.timestamp.clone(),
-                    "hostname": self.hostname.clone(),
-                    "fpid": self.fpid.clone(),
-                    "version": self.version.clone(),
-                    "system": self.system.clone(),
-                    "message": self.message.clone(),
-                    "parent_id": self.parent_id.clone()
-                }),
-                "index": "fim_events"
-            });
-            debug!("Sending received event to Splunk integration, event: {}", data);
-            let request_url = format!("{}/services/collector/event", cfg.endpoint_address);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .header("Authorization", format!("Splunk {}", cfg.endpoint_token))
-                .json(&data)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        // Elastic endpoint integration
-        } else {
-            let data = json!({
-                "rule": self.rule.clone(),
-                "timestamp": self.timestamp.clone(),
-                "hostname": self.hostname.clone(),
-                "fpid": self.fpid.clone(),
-                "version": self.version.clone(),
-                "system": self.system.clone(),
-                "message": self.message.clone(),
-                "parent_id": self.parent_id.clone()
-            });
-            let request_url = format!("{}/{}/_doc/{}", cfg.endpoint_address, index, self.id);
-            let client = Client::builder()
-                .danger_accept_invalid_certs(cfg.insecure)
-                .timeout(Duration::from_secs(30))
-                .build().unwrap();
-            match client
-                .post(request_url)
-                .basic_auth(cfg.endpoint_user, Some(cfg.endpoint_pass))
-                .json(&data)
-                .send()
-                .await {
-                    Ok(response) => debug!("Response received: {:?}",
-                        response.text().await.unwrap()),
-                    Err(e) => debug!("Error on request: {:?}", e)
-            }
-        }
-
+    fn envelope(&self) -> Envelope {
+        Envelope::new(&self.event_id, self.get_json()).with_source("FIM_RULESET").with_kind("rule")
+            .with_path(self.path.to_str().unwrap())
     }
 
     // ------------------------------------------------------------------------
 
     // Function to manage event destination
     async fn process(&self, cfg: AppConfig, _ruleset: Ruleset) {
-        match cfg.get_events_destination().as_str() {
-            appconfig::BOTH_MODE => {
-                self.log(cfg.clone());
-                self.send(cfg).await;
-            },
-            appconfig::NETWORK_MODE => {
-                self.send(cfg).await;
-            },
-            _ => self.log(cfg.clone())
-        }
+        let destination = cfg.get_path_destination(self.path.to_str().unwrap());
+        output::route(&self.envelope(), &destination, &cfg).await;
     }
 
     // ------------------------------------------------------------------------
@@ -202,6 +119,7 @@ mod tests {
 
     fn create_test_event() -> RuleEvent {
         RuleEvent {
+            event_id: "0000-1111-2222".to_string(),
             id: 0,
             rule: "\\.php$".to_string(),
             timestamp: "Timestamp".to_string(),
@@ -211,6 +129,7 @@ mod tests {
             fpid: 0,
             system: "test".to_string(),
             message: "This is a message".to_string(),
+            severity: 7,
             parent_id: "0000".to_string()
         }
     }
@@ -221,6 +140,7 @@ mod tests {
     fn test_clone() {
         let event = create_test_event();
         let cloned = event.clone();
+        assert_eq!(event.event_id, cloned.event_id);
         assert_eq!(event.id, cloned.id);
         assert_eq!(event.timestamp, cloned.timestamp);
         assert_eq!(event.hostname, cloned.hostname);
@@ -250,11 +170,21 @@ mod tests {
 
     // ------------------------------------------------------------------------
 
+    #[test]
+    fn test_envelope() {
+        let envelope = create_test_event().envelope();
+        assert_eq!(envelope.id, "0000-1111-2222");
+        assert_eq!(envelope.data["id"], 0);
+        assert_eq!(envelope.kind, Some(String::from("rule")));
+    }
+
+    // ------------------------------------------------------------------------
+
     #[test]
     fn test_send() {
         let evt = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        block_on( evt.send(cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
@@ -262,8 +192,8 @@ mod tests {
     #[test]
     fn test_send_splunk() {
         let evt = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml"));
-        block_on( evt.send(cfg) );
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+        block_on( output::route(&evt.envelope(), NETWORK_MODE, &cfg) );
     }
 
     // ------------------------------------------------------------------------
@@ -271,8 +201,8 @@ mod tests {
     #[test]
     fn test_process() {
         let event = create_test_event();
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        let ruleset = Ruleset::new(&utils::get_os(), None);  
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap();  
 
         block_on(event.process(cfg, ruleset));
     }
@@ -282,7 +212,7 @@ mod tests {
     #[test]
     fn test_format_json() {
         let expected = "{\"fpid\":0,\"hostname\":\"Hostname\",\"id\":0,\"message\":\"This is a message\",\
-        \"parent_id\":\"0000\",\"rule\":\"\\\\.php$\",\"system\":\"test\",\"timestamp\":\"Timestamp\",\"version\":\"x.x.x\"}";
+        \"parent_id\":\"0000\",\"rule\":\"\\\\.php$\",\"severity\":7,\"system\":\"test\",\"timestamp\":\"Timestamp\",\"version\":\"x.x.x\"}";
         assert_eq!(create_test_event().format_json(), expected);
     }
 
@@ -290,14 +220,14 @@ mod tests {
 
     #[test]
     fn test_log() {
-        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log_ruleevent.yml"));
+        let cfg = AppConfig::new(&utils::get_os(), Some("test/unit/config/common/test_log_ruleevent.yml")).unwrap();
         let filename = String::from("test_ruleevent.json");
         let evt = create_test_event();
 
-        evt.log(cfg.clone());
+        block_on( output::route(&evt.envelope(), FILE_MODE, &cfg) );
         let contents = fs::read_to_string(filename.clone());
         let expected = "{\"fpid\":0,\"hostname\":\"Hostname\",\"id\":0,\"message\":\"This is a message\",\
-        \"parent_id\":\"0000\",\"rule\":\"\\\\.php$\",\"system\":\"test\",\"timestamp\":\"Timestamp\",\"version\":\"x.x.x\"}\n";
+        \"parent_id\":\"0000\",\"rule\":\"\\\\.php$\",\"severity\":7,\"system\":\"test\",\"timestamp\":\"Timestamp\",\"version\":\"x.x.x\"}\n";
         assert_eq!(contents.unwrap(), expected);
         remove_test_file(filename.clone());
     }
@@ -4,10 +4,9 @@
 const RULESET_MACOS_PATH: &str = "/Applications/FileMonitor.app/rules.yml";
 const RULESET_LINUX_PATH: &str = "/etc/fim/rules.yml";
 const RULESET_WINDOWS_PATH: &str = "C:\\Program Files\\File Integrity Monitor\\rules.yml";
+// Severity of rule events when the rule does not set one
+pub const DEFAULT_SEVERITY: u8 = 7;
 
-use yaml_rust::yaml::{Yaml, YamlLoader};
-use std::fs::File;
-use std::io::Read;
 use std::path::Path;
 use std::collections::HashMap;
 use log::{debug, error};
@@ -18,6 +17,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use crate::utils;
 use crate::appconfig;
 use crate::appconfig::*;
+use crate::configfile::*;
 use crate::event;
 use event::Event;
 use crate::ruleevent::RuleEvent;
@@ -27,69 +27,71 @@ use crate::ruleevent::RuleEvent;
 #[derive(Clone)]
 pub struct Ruleset {
     pub rules: HashMap<usize, HashMap<String, String>>,
+    pub path: String
 }
 
 impl Ruleset {
 
     pub fn clone(&self) -> Self {
         Ruleset {
-            rules: self.rules.clone()
+            rules: self.rules.clone(),
+            path: self.path.clone()
         }
     }
 
-    pub fn new(system: &str, path: Option<&str>) -> Self {
+    pub fn new(system: &str, path: Option<&str>) -> Result<Self, ConfigErrors> {
         println!("[INFO] Reading ruleset...");
         let rules_file = match path {
             Some(p) => String::from(p),
             None => get_ruleset_path(system)
         };
         println!("[INFO] Loading rules from: '{}'", rules_file);
-        
-        let yaml = read_ruleset(rules_file.clone());
 
-        // Manage null value on rules
         let mut rules = HashMap::new();
-        if !yaml.is_empty() {
-            let vec_of_rules = match yaml[0]["rules"].as_vec() {
-                Some(value) => value.to_vec(),
-                None => {
-                    println!("[INFO] No rules to load.");
-                    Vec::new()
-                }
-            };
-        
-            let itr = vec_of_rules.iter();
-            itr.for_each(|yml| {
-                let mut map = HashMap::new();
-                match yml["path"].as_str() {
-                    Some(p) => map.insert(String::from("path"), String::from(p)),
-                    None => panic!("[ERROR] Ruleset syntax error, attribute 'path' in rule not defined. \
-                                        Required fields in rule are 'path', 'rule', 'message' and 'id'.")
-                };
-                match yml["rule"].as_str() {
-                    Some(r) => map.insert(String::from("rule"), sanitize(r)),
-                    None => panic!("[ERROR] Ruleset syntax error, attribute 'rule' in rule not defined. \
-                                        Required fields in rule are 'path', 'rule', 'message' and 'id'.")
-                };
-                match yml["message"].as_str() {
-                    Some(m) => map.insert(String::from("message"), String::from(m)),
-                    None => panic!("[ERROR] Ruleset syntax error, attribute 'message' in rule not defined. \
-                                        Required fields in rule are 'path', 'rule', 'message' and 'id'.")
-                };
+        for rule in read_ruleset(rules_file.clone())? {
+            let mut map = HashMap::new();
+            map.insert(String::from("path"), rule.path);
+            map.insert(String::from("rule"), sanitize(&rule.rule));
+            map.insert(String::from("message"), rule.message);
+            map.insert(String::from("severity"), rule.severity.unwrap_or(DEFAULT_SEVERITY).to_string());
+            rules.insert(rule.id, map);
+        }
 
-                let id = match yml["id"].as_i64() {
-                    Some(value) => usize::try_from(value).unwrap(),
-                    None => panic!("[ERROR] Ruleset syntax error, attribute 'id' in rule not defined. \
-                    Required fields in rule are 'path', 'rule', 'message' and 'id'.")
-                };
-                rules.insert(id, map);
-            });
-            println!("[INFO] Ruleset successfully load.");
-        }else{
-            println!("[INFO] Ruleset empty, nothing to do.");
+        match rules.is_empty() {
+            true => println!("[INFO] Ruleset empty, nothing to do."),
+            false => println!("[INFO] Ruleset successfully load.")
         }
+        Ok(Ruleset { rules, path: rules_file })
+    }
+
+    // ------------------------------------------------------------------------
 
-        Ruleset { rules }
+    /// Add the rules defined in configuration files, their ids can't be
+    /// already used in the ruleset file.
+    pub fn merge(&mut self, rules: &[RuleConfig]) -> Result<(), ConfigErrors> {
+        let mut errors = Vec::new();
+        for rule in rules {
+            if self.rules.contains_key(&rule.id) {
+                errors.push(ConfigError {
+                    file: rule.file.clone(),
+                    key: String::from("rules"),
+                    message: format!("duplicated id {}, already defined in '{}'", rule.id, self.path),
+                    line: 0,
+                    column: 0
+                });
+                continue;
+            }
+            let mut map = HashMap::new();
+            map.insert(String::from("path"), rule.path.clone());
+            map.insert(String::from("rule"), sanitize(&rule.rule));
+            map.insert(String::from("message"), rule.message.clone());
+            map.insert(String::from("severity"), rule.severity.unwrap_or(DEFAULT_SEVERITY).to_string());
+            self.rules.insert(rule.id, map);
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigErrors::new(&self.path, errors))
+        }
     }
 
     // ------------------------------------------------------------------------
@@ -134,6 +136,7 @@ impl Ruleset {
                 debug!("Rule with ID: '{}', match event path: '{}'.", id, path);
                 // Send rule event
                 let event = RuleEvent {
+                    event_id: utils::get_uuid(),
                     id,
                     rule,
                     timestamp: format!("{:?}", SystemTime::now().duration_since(UNIX_EPOCH).expect("Time went backwards").as_millis()),
@@ -143,6 +146,8 @@ impl Ruleset {
                     fpid: utils::get_pid(),
                     system: cfg.clone().system,
                     message: self.rules.get(&id).unwrap().get("message").unwrap().clone(),
+                    severity: self.rules.get(&id).unwrap().get("severity")
+                        .and_then(|severity| severity.parse().ok()).unwrap_or(DEFAULT_SEVERITY),
                     parent_id: ruleid
                 };
                 event.process(cfg, self.clone()).await;
@@ -162,16 +167,63 @@ pub fn sanitize(raw_rule: &str) -> String {
 
 // ----------------------------------------------------------------------------
 
-pub fn read_ruleset(path: String) -> Vec<Yaml> {
-    let mut file: File = File::open(path.clone())
-        .unwrap_or_else(|_| panic!("(read_ruleset): Unable to open file '{}'", path));
-    let mut contents: String = String::new();
+/// Check every rule has a unique id, a valid regular expression and a
+/// severity from 0 to 10, `error` builds the error for a given key and message.
+pub fn check_rules(rules: &[RuleConfig], error: impl Fn(&str, &str) -> ConfigError) -> Vec<ConfigError> {
+    let mut errors = Vec::new();
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    for (index, rule) in rules.iter().enumerate() {
+        if let Some(first) = ids.insert(rule.id, index) {
+            errors.push(error(&format!("rules[{}].id", index),
+                &format!("duplicated id {}, already defined in '{}'", rule.id, rules[first].file)));
+        }
+        if let Err(e) = Regex::new(&sanitize(&rule.rule)) {
+            // Keep only the error description, the pattern is already in the file
+            let description = e.to_string();
+            errors.push(error(&format!("rules[{}].rule", index), &format!("invalid regular expression, {}",
+                description.lines().last().unwrap_or_default().trim_start_matches("error: "))));
+        }
+        if rule.severity.is_some_and(|severity| severity > 10) {
+            errors.push(error(&format!("rules[{}].severity", index), "expected a value from 0 to 10"));
+        }
+    }
+    errors
+}
+
+// ----------------------------------------------------------------------------
 
-    file.read_to_string(&mut contents)
-        .unwrap_or_else(|_| panic!("(read_ruleset): Unable to read contents of file '{}'", path));
-    YamlLoader::load_from_str(&contents).unwrap()
+/// Parse the contents of a rules file, checking every rule has a unique id
+/// and a valid regular expression.
+pub fn parse_ruleset(file: &str, contents: &str) -> Result<Vec<RuleConfig>, ConfigErrors> {
+    let mut errors = Vec::new();
+    let (mut root, locations) = load_document(file, contents, &["rules"], &mut errors)?;
+    let value = root.remove("rules").unwrap_or(serde_yaml::Value::Null);
+    let rules: Vec<RuleConfig> = parse_list(value, "rules", &locations, &mut errors).into_iter()
+        .map(|rule| RuleConfig { file: String::from(file), ..rule }).collect();
+
+    errors.extend(check_rules(&rules, |key, message| {
+        let (line, column) = get_location(&locations, key);
+        ConfigError {
+            file: String::new(),
+            key: String::from(key),
+            message: String::from(message),
+            line,
+            column
+        }
+    }));
+
+    match errors.is_empty() {
+        true => Ok(rules),
+        false => Err(ConfigErrors::new(file, errors))
+    }
 }
 
+// ----------------------------------------------------------------------------
+
+/// To read the YAML rules file into its typed representation
+pub fn read_ruleset(path: String) -> Result<Vec<RuleConfig>, ConfigErrors> {
+    parse_ruleset(&path, &read_file(&path)?)
+}
 
 // ----------------------------------------------------------------------------
 
@@ -209,6 +261,18 @@ pub fn get_ruleset_path(system: &str) -> String {
 
 // ----------------------------------------------------------------------------
 
+/// Ruleset used with a configuration file. The service looks for config.yml
+/// and rules.yml in the same places, so the rules next to the given file are
+/// the ones loaded with it.
+pub fn get_config_ruleset_path(system: &str, config_path: Option<&str>) -> String {
+    match config_path.map(|path| Path::new(path).with_file_name("rules.yml")) {
+        Some(path) if path.exists() => path.to_string_lossy().to_string(),
+        _ => get_ruleset_path(system)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -226,6 +290,17 @@ mod tests {
 
     // ------------------------------------------------------------------------
 
+    #[test]
+    fn test_get_config_ruleset_path() {
+        assert_eq!(get_config_ruleset_path(&utils::get_os(), Some("test/unit/config/common/check_rules/config.yml")),
+            "test/unit/config/common/check_rules/rules.yml");
+        assert_eq!(get_config_ruleset_path(&utils::get_os(), Some("test/unit/config/common/test_syslog.yml")),
+            get_ruleset_path(&utils::get_os()));
+        assert_eq!(get_config_ruleset_path(&utils::get_os(), None), get_ruleset_path(&utils::get_os()));
+    }
+
+    // ------------------------------------------------------------------------
+
     #[cfg(target_os = "windows")]
     #[test]
     fn test_get_ruleset_path_windows() {
@@ -238,12 +313,12 @@ mod tests {
 
     #[test]
     fn test_read_ruleset_unix() {
-        let yaml = read_ruleset(String::from("config/linux/rules.yml"));
+        let rules = read_ruleset(String::from("config/linux/rules.yml")).unwrap();
 
-        assert_eq!(yaml[0]["rules"][0]["id"].as_i64().unwrap(), 1);
-        assert_eq!(yaml[0]["rules"][0]["path"].as_str().unwrap(), "/etc");
-        assert_eq!(yaml[0]["rules"][0]["rule"].as_str().unwrap(), "\\.sh$");
-        assert_eq!(yaml[0]["rules"][0]["message"].as_str().unwrap(), "Shell script present in /etc folder.");
+        assert_eq!(rules[0].id, 1);
+        assert_eq!(rules[0].path, "/etc");
+        assert_eq!(rules[0].rule, "\\.sh$");
+        assert_eq!(rules[0].message, "Shell script present in /etc folder.");
     }
 
     // ------------------------------------------------------------------------
@@ -251,28 +326,103 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_read_ruleset_windows() {
-        let yaml = read_ruleset(String::from("config/windows/rules.yml"));
+        let rules = read_ruleset(String::from("config/windows/rules.yml")).unwrap();
 
-        assert_eq!(yaml[0]["rules"][0]["id"].as_i64().unwrap(), 1);
-        assert_eq!(yaml[0]["rules"][0]["path"].as_str().unwrap(), "C:\\");
-        assert_eq!(yaml[0]["rules"][0]["rule"].as_str().unwrap(), "\\.ps1$");
-        assert_eq!(yaml[0]["rules"][0]["message"].as_str().unwrap(), "Powershell script present in root directory.");
+        assert_eq!(rules[0].id, 1);
+        assert_eq!(rules[0].path, "C:\\");
+        assert_eq!(rules[0].rule, "\\.ps1$");
+        assert_eq!(rules[0].message, "Powershell script present in root directory.");
     }
 
     // ------------------------------------------------------------------------
 
     #[test]
-    #[should_panic(expected = "NotFound")]
-    fn test_read_ruleset_panic() {
-        read_ruleset(String::from("NotFound"));
+    fn test_read_ruleset_not_found() {
+        let errors = read_ruleset(String::from("NotFound")).unwrap_err();
+        assert_eq!(errors.errors.len(), 1);
+        assert!(errors.errors[0].message.contains("NotFound"));
     }
 
     // ------------------------------------------------------------------------
 
     #[test]
-    #[should_panic(expected = "ScanError")]
-    fn test_read_ruleset_panic_not_config() {
-        read_ruleset(String::from("README.md"));
+    fn test_read_ruleset_not_config() {
+        assert!(read_ruleset(String::from("README.md")).is_err());
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_parse_ruleset_empty() {
+        assert_eq!(parse_ruleset("rules.yml", "").unwrap(), Vec::new());
+        assert_eq!(parse_ruleset("rules.yml", "rules:\n").unwrap(), Vec::new());
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_parse_ruleset_errors() {
+        let contents = "rules:
+  - id: 1
+    path: /etc
+    rule: '\\.sh$'
+    message: Shell script
+  - id: 1
+    path: /tmp
+    rule: '(\\.sh$'
+    message: Unclosed group
+  - id: 2
+    path: /usr
+    message: Missing rule
+";
+        let errors = parse_ruleset("rules.yml", contents).unwrap_err().errors;
+        assert_eq!(errors.len(), 3);
+
+        assert_eq!(errors[0].key, "rules[2]");
+        assert!(errors[0].message.contains("missing field `rule`"));
+        assert_eq!((errors[0].line, errors[0].column), (10, 5));
+
+        assert_eq!(errors[1].key, "rules[1].id");
+        assert!(errors[1].message.contains("duplicated id 1"));
+        assert_eq!((errors[1].line, errors[1].column), (6, 5));
+
+        assert_eq!(errors[2].key, "rules[1].rule");
+        assert!(errors[2].message.starts_with("invalid regular expression"));
+        assert_eq!((errors[2].line, errors[2].column), (8, 5));
+
+        let contents = "rules:
+  - id: 1
+    path: /var
+    rule: '\\.php$'
+    message: Out of range
+    severity: 11
+";
+        let errors = parse_ruleset("rules.yml", contents).unwrap_err().errors;
+        assert_eq!(errors[0].key, "rules[0].severity");
+        assert_eq!(errors[0].message, "expected a value from 0 to 10");
+        assert_eq!((errors[0].line, errors[0].column), (6, 5));
+    }
+
+    // ------------------------------------------------------------------------
+
+    #[test]
+    fn test_merge() {
+        let mut ruleset = Ruleset::new(utils::get_os(), None).unwrap();
+        let rule = |id: usize| RuleConfig {
+            id,
+            path: String::from("/var"),
+            rule: String::from("\\.php$"),
+            message: String::from("PHP file"),
+            severity: Some(9),
+            file: String::from("conf.d/web.yml")
+        };
+        assert!(ruleset.merge(&[rule(100)]).is_ok());
+        assert_eq!(ruleset.rules.get(&100).unwrap().get("path").unwrap(), "/var");
+        assert_eq!(ruleset.rules.get(&100).unwrap().get("severity").unwrap(), "9");
+
+        let errors = ruleset.merge(&[rule(1)]).unwrap_err().errors;
+        assert_eq!(errors[0].file, "conf.d/web.yml");
+        assert!(errors[0].message.starts_with("duplicated id 1, already defined in"));
     }
 
     // ------------------------------------------------------------------------
@@ -297,8 +447,8 @@ mod tests {
     #[cfg(not(target_os = "windows"))]
     #[test]
     fn test_match_rule_unix() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        let ruleset = Ruleset::new(&utils::get_os(), None); 
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap(); 
 
         let (result, id) = block_on(ruleset.match_rule(cfg.clone(), PathBuf::from("/etc/file.sh"), String::from("0000")));
         assert_eq!(id, 1);
@@ -314,8 +464,8 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_match_rule_windows() {
-        let cfg = AppConfig::new(&utils::get_os(), None);
-        let ruleset = Ruleset::new(&utils::get_os(), None); 
+        let cfg = AppConfig::new(&utils::get_os(), None).unwrap();
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap(); 
 
         let (result, id) = block_on(ruleset.match_rule(cfg.clone(), PathBuf::from("C:\\file.ps1"), String::from("0000")));
         assert_eq!(id, 1);
@@ -331,7 +481,7 @@ mod tests {
     #[cfg(not(target_os = "windows"))]
     #[test]
     fn test_new_unix() {
-        let ruleset = Ruleset::new(&utils::get_os(), None);
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap();
         let element = ruleset.rules.get(&1usize).unwrap();
 
         assert_eq!(element.get("path").unwrap(), "/etc");
@@ -344,7 +494,7 @@ mod tests {
     #[cfg(target_os = "windows")]
     #[test]
     fn test_new_windows() {
-        let ruleset = Ruleset::new(&utils::get_os(), None);
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap();
         let element = ruleset.rules.get(&1usize).unwrap();
         assert_eq!(element.get("path").unwrap(), "C:\\");
         assert_eq!(element.get("rule").unwrap(), "\\.ps1$");
@@ -355,12 +505,13 @@ mod tests {
 
     #[test]
     fn test_clone() {
-        let ruleset = Ruleset::new(&utils::get_os(), None);
+        let ruleset = Ruleset::new(&utils::get_os(), None).unwrap();
         let cloned = ruleset.clone();
         let ruleset_values = ruleset.rules.get(&1usize).unwrap();
         let cloned_values = cloned.rules.get(&1usize).unwrap();
 
         assert_eq!(ruleset.rules.keys().next(), cloned.rules.keys().next());
+        assert_eq!(ruleset.path, cloned.path);
         assert_eq!(ruleset_values, cloned_values);
         assert_eq!(ruleset_values.get("path").unwrap(), cloned_values.get("path").unwrap());
         assert_eq!(ruleset_values.get("rule").unwrap(), cloned_values.get("rule").unwrap());
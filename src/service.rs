@@ -10,6 +10,7 @@ use crate::monitor;
 use crate::rotator;
 use crate::init::init;
 use crate::hashscanner;
+use crate::spool;
 
 use log::{error, info};
 use std::{
@@ -121,6 +122,13 @@ pub fn run_service() -> Result<()> {
     } else {
         info!("FIM HashScanner thread disabled, not running.")
     };
+    // Started in every mode, a reload may switch to network delivery
+    let spooler_cfg = cfg.clone();
+    match thread::Builder::new()
+        .name("FIM_Spooler".to_string()).spawn(|| spool::spooler(spooler_cfg)){
+        Ok(_v) => info!("FIM spooler thread started."),
+        Err(e) => error!("Could not start FIM spooler thread, error: {}", e)
+    };
 
     let rt = Runtime::new().unwrap();
     rt.block_on(monitor::monitor(tx, rx, cfg, ruleset));
@@ -0,0 +1,290 @@
+// Copyright (C) 2026, Achiefs.
+
+// To store pending events across restarts
+use rusqlite::params;
+use log::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::thread;
+
+use crate::appconfig;
+use crate::appconfig::AppConfig;
+use crate::appevent::AppEvent;
+use crate::output::{self, Envelope, Router};
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// Events discarded because the spool was full, reported in backlog events
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+// Seconds between checks of the batch and the spool while nothing is pending
+const IDLE_INTERVAL: u64 = 1;
+// Minimum seconds between two backlog events
+const REPORT_INTERVAL: u64 = 60;
+
+// ----------------------------------------------------------------------------
+
+/// Spool database opened once, with the number of events it holds so
+/// routing an event doesn't query it.
+pub struct Connection {
+    db: rusqlite::Connection,
+    count: usize
+}
+
+/// Network events that could not be delivered, stored in a SQLite table in
+/// arrival order until the spooler thread sends them.
+#[derive(Clone)]
+pub struct Spool {
+    pub path: String,
+    pub max_events: usize,
+    pub overflow: String,
+    pub lock: Arc<Mutex<Option<Connection>>>
+}
+
+/// Event read from the spool with the sink it was addressed to.
+#[derive(Debug, PartialEq)]
+pub struct SpoolEntry {
+    pub id: i64,
+    pub sink: String,
+    pub envelope: Envelope
+}
+
+impl Spool {
+    pub fn new(cfg: &AppConfig) -> Self {
+        Spool {
+            path: cfg.spool_file.clone(),
+            max_events: cfg.spool_max_events,
+            overflow: cfg.spool_overflow.clone(),
+            lock: cfg.spool_lock.clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn open(&self) -> Result<Connection, String> {
+        let db = rusqlite::Connection::open(&self.path)
+            .map_err(|e| format!("cannot open spool '{}', {}", self.path, e))?;
+        db.busy_timeout(Duration::from_secs(5)).map_err(|e| e.to_string())?;
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                sink TEXT NOT NULL,
+                envelope TEXT NOT NULL)",
+            ()).map_err(|e| format!("cannot create spool table, {}", e))?;
+        let count: i64 = db.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))
+            .map_err(|e| format!("cannot read spool size, {}", e))?;
+        Ok(Connection { db, count: count as usize })
+    }
+
+    // ------------------------------------------------------------------------
+
+    // Run with the connection of the spool, opened on first use
+    fn with<T>(&self, f: impl FnOnce(&mut Connection) -> Result<T, String>) -> Result<T, String> {
+        let mut connection = self.lock.lock().unwrap();
+        if connection.is_none() {
+            *connection = Some(self.open()?);
+        }
+        f(connection.as_mut().unwrap())
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Number of events waiting to be delivered.
+    pub fn len(&self) -> usize {
+        // Nothing was spooled if the database doesn't exist, it isn't created
+        // while the spooler thread idles
+        if self.lock.lock().unwrap().is_none() && !Path::new(&self.path).exists() {
+            return 0;
+        }
+        match self.with(|connection| Ok(connection.count)) {
+            Ok(count) => count,
+            Err(e) => {
+                error!("Could not read spool size, error: {}", e);
+                0
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    // ------------------------------------------------------------------------
+
+    /// Store an event, applying the overflow policy when the spool is full.
+    pub fn push(&self, sink: &str, envelope: &Envelope) -> Result<(), String> {
+        self.with(|connection| {
+            if connection.count >= self.max_events {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+                if self.overflow == "drop_newest" {
+                    warn!("Spool full, event '{}' dropped", envelope.id);
+                    return Ok(());
+                }
+                warn!("Spool full, dropping the oldest event");
+                connection.count -= connection.db.execute("DELETE FROM events WHERE id = (SELECT MIN(id) FROM events)", ())
+                    .map_err(|e| e.to_string())?;
+            }
+            let data = serde_json::to_string(envelope).map_err(|e| e.to_string())?;
+            connection.db.execute("INSERT INTO events (sink, envelope) VALUES (?1, ?2)", params![sink, data])
+                .map_err(|e| format!("cannot store event in spool, {}", e))?;
+            connection.count += 1;
+            debug!("Event '{}' stored in spool", envelope.id);
+            Ok(())
+        })
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Oldest events of the spool.
+    pub fn peek(&self, limit: usize) -> Vec<SpoolEntry> {
+        let read = |connection: &mut Connection| -> Result<Vec<SpoolEntry>, String> {
+            let rows: Vec<(i64, String, String)> = {
+                let mut statement = connection.db.prepare("SELECT id, sink, envelope FROM events ORDER BY id LIMIT ?1")
+                    .map_err(|e| e.to_string())?;
+                let rows = statement.query_map([limit as i64], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+                }).map_err(|e| e.to_string())?;
+                rows.flatten().collect()
+            };
+            let mut entries = Vec::new();
+            for (id, sink, data) in rows {
+                match serde_json::from_str(&data) {
+                    Ok(envelope) => entries.push(SpoolEntry { id, sink, envelope }),
+                    Err(e) => {
+                        error!("Discarding corrupted spool entry {}, error: {}", id, e);
+                        connection.count -= connection.db.execute("DELETE FROM events WHERE id = ?1", [id])
+                            .map_err(|e| e.to_string())?;
+                    }
+                }
+            }
+            Ok(entries)
+        };
+        self.with(read).unwrap_or_else(|e| {
+            error!("Could not read spool, error: {}", e);
+            Vec::new()
+        })
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Remove a delivered event.
+    pub fn remove(&self, id: i64) {
+        let removed = self.with(|connection| {
+            connection.count -= connection.db.execute("DELETE FROM events WHERE id = ?1", [id])
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        });
+        if let Err(e) = removed {
+            error!("Could not remove event {} from spool, error: {}", id, e);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Seconds to wait after a number of consecutive failed deliveries.
+pub fn get_backoff(failures: u32, max_backoff: u64) -> u64 {
+    2u64.saturating_pow(failures.min(32)).min(max_backoff)
+}
+
+// ----------------------------------------------------------------------------
+
+/// Events discarded since start because the spool was full.
+pub fn get_dropped() -> usize { DROPPED.load(Ordering::Relaxed) }
+
+// ----------------------------------------------------------------------------
+
+/// Send the oldest spooled events in order, grouped by sink, stops at the
+/// first group with failures. Returns the number of delivered events or the
+/// delivery error.
+pub async fn deliver(spool: &Spool, router: &Router, limit: usize) -> Result<usize, String> {
+    let mut delivered = 0;
+    let entries = spool.peek(limit);
+    for group in entries.chunk_by(|a, b| a.sink == b.sink) {
+        let envelopes: Vec<Envelope> = group.iter().map(|entry| entry.envelope.clone()).collect();
+        let results = router.send(&group[0].sink, &envelopes).await;
+        let mut error = None;
+        for (entry, result) in group.iter().zip(results) {
+            match result {
+                Ok(_) => {
+                    spool.remove(entry.id);
+                    delivered += 1;
+                },
+                Err(e) => { error.get_or_insert(e); }
+            }
+        }
+        if let Some(e) = error { return Err(e); }
+    }
+    Ok(delivered)
+}
+
+// ----------------------------------------------------------------------------
+
+async fn report(cfg: &AppConfig, message: String) {
+    info!("{}", message);
+    let appevent = AppEvent {
+        id: utils::get_uuid(),
+        timestamp: utils::get_current_time_millis(),
+        hostname: utils::get_hostname(),
+        node: cfg.node.clone(),
+        version: String::from(appconfig::VERSION),
+        message,
+        fpid: utils::get_pid(),
+        system: cfg.system.clone()
+    };
+    output::route(&appevent.envelope(), &cfg.get_events_destination(), cfg).await;
+}
+
+// ----------------------------------------------------------------------------
+
+/// Spooler thread, sends batched events once the batch interval is over and
+/// retries spooled events with exponential backoff, reporting the backlog
+/// while the endpoint is unreachable.
+pub fn spooler(cfg: AppConfig) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let spool = Spool::new(&cfg);
+    let mut failures: u32 = 0;
+    let mut last_report: Option<Instant> = None;
+    let mut reported = (0, 0);
+
+    loop {
+        // Reloads replace the router, the spool stays the same
+        let router = cfg.get_router();
+        rt.block_on(router.flush_due());
+        let pending = spool.len();
+        if pending == 0 {
+            if last_report.is_some() {
+                rt.block_on(report(&cfg, String::from("Network delivery recovered, spooled events sent")));
+                last_report = None;
+                reported = (0, 0);
+            }
+            failures = 0;
+            thread::sleep(Duration::from_secs(IDLE_INTERVAL));
+            continue;
+        }
+
+        match rt.block_on(deliver(&spool, &router, cfg.batch_max_events)) {
+            Ok(delivered) => {
+                debug!("Delivered {} spooled events", delivered);
+                failures = 0;
+            },
+            Err(e) => {
+                failures += 1;
+                let backoff = get_backoff(failures, cfg.spool_max_backoff);
+                warn!("Could not deliver spooled events, retrying in {} seconds, error: {}", backoff, e);
+                let backlog = (spool.len(), get_dropped());
+                let due = last_report.map(|t| t.elapsed().as_secs() >= REPORT_INTERVAL).unwrap_or(true);
+                if due && backlog != reported {
+                    rt.block_on(report(&cfg, format!("Network delivery failing, {} events pending in spool, \
+                        {} events dropped", backlog.0, backlog.1)));
+                    last_report = Some(Instant::now());
+                    reported = backlog;
+                }
+                thread::sleep(Duration::from_secs(backoff));
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use super::*;
+use serde_json::json;
+use std::fs;
+use tokio_test::block_on;
+
+// ----------------------------------------------------------------------------
+
+fn create_test_spool(path: &str, max_events: usize, overflow: &str) -> Spool {
+    let _ = fs::remove_file(path);
+    Spool {
+        path: String::from(path),
+        max_events,
+        overflow: String::from(overflow),
+        lock: Arc::new(Mutex::new(None))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn create_test_envelope(id: &str) -> Envelope {
+    Envelope::new(id, json!({ "id": id }))
+}
+
+// ----------------------------------------------------------------------------
+
+fn create_test_config(spool_file: &str) -> AppConfig {
+    let mut cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_send_splunk.yml")).unwrap();
+    cfg.spool_file = String::from(spool_file);
+    cfg.batch_max_events = 1;
+    let _ = fs::remove_file(spool_file);
+    cfg
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check events are kept in arrival order and removed once delivered
+fn test_push_peek_remove() {
+    let spool = create_test_spool("test_spool.db", 10, "drop_oldest");
+    assert!(spool.is_empty());
+    for id in ["1", "2", "3"] {
+        spool.push("elastic", &create_test_envelope(id)).unwrap();
+    }
+    assert_eq!(spool.len(), 3);
+
+    let entries = spool.peek(2);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].sink, "elastic");
+    assert_eq!(entries[0].envelope, create_test_envelope("1"));
+    assert_eq!(entries[1].envelope, create_test_envelope("2"));
+
+    spool.remove(entries[0].id);
+    assert_eq!(spool.peek(1)[0].envelope, create_test_envelope("2"));
+    assert_eq!(spool.len(), 2);
+    fs::remove_file("test_spool.db").unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the size is kept in memory and read again when the spool is opened
+fn test_len() {
+    let spool = create_test_spool("test_spool_len.db", 10, "drop_oldest");
+    assert!(spool.is_empty());
+    assert!(!Path::new("test_spool_len.db").exists());
+    spool.push("elastic", &create_test_envelope("1")).unwrap();
+    spool.push("elastic", &create_test_envelope("2")).unwrap();
+    // Copies share the connection and its size
+    assert_eq!(spool.clone().len(), 2);
+
+    let reopened = Spool { lock: Arc::new(Mutex::new(None)), ..spool.clone() };
+    assert_eq!(reopened.len(), 2);
+    reopened.remove(reopened.peek(1)[0].id);
+    assert_eq!(reopened.len(), 1);
+    fs::remove_file("test_spool_len.db").unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the overflow policies when the spool is full
+fn test_push_overflow() {
+    let spool = create_test_spool("test_spool_oldest.db", 2, "drop_oldest");
+    for id in ["1", "2", "3"] {
+        spool.push("splunk", &create_test_envelope(id)).unwrap();
+    }
+    let ids: Vec<String> = spool.peek(10).into_iter().map(|e| e.envelope.id).collect();
+    assert_eq!(ids, vec!["2", "3"]);
+    fs::remove_file("test_spool_oldest.db").unwrap();
+
+    let spool = create_test_spool("test_spool_newest.db", 2, "drop_newest");
+    for id in ["1", "2", "3"] {
+        spool.push("splunk", &create_test_envelope(id)).unwrap();
+    }
+    let ids: Vec<String> = spool.peek(10).into_iter().map(|e| e.envelope.id).collect();
+    assert_eq!(ids, vec!["1", "2"]);
+    assert!(get_dropped() >= 2);
+    fs::remove_file("test_spool_newest.db").unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a spool that cannot be opened reports an error
+fn test_push_error() {
+    let spool = create_test_spool("/not_found/spool.db", 10, "drop_oldest");
+    assert!(spool.push("elastic", &create_test_envelope("1")).is_err());
+    assert_eq!(spool.len(), 0);
+    assert!(spool.peek(1).is_empty());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the backoff doubles on each failure up to the maximum
+fn test_get_backoff() {
+    assert_eq!(get_backoff(1, 300), 2);
+    assert_eq!(get_backoff(2, 300), 4);
+    assert_eq!(get_backoff(8, 300), 256);
+    assert_eq!(get_backoff(9, 300), 300);
+    assert_eq!(get_backoff(100, 300), 300);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check undelivered events are spooled and stay there while the endpoint fails
+fn test_route_and_deliver() {
+    let cfg = create_test_config("test_spool_route.db");
+    let router = Router::new(&cfg);
+    let spool = Spool::new(&cfg);
+
+    block_on(router.route(&create_test_envelope("1"), appconfig::NETWORK_MODE));
+    block_on(router.route(&create_test_envelope("2"), appconfig::NETWORK_MODE));
+    let entries = spool.peek(10);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].sink, "splunk");
+    assert_eq!(entries[1].envelope, create_test_envelope("2"));
+
+    assert!(block_on(deliver(&spool, &router, 10)).is_err());
+    assert_eq!(spool.len(), 2);
+    fs::remove_file("test_spool_route.db").unwrap();
+}
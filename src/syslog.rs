@@ -0,0 +1,247 @@
+// Copyright (C) 2026, Achiefs.
+
+// To send events to syslog collectors over TLS
+use rustls::{ClientConfig, ClientConnection, StreamOwned};
+use rustls::pki_types::ServerName;
+use futures::future::BoxFuture;
+use serde_json::Value;
+use time::OffsetDateTime;
+use log::*;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::appconfig;
+use crate::appconfig::AppConfig;
+use crate::formatter;
+use crate::output::{Envelope, OutputSink};
+use crate::tls;
+use crate::utils;
+
+#[cfg(test)]
+mod test;
+
+// Facility names, their position is the facility code
+pub const FACILITIES: [&str; 24] = ["kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news",
+    "uucp", "cron", "authpriv", "ftp", "ntp", "audit", "alert", "clock",
+    "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7"];
+// Severity names, their position is the severity code
+pub const SEVERITIES: [&str; 8] = ["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+// Structured data id of FIM events
+const SD_ID: &str = "fim@32473";
+pub const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// ----------------------------------------------------------------------------
+
+enum Stream {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>)
+}
+
+/// Connection to the syslog collector, kept between events and opened again
+/// when it fails or the collector settings change.
+pub struct Connection {
+    target: String,
+    stream: Stream
+}
+
+impl Connection {
+    pub fn open(protocol: &str, address: &str, tls: &Result<Arc<ClientConfig>, String>) -> Result<Self, String> {
+        let socket_address = get_socket_address(address)?;
+        let stream = match protocol {
+            "udp" => {
+                let local = if socket_address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+                let socket = UdpSocket::bind(local).map_err(|e| e.to_string())?;
+                socket.connect(socket_address).map_err(|e| e.to_string())?;
+                Stream::Udp(socket)
+            },
+            _ => {
+                let tcp = TcpStream::connect_timeout(&socket_address, TIMEOUT)
+                    .map_err(|e| format!("cannot connect to '{}', {}", address, e))?;
+                tcp.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+                match protocol {
+                    "tls" => {
+                        let host = address.rsplit_once(':').map(|(host, _)| host).unwrap_or(address)
+                            .trim_start_matches('[').trim_end_matches(']');
+                        let name = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+                        let tls = ClientConnection::new(tls.clone()?, name)
+                            .map_err(|e| e.to_string())?;
+                        Stream::Tls(Box::new(StreamOwned::new(tls, tcp)))
+                    },
+                    _ => Stream::Tcp(tcp)
+                }
+            }
+        };
+        debug!("Connected to syslog collector '{}' over {}", address, protocol);
+        Ok(Connection { target: get_target(protocol, address), stream })
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        match &mut self.stream {
+            Stream::Udp(socket) => socket.send(frame).map(|_| ()),
+            Stream::Tcp(stream) => stream.write_all(frame),
+            Stream::Tls(stream) => {
+                stream.write_all(frame)?;
+                stream.flush()
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_target(protocol: &str, address: &str) -> String {
+    format!("{}://{}", protocol, address)
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_socket_address(address: &str) -> Result<SocketAddr, String> {
+    address.to_socket_addrs().map_err(|e| format!("cannot resolve '{}', {}", address, e))?
+        .next().ok_or(format!("cannot resolve '{}'", address))
+}
+
+// ----------------------------------------------------------------------------
+
+// Escape the characters not allowed in structured data values
+fn escape_param(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace(']', "\\]")
+}
+
+// ----------------------------------------------------------------------------
+
+/// Structured data element with the top level scalar fields of the event.
+pub fn get_structured_data(envelope: &Envelope) -> String {
+    let mut element = String::from(SD_ID);
+    if let Some(fields) = envelope.data.as_object() {
+        for (key, value) in fields {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                _ => continue
+            };
+            let name: String = key.chars().filter(|c| c.is_ascii_graphic() && !['=', ']', '"'].contains(c))
+                .take(32).collect();
+            element.push_str(&format!(" {}=\"{}\"", name, escape_param(&value)));
+        }
+    }
+    format!("[{}]", element)
+}
+
+// ----------------------------------------------------------------------------
+
+/// Sends each event as a syslog message to a collector over UDP, TCP or TLS,
+/// the events it can't send are spooled and sent again later.
+pub struct SyslogSink {
+    connection: Arc<Mutex<Option<Connection>>>,
+    address: String,
+    protocol: String,
+    format: String,
+    framing: String,
+    priority: u8,
+    app_name: String,
+    hostname: String,
+    tls: Result<Arc<ClientConfig>, String>,
+    cfg: AppConfig
+}
+
+impl SyslogSink {
+    pub fn new(cfg: &AppConfig) -> Self {
+        SyslogSink {
+            connection: cfg.syslog_lock.clone(),
+            address: cfg.syslog_address.clone(),
+            protocol: cfg.syslog_protocol.clone(),
+            format: cfg.syslog_format.clone(),
+            framing: cfg.syslog_framing.clone(),
+            priority: cfg.syslog_facility * 8 + cfg.syslog_severity,
+            app_name: cfg.syslog_app_name.clone(),
+            hostname: utils::get_hostname(),
+            tls: tls::get_config(cfg.syslog_insecure, cfg.syslog_ca_file.as_deref(), cfg.syslog_client_cert.as_deref(),
+                cfg.syslog_client_key.as_deref(), &cfg.syslog_pins).map(Arc::new),
+            cfg: cfg.clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Syslog message of an event, the event type is the MSGID and the event
+    /// in the configured format is the message text.
+    pub fn get_message(&self, envelope: &Envelope, now: OffsetDateTime) -> String {
+        let text = formatter::format(&self.cfg.format_syslog, envelope, &self.cfg);
+        match self.format.as_str() {
+            "rfc3164" => format!("<{}>{} {:>2} {:02}:{:02}:{:02} {} {}[{}]: {}",
+                self.priority, MONTHS[now.month() as usize - 1], now.day(),
+                now.hour(), now.minute(), now.second(),
+                self.hostname, self.app_name, utils::get_pid(), text),
+            _ => format!("<{}>1 {:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z {} {} {} {} {} {}",
+                self.priority, now.year(), now.month() as u8, now.day(),
+                now.hour(), now.minute(), now.second(), now.millisecond(),
+                self.hostname, self.app_name, utils::get_pid(),
+                envelope.kind.as_deref().unwrap_or("-"),
+                get_structured_data(envelope), text)
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Message as written to the connection, stream transports prefix its
+    /// length with octet counting or end it with a new line.
+    pub fn get_frame(&self, message: &str) -> Vec<u8> {
+        match (self.protocol.as_str(), self.framing.as_str()) {
+            ("udp", _) => message.as_bytes().to_vec(),
+            (_, "octet-counting") => format!("{} {}", message.len(), message).into_bytes(),
+            _ => format!("{}\n", message).into_bytes()
+        }
+    }
+}
+
+impl OutputSink for SyslogSink {
+    fn name(&self) -> &str { "syslog" }
+
+    fn mode(&self) -> &str { appconfig::SYSLOG_MODE }
+
+    fn retried(&self) -> bool { true }
+
+    fn write<'a>(&'a self, envelope: &'a Envelope) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let frame = self.get_frame(&self.get_message(envelope, OffsetDateTime::now_utc()));
+            let connection = self.connection.clone();
+            let (protocol, address, tls) = (self.protocol.clone(), self.address.clone(), self.tls.clone());
+            // Connecting and writing block, keep them out of the async workers
+            tokio::task::spawn_blocking(move || send(&connection, &protocol, &address, &tls, &frame)).await
+                .map_err(|e| format!("cannot send event to '{}', {}", self.address, e))?
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Write a frame to the collector, opening the connection when there is none
+// or the collector settings changed
+fn send(connection: &Mutex<Option<Connection>>, protocol: &str, address: &str,
+    tls: &Result<Arc<ClientConfig>, String>, frame: &[u8]) -> Result<(), String> {
+    let target = get_target(protocol, address);
+    let mut connection = connection.lock().unwrap();
+    // A dropped connection is only noticed on write, try again once with a new one
+    let mut retry = true;
+    loop {
+        if connection.as_ref().map(|c| c.target != target).unwrap_or(true) {
+            *connection = Some(Connection::open(protocol, address, tls)?);
+        }
+        match connection.as_mut().unwrap().send(frame) {
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                *connection = None;
+                if !retry { return Err(format!("cannot send event to '{}', {}", address, e)); }
+                debug!("Syslog connection lost, reconnecting. Error: {}", e);
+                retry = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use super::*;
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read};
+use std::net::TcpListener;
+use tokio_test::block_on;
+
+// ----------------------------------------------------------------------------
+
+fn create_test_envelope() -> Envelope {
+    Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "file": "/etc/\"a\\b]",
+        "file_size": 10,
+        "labels": ["etc"]
+    }))
+}
+
+// ----------------------------------------------------------------------------
+
+fn create_test_sink(address: &str, protocol: &str) -> SyslogSink {
+    let mut cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog.yml")).unwrap();
+    cfg.syslog_address = String::from(address);
+    cfg.syslog_protocol = String::from(protocol);
+    SyslogSink::new(&cfg)
+}
+
+// ----------------------------------------------------------------------------
+
+fn get_test_time() -> OffsetDateTime {
+    // 2023-11-04 02:03:20.250 UTC
+    OffsetDateTime::from_unix_timestamp_nanos(1699063400250000000).unwrap()
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check scalar event fields become escaped structured data parameters
+fn test_get_structured_data() {
+    assert_eq!(get_structured_data(&create_test_envelope()),
+        "[fim@32473 file=\"/etc/\\\"a\\\\b\\]\" file_size=\"10\" id=\"Test_id\"]");
+    assert_eq!(get_structured_data(&Envelope::new("Test_id", json!([]))), "[fim@32473]");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the RFC 5424 and RFC 3164 message headers
+fn test_get_message() {
+    let mut sink = create_test_sink("127.0.0.1:5514", "tcp");
+    let envelope = create_test_envelope();
+    // local0.info
+    assert_eq!(sink.priority, 134);
+    let message = sink.get_message(&envelope, get_test_time());
+    assert_eq!(message, format!("<134>1 2023-11-04T02:03:20.250Z {} fim-test {} - {} {}",
+        utils::get_hostname(), utils::get_pid(), get_structured_data(&envelope), envelope.to_json()));
+    let message = sink.get_message(&envelope.clone().with_kind("rule"), get_test_time());
+    assert!(message.contains(" rule [fim@32473 "));
+
+    sink.format = String::from("rfc3164");
+    assert_eq!(sink.get_message(&envelope, get_test_time()), format!("<134>Nov  4 02:03:20 {} fim-test[{}]: {}",
+        utils::get_hostname(), utils::get_pid(), envelope.to_json()));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check stream framing, UDP datagrams carry the message as is
+fn test_get_frame() {
+    let mut sink = create_test_sink("127.0.0.1:5514", "tcp");
+    assert_eq!(sink.get_frame("<134>1 test"), b"11 <134>1 test");
+    sink.framing = String::from("non-transparent");
+    assert_eq!(sink.get_frame("<134>1 test"), b"<134>1 test\n");
+    sink.protocol = String::from("udp");
+    assert_eq!(sink.get_frame("<134>1 test"), b"<134>1 test");
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check events are sent as UDP datagrams
+fn test_write_udp() {
+    let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+    collector.set_read_timeout(Some(TIMEOUT)).unwrap();
+    let sink = create_test_sink(&collector.local_addr().unwrap().to_string(), "udp");
+    block_on(sink.write(&create_test_envelope())).unwrap();
+
+    let mut buffer = [0; 4096];
+    let size = collector.recv(&mut buffer).unwrap();
+    let message = String::from_utf8_lossy(&buffer[..size]);
+    assert!(message.starts_with("<134>1 "));
+    assert!(message.ends_with(&create_test_envelope().to_json()));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check events are octet counted over TCP and the connection is reused
+fn test_write_tcp() {
+    let collector = TcpListener::bind("127.0.0.1:0").unwrap();
+    let sink = create_test_sink(&collector.local_addr().unwrap().to_string(), "tcp");
+    block_on(sink.write(&create_test_envelope())).unwrap();
+    block_on(sink.write(&create_test_envelope())).unwrap();
+
+    let (stream, _) = collector.accept().unwrap();
+    stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+    let mut reader = BufReader::new(stream);
+    for _ in 0..2 {
+        let mut length = Vec::new();
+        reader.read_until(b' ', &mut length).unwrap();
+        let length: usize = String::from_utf8_lossy(&length).trim().parse().unwrap();
+        let mut message = vec![0; length];
+        reader.read_exact(&mut message).unwrap();
+        assert!(String::from_utf8_lossy(&message).ends_with(&create_test_envelope().to_json()));
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the sink connects again when the collector address changes
+fn test_write_reconnect() {
+    let first = TcpListener::bind("127.0.0.1:0").unwrap();
+    let second = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut sink = create_test_sink(&first.local_addr().unwrap().to_string(), "tcp");
+    block_on(sink.write(&create_test_envelope())).unwrap();
+    assert!(first.accept().is_ok());
+
+    sink.address = second.local_addr().unwrap().to_string();
+    block_on(sink.write(&create_test_envelope())).unwrap();
+    assert!(second.accept().is_ok());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check an unreachable collector reports an error
+fn test_write_error() {
+    let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let sink = create_test_sink(&address, "tcp");
+    assert!(block_on(sink.write(&create_test_envelope())).is_err());
+    assert!(sink.connection.lock().unwrap().is_none());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the TLS settings of the collector are loaded with the sink
+fn test_sink_tls() {
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_syslog_tls.yml")).unwrap();
+    assert!(SyslogSink::new(&cfg).tls.is_ok());
+
+    let mut cfg = cfg.clone();
+    cfg.syslog_ca_file = Some(String::from("test/unit/tls/missing.pem"));
+    assert!(SyslogSink::new(&cfg).tls.is_err());
+}
@@ -0,0 +1,164 @@
+// Copyright (C) 2026, Achiefs.
+
+// To build the TLS settings of the network endpoint and syslog collectors
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::pki_types::pem::PemObject;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::appconfig::AppConfig;
+
+#[cfg(test)]
+mod test;
+
+// ----------------------------------------------------------------------------
+
+/// Certificates of a PEM file, at least one is required.
+pub fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("unable to read certificates from '{}', {}", path, e))?;
+    match certs.is_empty() {
+        true => Err(format!("no certificates found in '{}'", path)),
+        false => Ok(certs)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Private key of a PEM file, PKCS#8, PKCS#1 or SEC1.
+pub fn load_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    PrivateKeyDer::from_pem_file(path)
+        .map_err(|e| format!("unable to read private key from '{}', {}", path, e))
+}
+
+// ----------------------------------------------------------------------------
+
+/// SHA-256 fingerprint of a certificate as lowercase hex, the format pins
+/// are stored in.
+pub fn get_fingerprint(cert: &CertificateDer<'_>) -> String {
+    hex::encode(Sha256::digest(cert.as_ref()))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Pin as written in the configuration, colons are allowed between bytes as
+/// `openssl x509 -fingerprint -sha256` prints them.
+pub fn parse_pin(pin: &str) -> Result<String, String> {
+    let pin = pin.replace(':', "").to_lowercase();
+    match pin.len() == 64 && pin.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => Ok(pin),
+        false => Err(String::from("expected the SHA-256 fingerprint of a certificate, 64 hex characters"))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// TLS client settings. Server certificates are verified against the public
+/// roots and the `ca_file` certificates unless `insecure` is set, when `pins`
+/// is not empty a certificate of the chain must also have one of those
+/// fingerprints. The client certificate is sent when both `client_cert` and
+/// `client_key` are set.
+pub fn get_config(insecure: bool, ca_file: Option<&str>, client_cert: Option<&str>, client_key: Option<&str>,
+    pins: &[String]) -> Result<ClientConfig, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier: Arc<dyn ServerCertVerifier> = match insecure {
+        true => Arc::new(NoVerifier(provider.clone())),
+        false => {
+            let mut roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+            if let Some(ca_file) = ca_file {
+                for cert in load_certs(ca_file)? {
+                    roots.add(cert).map_err(|e| format!("invalid CA certificate in '{}', {}", ca_file, e))?;
+                }
+            }
+            WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build()
+                .map_err(|e| e.to_string())?
+        }
+    };
+    let verifier: Arc<dyn ServerCertVerifier> = match pins.is_empty() {
+        true => verifier,
+        false => Arc::new(PinnedVerifier { inner: verifier, pins: pins.to_vec() })
+    };
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions().map_err(|e| e.to_string())?
+        .dangerous().with_custom_certificate_verifier(verifier);
+    match (client_cert, client_key) {
+        (Some(cert), Some(key)) => builder.with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+            .map_err(|e| format!("invalid client certificate or key, {}", e)),
+        _ => Ok(builder.with_no_client_auth())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// TLS client settings of the network endpoint.
+pub fn get_endpoint_config(cfg: &AppConfig) -> Result<ClientConfig, String> {
+    get_config(cfg.insecure, cfg.endpoint_ca_file.as_deref(), cfg.endpoint_client_cert.as_deref(),
+        cfg.endpoint_client_key.as_deref(), &cfg.endpoint_pins)
+}
+
+// ----------------------------------------------------------------------------
+
+// Accepts any server certificate, used when insecure is set
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(&self, _end_entity: &CertificateDer<'_>, _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>, _ocsp_response: &[u8], _now: UnixTime) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Requires a pinned certificate in the chain before the usual verification
+#[derive(Debug)]
+struct PinnedVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    pins: Vec<String>
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(&self, end_entity: &CertificateDer<'_>, intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>, ocsp_response: &[u8], now: UnixTime) -> Result<ServerCertVerified, rustls::Error> {
+        let pinned = std::iter::once(end_entity).chain(intermediates)
+            .any(|cert| self.pins.contains(&get_fingerprint(cert)));
+        if !pinned {
+            return Err(rustls::Error::General(format!("certificate '{}' is not pinned", get_fingerprint(end_entity))));
+        }
+        self.inner.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
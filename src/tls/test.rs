@@ -0,0 +1,66 @@
+use super::*;
+
+use crate::utils;
+
+// Fingerprint of test/unit/tls/client.pem
+const CLIENT_PIN: &str = "0fe49d50888b3cd3dcee140c39ccda312467555215bd026571986384c9404508";
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check certificates and keys are read from PEM files
+fn test_load_certs_key() {
+    assert_eq!(load_certs("test/unit/tls/client.pem").unwrap().len(), 1);
+    assert_eq!(load_certs("test/unit/tls/client.key").unwrap_err(), "no certificates found in 'test/unit/tls/client.key'");
+    assert!(load_certs("test/unit/tls/missing.pem").unwrap_err().starts_with("unable to read certificates"));
+    assert!(load_key("test/unit/tls/client.key").is_ok());
+    assert!(load_key("test/unit/tls/ca.pem").unwrap_err().starts_with("unable to read private key"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check pins accept the openssl fingerprint output
+fn test_parse_pin() {
+    let cert = load_certs("test/unit/tls/client.pem").unwrap().remove(0);
+    assert_eq!(get_fingerprint(&cert), CLIENT_PIN);
+    assert_eq!(parse_pin("0F:E4:9D:50:88:8B:3C:D3:DC:EE:14:0C:39:CC:DA:31:24:67:55:52:15:BD:02:65:71:98:63:84:C9:40:45:08"),
+        Ok(String::from(CLIENT_PIN)));
+    assert!(parse_pin("0FE49D").is_err());
+    assert!(parse_pin(&"z".repeat(64)).is_err());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the client certificate is only sent when configured
+fn test_get_config() {
+    let config = get_config(false, None, None, None, &[]).unwrap();
+    assert!(!config.client_auth_cert_resolver.has_certs());
+    let config = get_config(false, Some("test/unit/tls/ca.pem"), Some("test/unit/tls/client.pem"),
+        Some("test/unit/tls/client.key"), &[String::from(CLIENT_PIN)]).unwrap();
+    assert!(config.client_auth_cert_resolver.has_certs());
+    assert!(get_config(false, Some("test/unit/tls/client.key"), None, None, &[]).is_err());
+    assert!(get_config(true, None, Some("test/unit/tls/client.pem"), Some("test/unit/tls/ca.pem"), &[]).is_err());
+
+    let cfg = AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_tls.yml")).unwrap();
+    assert!(get_endpoint_config(&cfg).unwrap().client_auth_cert_resolver.has_certs());
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check a pinned certificate is required in the chain
+fn test_pinned_verifier() {
+    let cert = load_certs("test/unit/tls/client.pem").unwrap().remove(0);
+    let ca = load_certs("test/unit/tls/ca.pem").unwrap().remove(0);
+    let name = ServerName::try_from("localhost").unwrap();
+    let inner: Arc<dyn ServerCertVerifier> = Arc::new(NoVerifier(Arc::new(rustls::crypto::ring::default_provider())));
+    let verifier = PinnedVerifier { inner: inner.clone(), pins: vec![String::from(CLIENT_PIN)] };
+    assert!(verifier.verify_server_cert(&cert, &[], &name, &[], UnixTime::now()).is_ok());
+    assert!(verifier.verify_server_cert(&ca, &[], &name, &[], UnixTime::now()).is_err());
+
+    let verifier = PinnedVerifier { inner, pins: vec![get_fingerprint(&ca)] };
+    assert!(verifier.verify_server_cert(&cert, std::slice::from_ref(&ca), &name, &[], UnixTime::now()).is_ok());
+    assert!(verifier.verify_server_cert(&cert, &[], &name, &[], UnixTime::now()).is_err());
+}
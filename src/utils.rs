@@ -311,4 +311,44 @@ pub fn get_unix_permissions(_v: &str) -> u32 {
     return 0
 }
 
+// ----------------------------------------------------------------------------
+
+/// Read a secret stored in a file, on Unix systems it must be owned by root
+/// or the user FIM runs as and only accessible by its owner. The trailing
+/// line break is removed.
+pub fn read_secret(path: &str) -> Result<String, String> {
+    let metadata = match Path::new(path).metadata() {
+        Ok(metadata) => metadata,
+        Err(e) => return Err(format!("Unable to read file '{}', {:?}", path, e.kind()))
+    };
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        // Another user could replace the secret even without access to it
+        let uid = unsafe { libc::geteuid() };
+        if metadata.uid() != 0 && metadata.uid() != uid {
+            return Err(format!("file '{}' is owned by uid {}, it must be owned by root or uid {}",
+                path, metadata.uid(), uid));
+        }
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Err(format!("permissions {:o} of file '{}' are too open, \
+                it must be accessible only by its owner (600 or 400)", mode, path));
+        }
+    }
+    #[cfg(target_family = "windows")]
+    let _ = metadata;
+
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            let secret = contents.trim_end_matches(['\r', '\n']);
+            match secret.is_empty() {
+                true => Err(format!("file '{}' is empty", path)),
+                false => Ok(String::from(secret))
+            }
+        },
+        Err(e) => Err(format!("Unable to read file '{}', {:?}", path, e.kind()))
+    }
+}
+
 // ----------------------------------------------------------------------------
\ No newline at end of file
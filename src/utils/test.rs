@@ -185,8 +185,8 @@ fn test_get_file_size() {
 #[test]
 fn test_get_audit_rule_permissions() {
     use crate::appconfig::*;
-    let cfg = AppConfig::new(&get_os(), Some("test/unit/config/linux/audit_rule.yml"));
-    assert_eq!(get_audit_rule_permissions(cfg.audit[0]["rule"].as_str()), "rwax");
+    let cfg = AppConfig::new(&get_os(), Some("test/unit/config/linux/audit_rule.yml")).unwrap();
+    assert_eq!(get_audit_rule_permissions(cfg.audit[0].rule.as_deref()), "rwax");
 }
 
 // ------------------------------------------------------------------------
@@ -195,9 +195,9 @@ fn test_get_audit_rule_permissions() {
 #[test]
 fn test_run_auditctl() {
     use crate::appconfig::*;
-    let cfg = AppConfig::new(&get_os(), Some("test/unit/config/linux/audit_rule.yml"));
-    let path = cfg.audit[0]["path"].as_str().unwrap();
-    let rule = cfg.audit[0]["rule"].as_str().unwrap();
+    let cfg = AppConfig::new(&get_os(), Some("test/unit/config/linux/audit_rule.yml")).unwrap();
+    let path = cfg.audit[0].path.as_str();
+    let rule = cfg.audit[0].rule.as_deref().unwrap();
     run_auditctl(&["-w", path, "-k", "fim", "-p", rule]);
 
     match Command::new("/usr/sbin/auditctl")
@@ -258,4 +258,37 @@ fn test_get_unix_permissions() {
     }else{
         assert_eq!(get_unix_permissions("LICENSE"), 100644);
     }
-}
\ No newline at end of file
+}
+
+// ------------------------------------------------------------------------
+
+#[cfg(target_family = "unix")]
+#[test]
+/// Check secrets are read only from files accessible by their owner
+fn test_read_secret() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    let filename = "test_read_secret.txt";
+    fs::write(filename, "secret\n").unwrap();
+
+    fs::set_permissions(filename, fs::Permissions::from_mode(0o600)).unwrap();
+    assert_eq!(read_secret(filename), Ok(String::from("secret")));
+
+    fs::set_permissions(filename, fs::Permissions::from_mode(0o640)).unwrap();
+    assert!(read_secret(filename).unwrap_err().contains("permissions 640"));
+
+    // Only root can give the file to another user
+    if unsafe { libc::geteuid() } == 0 {
+        std::os::unix::fs::chown(filename, Some(65534), None).unwrap();
+        fs::set_permissions(filename, fs::Permissions::from_mode(0o600)).unwrap();
+        assert!(read_secret(filename).unwrap_err().contains("is owned by uid 65534"));
+        std::os::unix::fs::chown(filename, Some(0), None).unwrap();
+    }
+
+    fs::write(filename, "\n").unwrap();
+    fs::set_permissions(filename, fs::Permissions::from_mode(0o400)).unwrap();
+    assert!(read_secret(filename).unwrap_err().contains("is empty"));
+
+    fs::remove_file(filename).unwrap();
+    assert!(read_secret(filename).unwrap_err().contains("NotFound"));
+}
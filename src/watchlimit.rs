@@ -0,0 +1,135 @@
+// Copyright (C) 2026, Achiefs.
+
+// To check the inotify watches a monitored tree needs before watching it
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[cfg(test)]
+mod test;
+
+const MAX_WATCHES_PATH: &str = "/proc/sys/fs/inotify/max_user_watches";
+
+// ----------------------------------------------------------------------------
+
+/// Monitored tree that doesn't fit in the inotify watches left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shortage {
+    pub path: PathBuf,
+    pub needed: u64,
+    pub available: u64,
+    pub covered: Vec<PathBuf>,
+    pub uncovered: Vec<PathBuf>,
+    pub polled: bool
+}
+
+// ----------------------------------------------------------------------------
+
+/// Watches allowed to each user, None if inotify limits are unknown.
+pub fn get_max_watches() -> Option<u64> {
+    fs::read_to_string(MAX_WATCHES_PATH).ok()?.trim().parse().ok()
+}
+
+// ----------------------------------------------------------------------------
+
+// Real user id of a process from its status file
+fn get_uid(proc: &Path) -> Option<String> {
+    fs::read_to_string(proc.join("status")).ok()?.lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().next().map(String::from))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Watches held by the inotify instances of the processes of the user
+/// running FIM, the ones of processes that can't be read are not counted.
+pub fn get_used_watches() -> u64 {
+    let uid = get_uid(Path::new("/proc/self"));
+    let processes = match fs::read_dir("/proc") {
+        Ok(processes) => processes,
+        Err(_) => return 0
+    };
+    processes.filter_map(Result::ok)
+        .filter(|process| process.file_name().to_str().is_some_and(|name| name.parse::<u32>().is_ok()))
+        .filter(|process| uid.is_some() && get_uid(&process.path()) == uid)
+        .map(|process| {
+            let fds = match fs::read_dir(process.path().join("fd")) {
+                Ok(fds) => fds,
+                Err(_) => return 0
+            };
+            fds.filter_map(Result::ok)
+                .filter(|fd| fs::read_link(fd.path()).is_ok_and(|link| link == Path::new("anon_inode:inotify")))
+                .map(|fd| fs::read_to_string(process.path().join("fdinfo").join(fd.file_name()))
+                    .map(|info| info.lines().filter(|line| line.starts_with("inotify wd:")).count() as u64)
+                    .unwrap_or(0))
+                .sum()
+        }).sum()
+}
+
+// ----------------------------------------------------------------------------
+
+/// Folders of a tree, one inotify watch each. Links are followed as the
+/// recursive watches do.
+pub fn count_folders(root: &Path) -> u64 {
+    WalkDir::new(root).follow_links(true).into_iter().filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir()).count() as u64
+}
+
+// ----------------------------------------------------------------------------
+
+// Folders of a tree and of each of its first level subtrees, in the order
+// they are read, from a single walk
+fn get_subtrees(root: &Path) -> (u64, Vec<(PathBuf, u64)>) {
+    let mut folders = 0;
+    let mut subtrees: Vec<(PathBuf, u64)> = Vec::new();
+    for entry in WalkDir::new(root).follow_links(true).into_iter().filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir()) {
+        folders += 1;
+        match entry.depth() {
+            0 => (),
+            1 => subtrees.push((entry.into_path(), 1)),
+            // Folders of a subtree are read before the next first level one
+            _ => if let Some((_, count)) = subtrees.last_mut() {
+                *count += 1;
+            }
+        }
+    }
+    (folders, subtrees)
+}
+
+// ----------------------------------------------------------------------------
+
+/// Watches left to the user running FIM, None if inotify limits are unknown.
+pub fn get_available() -> Option<u64> {
+    get_max_watches().map(|max| max.saturating_sub(get_used_watches()))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Shortage of watching `root` with `available` watches, None when it fits.
+/// The root takes a watch and then each first level subtree is covered if
+/// it fits in the watches left, otherwise it is listed as uncovered. The
+/// root itself is uncovered when no watch is left.
+pub fn check(root: &Path, available: u64) -> Option<Shortage> {
+    let (needed, subtrees) = get_subtrees(root);
+    if needed <= available {
+        return None;
+    }
+    let (mut covered, mut uncovered) = (Vec::new(), Vec::new());
+    match available {
+        0 => uncovered.push(root.to_path_buf()),
+        _ => {
+            let mut used = 1;
+            for (subtree, folders) in subtrees {
+                match used + folders <= available {
+                    true => {
+                        used += folders;
+                        covered.push(subtree);
+                    },
+                    false => uncovered.push(subtree)
+                }
+            }
+        }
+    }
+    Some(Shortage { path: root.to_path_buf(), needed, available, covered, uncovered, polled: false })
+}
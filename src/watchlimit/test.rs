@@ -0,0 +1,89 @@
+use super::*;
+
+// ----------------------------------------------------------------------------
+
+// Tree of a root folder holding a, with two folders, and b, with none
+fn create_test_tree(root: &Path) {
+    let _ = fs::remove_dir_all(root);
+    fs::create_dir_all(root.join("a").join("a1")).unwrap();
+    fs::create_dir_all(root.join("a").join("a2")).unwrap();
+    fs::create_dir_all(root.join("b")).unwrap();
+    fs::write(root.join("a").join("file.txt"), "test").unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check every folder of a tree needs a watch, files don't
+fn test_count_folders() {
+    let root = Path::new("./test_watchlimit_count");
+    create_test_tree(root);
+    assert_eq!(count_folders(root), 5);
+    assert_eq!(count_folders(&root.join("b")), 1);
+    assert_eq!(count_folders(&root.join("none")), 0);
+    fs::remove_dir_all(root).unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the subtrees that don't fit are listed and the ones that do covered
+fn test_check() {
+    let root = Path::new("./test_watchlimit_check");
+    create_test_tree(root);
+    assert_eq!(check(root, 5), None);
+    let shortage = check(root, 1).unwrap();
+    assert_eq!(shortage.needed, 5);
+    assert_eq!(shortage.available, 1);
+    assert!(!shortage.polled);
+    assert!(shortage.covered.is_empty());
+    let mut uncovered = shortage.uncovered.clone();
+    uncovered.sort();
+    assert_eq!(uncovered, vec![root.join("a"), root.join("b")]);
+    assert_eq!(check(root, 0).unwrap().uncovered, vec![root.to_path_buf()]);
+    // Smaller subtrees read later are still covered when a bigger one doesn't fit
+    let shortage = check(root, 2).unwrap();
+    assert_eq!(shortage.covered, vec![root.join("b")]);
+    assert_eq!(shortage.uncovered, vec![root.join("a")]);
+    let shortage = check(root, 4).unwrap();
+    assert_eq!(shortage.covered.len(), 1);
+    assert_eq!(shortage.uncovered.len(), 1);
+    fs::remove_dir_all(root).unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check folders are counted for each first level subtree in a single walk
+fn test_get_subtrees() {
+    let root = Path::new("./test_watchlimit_subtrees");
+    create_test_tree(root);
+    let (folders, mut subtrees) = get_subtrees(root);
+    subtrees.sort();
+    assert_eq!(folders, 5);
+    assert_eq!(subtrees, vec![(root.join("a"), 3), (root.join("b"), 1)]);
+    assert_eq!(get_subtrees(&root.join("none")), (0, Vec::new()));
+    fs::remove_dir_all(root).unwrap();
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(target_os = "linux")]
+#[test]
+/// Check watches of this process are counted with the ones of its user
+fn test_get_used_watches() {
+    use notify::Watcher;
+    if get_max_watches().is_none() {
+        return;
+    }
+    let root = Path::new("./test_watchlimit_used");
+    create_test_tree(root);
+    let (tx, _rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::INotifyWatcher::new(tx, notify::Config::default()).unwrap();
+    watcher.watch(root, notify::RecursiveMode::Recursive).unwrap();
+    let used = get_used_watches();
+    assert!(used >= 5);
+    assert!(get_available().unwrap() <= get_max_watches().unwrap() - 5);
+    watcher.unwatch(root).unwrap();
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,184 @@
+// Copyright (C) 2026, Achiefs.
+
+// To post events to generic HTTP endpoints
+use futures::future::BoxFuture;
+use reqwest::{Client, Method};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::Value;
+use log::*;
+use std::time::Duration;
+
+use crate::appconfig;
+use crate::appconfig::AppConfig;
+use crate::configfile::WebhookConfig;
+use crate::formatter;
+use crate::output::{self, Envelope, OutputSink};
+use crate::tls;
+
+#[cfg(test)]
+mod test;
+
+// ----------------------------------------------------------------------------
+
+/// HTTP client of a webhook, with its CA certificates or without verifying
+/// the server when insecure is set.
+pub fn get_client(webhook: &WebhookConfig) -> Result<Client, String> {
+    let config = tls::get_config(webhook.insecure.unwrap_or(false), webhook.ca_file.as_deref(), None, None, &[])
+        .map_err(|e| format!("webhook TLS settings not applied, {}", e))?;
+    Client::builder().timeout(Duration::from_secs(30)).use_preconfigured_tls(config).build()
+        .map_err(|e| format!("unable to build HTTP client, {}", e))
+}
+
+// ----------------------------------------------------------------------------
+
+// Text of a field inside a template, lists are joined with commas
+fn get_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(get_text).collect::<Vec<String>>().join(","),
+        value => value.to_string()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Posts events to an HTTP endpoint, with the payload built from the
+/// configured template or the event in the webhook format.
+pub struct WebhookSink {
+    client: Result<Client, String>,
+    webhook: WebhookConfig,
+    payload: Option<Value>,
+    cfg: AppConfig
+}
+
+impl WebhookSink {
+    pub fn new(cfg: &AppConfig, webhook: &WebhookConfig) -> Self {
+        WebhookSink {
+            client: get_client(webhook),
+            webhook: webhook.clone(),
+            payload: webhook.payload.as_ref().map(|payload| serde_json::to_value(payload).unwrap_or_default()),
+            cfg: cfg.clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn format(&self) -> &str { self.webhook.format.as_deref().unwrap_or("json") }
+
+    // ------------------------------------------------------------------------
+
+    /// Value of a template field. `path`, `kind`, `source` and `event`, the
+    /// whole event in the webhook format, come from the envelope, other
+    /// names are event fields, as the ones read by `Event::get_string`.
+    pub fn get_field(&self, envelope: &Envelope, name: &str) -> Value {
+        match name {
+            "path" => Value::from(envelope.path.clone()),
+            "kind" => Value::from(envelope.kind.clone()),
+            "source" => Value::from(envelope.source.clone()),
+            "event" => formatter::format_value(self.format(), envelope, &self.cfg),
+            "id" if envelope.data["id"].is_null() => Value::from(envelope.id.clone()),
+            name => envelope.data[name].clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Template with its `{{field}}` references replaced. A string holding
+    /// only a reference takes the field value as it is, keeping numbers and
+    /// lists in JSON payloads, otherwise the field text is inserted.
+    pub fn render(&self, template: &Value, envelope: &Envelope) -> Value {
+        match template {
+            Value::String(text) => {
+                let trimmed = text.trim();
+                if let Some(name) = trimmed.strip_prefix("{{").and_then(|name| name.strip_suffix("}}")) {
+                    if !name.contains("{{") {
+                        return self.get_field(envelope, name.trim());
+                    }
+                }
+                let mut rendered = String::new();
+                let mut rest = text.as_str();
+                while let Some(start) = rest.find("{{") {
+                    match rest[start..].find("}}") {
+                        Some(end) => {
+                            rendered.push_str(&rest[..start]);
+                            rendered.push_str(&get_text(&self.get_field(envelope, rest[start + 2..start + end].trim())));
+                            rest = &rest[start + end + 2..];
+                        },
+                        None => break
+                    }
+                }
+                rendered.push_str(rest);
+                Value::String(rendered)
+            },
+            Value::Array(values) => Value::Array(values.iter().map(|value| self.render(value, envelope)).collect()),
+            Value::Object(fields) => Value::Object(fields.iter()
+                .map(|(key, value)| (key.clone(), self.render(value, envelope))).collect()),
+            value => value.clone()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Request body and its content type. Text templates are sent as plain
+    /// text, structured ones as JSON.
+    pub fn get_body(&self, envelope: &Envelope) -> (String, &str) {
+        match &self.payload {
+            Some(template @ Value::String(_)) => (get_text(&self.render(template, envelope)), "text/plain"),
+            Some(template) => (self.render(template, envelope).to_string(), "application/json"),
+            None => match formatter::format_value(self.format(), envelope, &self.cfg) {
+                Value::String(line) => (line, "text/plain"),
+                event => (event.to_string(), "application/json")
+            }
+        }
+    }
+}
+
+impl OutputSink for WebhookSink {
+    fn name(&self) -> &str { self.webhook.name.as_deref().unwrap_or("webhook") }
+
+    fn mode(&self) -> &str { appconfig::WEBHOOK_MODE }
+
+    /// Operations match the operation or detailed operation of the event,
+    /// labels any of its labels and rules only let rule events with those
+    /// ids through.
+    fn accepts(&self, envelope: &Envelope) -> bool {
+        let filter = &self.webhook.filter;
+        let data = &envelope.data;
+        let operation = filter.operations.as_ref().map(|operations| operations.iter()
+            .any(|operation| data["operation"] == *operation || data["detailed_operation"] == *operation))
+            .unwrap_or(true);
+        let labels = filter.labels.as_ref().map(|labels| data["labels"].as_array()
+            .map(|values| values.iter().any(|value| labels.iter().any(|label| value == label)))
+            .unwrap_or(false))
+            .unwrap_or(true);
+        let rule = filter.rules.as_ref().map(|rules| envelope.kind.as_deref() == Some("rule")
+            && data["id"].as_u64().map(|id| rules.contains(&(id as usize))).unwrap_or(false))
+            .unwrap_or(true);
+        operation && labels && rule
+    }
+
+    fn write<'a>(&'a self, envelope: &'a Envelope) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let method = Method::from_bytes(self.webhook.method.as_deref().unwrap_or("POST").as_bytes())
+                .unwrap_or(Method::POST);
+            let (body, content_type) = self.get_body(envelope);
+            let mut request = self.client.as_ref()?.request(method, &self.webhook.url);
+            if !self.webhook.headers.keys().any(|name| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str())) {
+                request = request.header(CONTENT_TYPE, content_type);
+            }
+            for (name, value) in self.webhook.headers.iter() {
+                request = request.header(name, value);
+            }
+            if let Some(credentials) = &self.webhook.credentials {
+                request = match (&credentials.token, &credentials.user) {
+                    (Some(token), _) => request.bearer_auth(token),
+                    (None, Some(user)) => request.basic_auth(user, credentials.password.as_ref()),
+                    (None, None) => request
+                };
+            }
+            debug!("Sending event '{}' to webhook '{}'", envelope.id, self.name());
+            output::check_response(request.body(body).send().await).await.map(|_| ())
+        })
+    }
+}
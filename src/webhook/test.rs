@@ -0,0 +1,158 @@
+use super::*;
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use tokio_test::block_on;
+
+use crate::utils;
+
+// ----------------------------------------------------------------------------
+
+fn create_test_config() -> AppConfig {
+    AppConfig::new(utils::get_os(), Some("test/unit/config/common/test_webhook.yml")).unwrap()
+}
+
+// ----------------------------------------------------------------------------
+
+fn create_monitor_envelope(operation: &str, labels: Value) -> Envelope {
+    Envelope::new("Test_id", json!({
+        "id": "Test_id",
+        "hostname": "Hostname",
+        "operation": operation,
+        "detailed_operation": format!("{}_FILE", operation),
+        "file_size": 10,
+        "labels": labels
+    })).with_kind("monitor").with_path("/etc/hosts")
+}
+
+// ----------------------------------------------------------------------------
+
+fn create_rule_envelope(id: usize) -> Envelope {
+    Envelope::new(&id.to_string(), json!({ "id": id, "message": "Shell script", "parent_id": "0000" }))
+        .with_kind("rule").with_path("/etc/test.sh")
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check field references keep their value or are inserted as text
+fn test_render() {
+    let cfg = create_test_config();
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[0]);
+    let envelope = create_monitor_envelope("CREATE", json!(["etc", "linux"]));
+    assert_eq!(sink.render(sink.payload.as_ref().unwrap(), &envelope), json!({
+        "text": "CREATE on /etc/hosts at Hostname",
+        "size": 10,
+        "labels": ["etc", "linux"]
+    }));
+    let template = json!(["{{kind}} {{labels}} {{missing}}{{unclosed", " {{ id }} ", 1]);
+    assert_eq!(sink.render(&template, &envelope), json!(["monitor etc,linux {{unclosed", "Test_id", 1]));
+    assert_eq!(sink.get_field(&create_rule_envelope(1), "id"), json!(1));
+    assert_eq!(sink.get_field(&Envelope::new("Db_id", json!({})), "id"), json!("Db_id"));
+    assert_eq!(sink.get_field(&envelope, "event"), envelope.data);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the body uses the template or the event in the webhook format
+fn test_get_body() {
+    let mut cfg = create_test_config();
+    let envelope = create_monitor_envelope("CREATE", json!(["etc"]));
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[0]);
+    let (body, content_type) = sink.get_body(&envelope);
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["text"], "CREATE on /etc/hosts at Hostname");
+    assert_eq!(content_type, "application/json");
+
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[1]);
+    assert_eq!(sink.get_body(&envelope), (formatter::to_cef(&envelope), "text/plain"));
+
+    cfg.webhooks[1].format = Some(String::from("json"));
+    cfg.webhooks[1].payload = Some(serde_yaml::Value::from("{{path}} changed"));
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[1]);
+    assert_eq!(sink.get_body(&envelope), (String::from("/etc/hosts changed"), "text/plain"));
+    cfg.webhooks[1].payload = None;
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[1]);
+    assert_eq!(sink.get_body(&envelope), (envelope.to_json(), "application/json"));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check every filter list set must match the event
+fn test_accepts() {
+    let cfg = create_test_config();
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[0]);
+    assert!(sink.accepts(&create_monitor_envelope("CREATE", json!(["etc"]))));
+    assert!(sink.accepts(&create_monitor_envelope("REMOVE", json!(["linux", "etc"]))));
+    assert!(!sink.accepts(&create_monitor_envelope("WRITE", json!(["etc"]))));
+    assert!(!sink.accepts(&create_monitor_envelope("CREATE", json!(["linux"]))));
+    assert!(!sink.accepts(&create_rule_envelope(1)));
+
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[1]);
+    assert!(sink.accepts(&create_rule_envelope(1)));
+    assert!(!sink.accepts(&create_rule_envelope(2)));
+    assert!(!sink.accepts(&create_monitor_envelope("CREATE", json!(["etc"]))));
+
+    let webhook = WebhookConfig { filter: Default::default(), ..cfg.webhooks[0].clone() };
+    assert!(WebhookSink::new(&cfg, &webhook).accepts(&create_rule_envelope(2)));
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check the request carries the method, headers, credentials and payload
+fn test_write() {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut cfg = create_test_config();
+    cfg.webhooks[0].method = Some(String::from("PUT"));
+    cfg.webhooks[0].url = format!("http://{}/hook", server.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = server.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut head = Vec::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" { break; }
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            head.push(line.trim_end().to_lowercase());
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+        (head, String::from_utf8(body).unwrap())
+    });
+
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[0]);
+    assert_eq!(sink.name(), "chatops");
+    assert_eq!(sink.mode(), appconfig::WEBHOOK_MODE);
+    block_on(sink.write(&create_monitor_envelope("CREATE", json!(["etc"])))).unwrap();
+    let (head, body) = handle.join().unwrap();
+    assert_eq!(head[0], "put /hook http/1.1");
+    assert!(head.contains(&String::from("authorization: bearer test_token")));
+    assert!(head.contains(&String::from("x-source: fim")));
+    assert!(head.contains(&String::from("content-type: application/json")));
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["size"], 10);
+}
+
+// ----------------------------------------------------------------------------
+
+#[test]
+/// Check nothing is sent when the webhook TLS settings can't be applied
+fn test_get_client() {
+    let mut cfg = create_test_config();
+    assert!(get_client(&cfg.webhooks[1]).is_ok());
+    cfg.webhooks[1].insecure = Some(true);
+    assert!(get_client(&cfg.webhooks[1]).is_ok());
+
+    cfg.webhooks[1].insecure = None;
+    cfg.webhooks[1].ca_file = Some(String::from("test/unit/tls/missing.pem"));
+    assert!(get_client(&cfg.webhooks[1]).unwrap_err().starts_with("webhook TLS settings not applied"));
+    let sink = WebhookSink::new(&cfg, &cfg.webhooks[1]);
+    assert!(block_on(sink.write(&create_rule_envelope(1))).unwrap_err().starts_with("webhook TLS settings not applied"));
+}